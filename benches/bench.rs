@@ -4,18 +4,18 @@ use std::str;
 
 fn prepare() -> TSTMap<i32> {
     let mut m = TSTMap::<i32>::new();
-    let mut key: &mut [u8] = &mut [b'b'; 10];
+    let key: &mut [u8] = &mut [b'b'; 10];
     m.insert(str::from_utf8(key).unwrap(), 13);
 
     fn fill(m: &mut TSTMap<i32>, key: &mut [u8], i: usize) {
         if i >= key.len() { return; }
-        for ch in &[b'b',b'a',b'c'] {
+        for ch in b"bac" {
             key[i] = *ch;
             m.insert(str::from_utf8(key).unwrap(), 13);
             fill(m, key, i+1);
         }
     }
-    fill(&mut m, &mut key, 0);
+    fill(&mut m, key, 0);
     m
 }
 
@@ -39,7 +39,7 @@ fn insert_same(c: &mut Criterion) {
 fn get_same(c: &mut Criterion) {
     c.bench_function("get_same", |b| {
         b.iter_with_setup(
-            || prepare(),
+            prepare,
             |m| {
                 std::hint::black_box(m.get("abcabcabca"));
             }
@@ -50,7 +50,7 @@ fn get_same(c: &mut Criterion) {
 fn remove_same(c: &mut Criterion) {
     c.bench_function("remove_same", |b| {
         b.iter_with_setup(
-            || prepare(),
+            prepare,
             |mut m| {
                 std::hint::black_box(m.remove("abcabcabca"));
             }
@@ -61,7 +61,7 @@ fn remove_same(c: &mut Criterion) {
 fn get_none(c: &mut Criterion) {
     c.bench_function("get_none", |b| {
         b.iter_with_setup(
-            || prepare(),
+            prepare,
             |m| {
                 std::hint::black_box(m.get("abcabcabcad"));
             }
@@ -72,7 +72,7 @@ fn get_none(c: &mut Criterion) {
 fn iterate(c: &mut Criterion) {
     c.bench_function("iterate", |b| {
         b.iter_with_setup(
-            || prepare(),
+            prepare,
             |m| {
                 for x in m.iter() {
                     std::hint::black_box(x);
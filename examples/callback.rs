@@ -7,6 +7,7 @@ fn main() {
     m.insert("add", Box::new(move |acc: i32| acc + 1));
     m.insert("sub", Box::new(move |acc: i32| acc - 1));
     m.insert("mul", Box::new(move |acc: i32| acc * acc));
+    #[allow(clippy::eq_op)]
     m.insert("div", Box::new(move |acc: i32| acc / acc));
 
     let mut res = 0;
@@ -6,7 +6,7 @@ static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 extern crate libc;
 //extern {fn __rjem_je_stats_print (write_cb: extern fn (*const libc::c_void, *const libc::c_char), cbopaque: *const libc::c_void, opts: *const libc::c_char);}
 extern "C" fn write_cb (_: *mut libc::c_void, message: *const libc::c_char) {
-    print! ("{}", String::from_utf8_lossy (unsafe {std::ffi::CStr::from_ptr (message as *const i8) .to_bytes()}));}
+    print! ("{}", String::from_utf8_lossy (unsafe {std::ffi::CStr::from_ptr (message) .to_bytes()}));}
 extern crate jemalloc_sys;
 
 use std::env;
@@ -14,13 +14,11 @@ use tst::TSTSet;
 use std::io;
 use std::fs::File;
 use std::io::prelude::*;
-use rand::thread_rng;
-use rand::seq::SliceRandom;
 
 
 fn match_prefix(set: &TSTSet, prefix: &str) {
     println!("match('{}'):", prefix);
-    if prefix.len() > 0 {
+    if !prefix.is_empty() {
         for k in set.prefix_iter(prefix) {
             println!("{}", k);
         }
@@ -31,36 +29,30 @@ fn match_prefix(set: &TSTSet, prefix: &str) {
     }
 }
 
-fn load_dict(path: &str, set: &mut TSTSet) -> io::Result<()> {
+fn load_dict(path: &str) -> io::Result<TSTSet> {
     let mut buffer = String::new();
     let mut file = File::open(path)?;
     file.read_to_string(&mut buffer)?;
 
     let mut v = vec![];
     for line in buffer.split('\n') {
-        if line.len() > 0 {
+        if !line.is_empty() {
             v.push(line);
 
         }
     }
-    let mut rng = thread_rng();
-    v.shuffle(&mut rng);
-
-    for line in v.iter() {
-        set.insert(line);
-    }
-
-    Ok(())
+    // sorted input is inserted median-first, so there is no need to shuffle it
+    v.sort();
+    Ok(TSTSet::from_sorted_iter(v))
 }
 
 fn main() {
     if env::args().count() < 3 {
         panic!("usage: {} <dict> <prefix1> [<prefix2> ...]",
-               &env::args().nth(0).unwrap());
+               &env::args().next().unwrap());
     }
     // read dict
-    let mut set = TSTSet::new();
-    load_dict(&env::args().nth(1).unwrap(), &mut set).unwrap();
+    let set = load_dict(&env::args().nth(1).unwrap()).unwrap();
     //TODO: use flag
     {unsafe {jemalloc_sys::malloc_stats_print (write_cb, std::ptr::null_mut(), std::ptr::null())};}
     // print matched with prefix
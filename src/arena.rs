@@ -0,0 +1,1032 @@
+use std::ops;
+use std::fmt::{self, Debug};
+use std::default::Default;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use crate::map::Entry::{self, *};
+
+// slot 0 is always taken by the root, so it is never a child and could be used as "no link"
+const NIL: u32 = 0;
+const ROOT: u32 = 0;
+
+#[derive(Clone, Copy, Debug)]
+struct ArenaNode {
+    lt: u32,
+    eq: u32,
+    gt: u32,
+    c: char,
+}
+
+#[derive(Clone, Copy)]
+enum Link {
+    Lt,
+    Eq,
+    Gt,
+}
+
+impl ArenaNode {
+    fn new(c: char) -> Self {
+        ArenaNode {
+            lt: NIL,
+            eq: NIL,
+            gt: NIL,
+            c,
+        }
+    }
+
+    fn link(&self, link: Link) -> u32 {
+        match link {
+            Link::Lt => self.lt,
+            Link::Eq => self.eq,
+            Link::Gt => self.gt,
+        }
+    }
+
+    fn link_mut(&mut self, link: Link) -> &mut u32 {
+        match link {
+            Link::Lt => &mut self.lt,
+            Link::Eq => &mut self.eq,
+            Link::Gt => &mut self.gt,
+        }
+    }
+}
+
+///
+/// Symbol table with string keys, implemented using a ternary search
+/// trie, which keeps all its nodes in one contiguous arena (`ArenaTSTMap`).
+///
+/// It has the same behaviour as `TSTMap`, but nodes are linked by `u32` indices
+/// instead of separate `Box` allocations, so big dictionaries have less allocator
+/// overhead and better cache locality. Slots of removed nodes go to a free list
+/// and are reused by the next inserts.
+/// # Examples
+///
+/// ```rust
+/// use tst::ArenaTSTMap;
+///
+/// let mut m = ArenaTSTMap::new();
+///
+/// m.insert("first", 1);
+/// m.insert("second", 2);
+/// m.insert("firstthird", 3);
+/// m.insert("firstsecond", 12);
+/// m.insert("xirst", -13);
+///
+/// assert_eq!(Some(&1), m.get("first"));
+/// assert_eq!(5, m.len());
+/// assert_eq!("firstsecond", m.longest_prefix("firstsecondthird"));
+/// assert_eq!(3, m.prefix_iter("first").count());
+/// assert_eq!(-12, m.wildcard_iter(".irst").fold(0, |sum, (_, val)| sum + val));
+/// ```
+#[derive(Clone)]
+pub struct ArenaTSTMap<Value> {
+    // links and chars are kept apart from values,
+    // value of a node is at the same index as the node itself
+    nodes: Vec<ArenaNode>,
+    values: Vec<Option<Value>>,
+    // head of the free list, free slots are chained by eq link
+    free: u32,
+    size: usize,
+}
+
+impl<Value> ArenaTSTMap<Value> {
+    /// Constructs a new, empty `ArenaTSTMap<Value>`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    /// let mut t: ArenaTSTMap<i64> = ArenaTSTMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Constructs a new, empty `ArenaTSTMap<Value>` with space for at least `capacity` nodes.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    /// let mut t: ArenaTSTMap<i64> = ArenaTSTMap::with_capacity(1024);
+    /// assert!(t.capacity() >= 1024);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut nodes = Vec::with_capacity(capacity + 1);
+        let mut values = Vec::with_capacity(capacity + 1);
+        nodes.push(ArenaNode::new(Default::default()));
+        values.push(None);
+        ArenaTSTMap {
+            nodes,
+            values,
+            free: NIL,
+            size: 0,
+        }
+    }
+
+    /// Returns the number of elements in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// assert_eq!(0, m.len());
+    /// m.insert("ab", 2);
+    /// m.insert("x", 1);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn len(&self) -> usize { self.size }
+
+    /// Returns true if the `ArenaTSTMap` contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// assert!(m.is_empty());
+    ///
+    /// m.insert("abc", 1);
+    /// assert!(!m.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.size == 0 }
+
+    /// Returns the number of nodes the arena can hold without reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert!(m.capacity() >= 3);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity() - 1
+    }
+
+    /// Shrinks the capacity of the arena as much as possible.
+    /// Free slots stay in the arena and are reused by the next inserts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::with_capacity(100);
+    /// m.insert("abc", 1);
+    /// m.shrink_to_fit();
+    /// assert_eq!(Some(&1), m.get("abc"));
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.values.shrink_to_fit();
+    }
+
+    /// Clears the `ArenaTSTMap`, all the slots are released.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 100);
+    /// m.clear();
+    ///
+    /// assert!(m.is_empty());
+    /// assert_eq!(None, m.get("abc"));
+    /// ```
+    pub fn clear(&mut self) {
+        self.nodes.truncate(1);
+        self.values.truncate(1);
+        self.nodes[ROOT as usize] = ArenaNode::new(Default::default());
+        self.values[ROOT as usize] = None;
+        self.free = NIL;
+        self.size = 0;
+    }
+
+    /// Inserts an element at key `key` with value `val`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if number of nodes doesn't fit into `u32`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("SOmeWOrd", 2);
+    /// m.insert("SOmeOtherWOrd", 4);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn insert(&mut self, key: &str, value: Value) -> Option<Value> {
+        match self.entry(key) {
+            Occupied(mut entry) => Some(entry.insert(value)),
+            Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Gets the given `key`'s corresponding entry in the `ArenaTSTMap` for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut count: ArenaTSTMap<usize> = ArenaTSTMap::new();
+    ///
+    /// for x in vec!["abc","bad","abd","cdddd","abc","bade"] {
+    ///     *count.entry(x).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(2, count["abc"]);
+    /// assert_eq!(1, count["abd"]);
+    /// ```
+    pub fn entry(&mut self, key: &str) -> Entry<'_, Value> {
+        let cur = self.insert_node(key);
+        Entry::new(&mut self.values[cur as usize], &mut self.size)
+    }
+
+    /// Removes a `key` from the `ArenaTSTMap`, returning the value at the key if the key
+    /// was previously in the map. Slots of the nodes which are not needed anymore
+    /// go to the free list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("abc", 100);
+    /// assert_eq!(Some(100), m.remove("abc"));
+    /// assert_eq!(None, m.remove("abc"));
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let mut path = Vec::with_capacity(key.len());
+        let mut last = ROOT;
+
+        for ch in key.chars() {
+            let mut parent = last;
+            let mut link = Link::Eq;
+            loop {
+                let cur = self.node(parent).link(link);
+                if cur == NIL {
+                    return None;
+                }
+                path.push((parent, link));
+                match ch.cmp(&self.node(cur).c) {
+                    Ordering::Less => link = Link::Lt,
+                    Ordering::Greater => link = Link::Gt,
+                    Ordering::Equal => {
+                        last = cur;
+                        break;
+                    },
+                }
+                parent = cur;
+            }
+        }
+        let ret = self.values[last as usize].take();
+        // cut the tail
+        if ret.is_some() {
+            self.size -= 1;
+            while let Some((parent, link)) = path.pop() {
+                let cur = self.node(parent).link(link);
+                if !self.is_leaf(cur) {
+                    break;
+                }
+                *self.nodes[parent as usize].link_mut(link) = NIL;
+                self.release(cur);
+            }
+        }
+        ret
+    }
+
+    /// Returns a reference to the value corresponding to the `key` or None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("first", 13);
+    /// assert_eq!(Some(&13), m.get("first"));
+    /// assert_eq!(None, m.get("second"));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self.search(key.chars()) {
+            None => None,
+            Some(idx) => self.values[idx as usize].as_ref(),
+        }
+    }
+
+    /// Returns a mutable reference to the value corresponding to the `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("first", 13);
+    /// if let Some(x) = m.get_mut("first") {
+    ///     *x = -13;
+    /// }
+    /// assert_eq!(-13, m["first"]);
+    /// ```
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        match self.search(key.chars()) {
+            None => None,
+            Some(idx) => self.values[idx as usize].as_mut(),
+        }
+    }
+
+    /// Returns true if the `ArenaTSTMap` contains a value for the specified `key`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert!(!m.contains_key("ab"));
+    /// assert!(m.contains_key("abc"))
+    /// ```
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Method returns longest prefix `pref` in the `ArenaTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// m.insert("abce", 1);
+    /// m.insert("abcdef", 1);
+    ///
+    /// assert_eq!("abcd", m.longest_prefix("abcde"));
+    /// ```
+    pub fn longest_prefix<'x>(&self, pref: &'x str) -> &'x str {
+        let mut cur = self.node(ROOT).eq;
+        let mut length = 0;
+        let mut i = 0;
+        for ch in pref.chars() {
+            loop {
+                if cur == NIL {
+                    return &pref[..length];
+                }
+                let node = self.node(cur);
+                match ch.cmp(&node.c) {
+                    Ordering::Less => cur = node.lt,
+                    Ordering::Greater => cur = node.gt,
+                    Ordering::Equal => {
+                        i += ch.len_utf8();
+                        if self.values[cur as usize].is_some() { length = i; }
+                        cur = node.eq;
+                        break;
+                    },
+                }
+            }
+        }
+        &pref[..length]
+    }
+
+    /// An iterator returning all nodes matching wildcard pattern `pat`.
+    /// Iterator element type is (String, V)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("cc", 3);
+    ///
+    /// assert_eq!(2, m.wildcard_iter(".").count());
+    /// ```
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<'_, Value> {
+        WildCardIter {
+            nodes: &self.nodes,
+            values: &self.values,
+            iter: WildCardTraverse::new(self, pat),
+        }
+    }
+
+    /// Method returns iterator over all values with common prefix `pref` in the `ArenaTSTMap`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// m.insert("zxd", 1);
+    /// m.insert("add", 1);
+    ///
+    /// for (key, value) in m.prefix_iter("abc") {
+    ///     println!("{}: {}", key, value);
+    /// }
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_, Value> {
+        let node = self.search(pref.chars());
+        Iter {
+            nodes: &self.nodes,
+            values: &self.values,
+            iter: Traverse::with_prefix(self, node, pref, 0, self.len(), true),
+        }
+    }
+
+    /// Method returns mutable iterator over all values with common prefix `pref` in the `ArenaTSTMap`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcdef", 1);
+    /// m.insert("add", 1);
+    ///
+    /// for (key, value) in m.prefix_iter_mut("abc") {
+    ///     *value += 100;
+    /// }
+    /// assert_eq!(101, m["abc"]);
+    /// assert_eq!(101, m["abcdef"]);
+    /// assert_eq!(1, m["add"]);
+    /// ```
+    pub fn prefix_iter_mut(&mut self, pref: &str) -> IterMut<'_, Value> {
+        let node = self.search(pref.chars());
+        let iter = Traverse::with_prefix(self, node, pref, 0, self.len(), true);
+        IterMut::new(self, iter)
+    }
+
+    /// Gets an iterator over the entries of the `ArenaTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("bbc", 2);
+    /// m.insert("cccda", 3);
+    ///
+    /// let (first_key, first_value) = m.iter().next().unwrap();
+    /// assert_eq!((first_key, *first_value), ("abc".to_string(), 1));
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Value> {
+        Iter {
+            nodes: &self.nodes,
+            values: &self.values,
+            iter: Traverse::with_prefix(self, Some(ROOT), "", self.len(), self.len(), true),
+        }
+    }
+
+    /// Gets a mutable iterator over the entries of the `ArenaTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    ///
+    /// for (key, value) in m.iter_mut() {
+    ///     if key != "a" {
+    ///         *value += 10;
+    ///     }
+    /// }
+    /// assert_eq!(1, m["a"]);
+    /// assert_eq!(12, m["b"]);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, Value> {
+        let iter = Traverse::with_prefix(self, Some(ROOT), "", self.len(), self.len(), true);
+        IterMut::new(self, iter)
+    }
+
+    /// An iterator visiting all keys in alphabetical order.
+    /// Iterator element type is String
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// assert_eq!(vec!["a", "b"], m.keys().collect::<Vec<String>>());
+    /// ```
+    pub fn keys(&self) -> KeysIter<'_, Value> {
+        KeysIter { iter: self.iter() }
+    }
+
+    /// An iterator visiting all values in order of their keys.
+    /// Iterator element type is &V
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// assert_eq!(vec![&1, &2], m.values().collect::<Vec<&i32>>());
+    /// ```
+    pub fn values(&self) -> ValuesIter<'_, Value> {
+        ValuesIter {
+            iter: Iter {
+                nodes: &self.nodes,
+                values: &self.values,
+                iter: Traverse::with_prefix(self, Some(ROOT), "", self.len(), self.len(), false),
+            },
+        }
+    }
+
+    fn node(&self, idx: u32) -> &ArenaNode {
+        &self.nodes[idx as usize]
+    }
+
+    fn has_value(&self, idx: u32) -> bool {
+        self.values[idx as usize].is_some()
+    }
+
+    fn is_leaf(&self, idx: u32) -> bool {
+        let node = self.node(idx);
+        node.lt == NIL && node.eq == NIL && node.gt == NIL && !self.has_value(idx)
+    }
+
+    fn alloc(&mut self, c: char) -> u32 {
+        if self.free != NIL {
+            let idx = self.free;
+            self.free = self.node(idx).eq;
+            self.nodes[idx as usize] = ArenaNode::new(c);
+            idx
+        } else {
+            let idx = self.nodes.len();
+            assert!(idx < u32::MAX as usize, "ArenaTSTMap is full");
+            self.nodes.push(ArenaNode::new(c));
+            self.values.push(None);
+            idx as u32
+        }
+    }
+
+    fn release(&mut self, idx: u32) {
+        let node = &mut self.nodes[idx as usize];
+        *node = ArenaNode::new(Default::default());
+        node.eq = self.free;
+        self.free = idx;
+    }
+
+    fn search<I: IntoIterator<Item = char>>(&self, key: I) -> Option<u32> {
+        let mut cur = self.node(ROOT).eq;
+        let mut last = ROOT;
+
+        for ch in key {
+            loop {
+                if cur == NIL {
+                    return None;
+                }
+                let node = self.node(cur);
+                match ch.cmp(&node.c) {
+                    Ordering::Less => cur = node.lt,
+                    Ordering::Greater => cur = node.gt,
+                    Ordering::Equal => {
+                        last = cur;
+                        cur = node.eq;
+                        break;
+                    },
+                }
+            }
+        }
+        Some(last)
+    }
+
+    fn insert_node(&mut self, key: &str) -> u32 {
+        let mut last = ROOT;
+
+        for ch in key.chars() {
+            let mut parent = last;
+            let mut link = Link::Eq;
+            loop {
+                let cur = self.node(parent).link(link);
+                if cur == NIL {
+                    last = self.alloc(ch);
+                    *self.nodes[parent as usize].link_mut(link) = last;
+                    break;
+                }
+                match ch.cmp(&self.node(cur).c) {
+                    Ordering::Less => link = Link::Lt,
+                    Ordering::Greater => link = Link::Gt,
+                    Ordering::Equal => {
+                        last = cur;
+                        break;
+                    },
+                }
+                parent = cur;
+            }
+        }
+        last
+    }
+}
+
+impl<Value> IntoIterator for ArenaTSTMap<Value> {
+    type Item = (String, Value);
+    type IntoIter = IntoIter<Value>;
+
+    /// Creates a consuming iterator, that is, one that moves each key-value
+    /// pair out of the `ArenaTSTMap` in order of keys. The `ArenaTSTMap` cannot be used after
+    /// calling this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    ///
+    /// let mut m = ArenaTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    ///
+    /// let vec: Vec<(String, isize)> = m.into_iter().collect();
+    /// ```
+    fn into_iter(self) -> IntoIter<Value> {
+        let iter = Traverse::with_prefix(&self, Some(ROOT), "", self.len(), self.len(), true);
+        IntoIter {
+            map: self,
+            iter,
+        }
+    }
+}
+
+impl<'x, Value> FromIterator<(&'x str, Value)> for ArenaTSTMap<Value> {
+    fn from_iter<I: IntoIterator<Item = (&'x str, Value)>>(iter: I) -> ArenaTSTMap<Value> {
+        let mut m = ArenaTSTMap::new();
+        for item in iter {
+            m.insert(item.0, item.1);
+        }
+        m
+    }
+}
+
+impl<'x, Value> Extend<(&'x str, Value)> for ArenaTSTMap<Value> {
+    #[inline]
+    fn extend<I: IntoIterator<Item=(&'x str, Value)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<Value> ops::Index<&str> for ArenaTSTMap<Value> {
+    type Output = Value;
+    #[inline]
+    fn index(&self, idx: &str) -> &Value {
+        self.get(idx).expect("no entry found for key")
+    }
+}
+
+impl<Value> ops::IndexMut<&str> for ArenaTSTMap<Value> {
+    #[inline]
+    fn index_mut(&mut self, idx: &str) -> &mut Value {
+        self.get_mut(idx).expect("no entry found for key")
+    }
+}
+
+impl<Value: Debug> Debug for ArenaTSTMap<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<Value> Default for ArenaTSTMap<Value> {
+    /// Constructs a new, empty `ArenaTSTMap<Value>`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ArenaTSTMap;
+    /// let mut t: ArenaTSTMap<i64> = Default::default();
+    /// ```
+    fn default() -> Self {
+        ArenaTSTMap::with_capacity(0)
+    }
+}
+
+//
+// iterators section
+//
+
+#[derive(Clone)]
+enum TraverseEntry {
+    Node(String, u32),
+    Value(String, u32),
+}
+
+// traversal over indices, it doesn't borrow the arena,
+// so the same code serves shared, mutable and consuming iterators
+#[derive(Clone)]
+struct Traverse {
+    stack: Vec<TraverseEntry>,
+    min_size: usize,
+    max_size: usize,
+    keys: bool,
+}
+
+impl Traverse {
+    fn with_prefix<Value>(map: &ArenaTSTMap<Value>, node: Option<u32>, prefix: &str,
+                          min: usize, max: usize, keys: bool) -> Self {
+        let mut iter = Traverse {
+            stack: vec![],
+            min_size: min,
+            max_size: max,
+            keys,
+        };
+        if let Some(idx) = node {
+            let prefix = if keys { prefix.to_string() } else { String::new() };
+            if map.node(idx).eq != NIL {
+                iter.stack.push(TraverseEntry::Node(prefix.clone(), map.node(idx).eq));
+            }
+            if map.has_value(idx) {
+                iter.min_size = iter.min_size.max(1);
+                iter.stack.push(TraverseEntry::Value(prefix, idx));
+            }
+        }
+        iter
+    }
+
+    fn next<F: Fn(u32) -> bool>(&mut self, nodes: &[ArenaNode], has_value: F) -> Option<(String, u32)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(prefix, idx) => {
+                    self.min_size = self.min_size.saturating_sub(1);
+                    self.max_size -= 1;
+                    return Some((prefix, idx));
+                }
+                TraverseEntry::Node(prefix, idx) => {
+                    let cur = &nodes[idx as usize];
+                    if cur.gt != NIL {
+                        self.stack.push(TraverseEntry::Node(prefix.clone(), cur.gt));
+                    }
+                    let has_value = has_value(idx);
+                    if cur.eq != NIL || has_value {
+                        let mut new_prefix = String::new();
+                        if self.keys {
+                            new_prefix.reserve(prefix.len() + cur.c.len_utf8());
+                            new_prefix.push_str(&prefix);
+                            new_prefix.push(cur.c);
+                        }
+                        if cur.eq != NIL {
+                            self.stack.push(TraverseEntry::Node(new_prefix.clone(), cur.eq));
+                        }
+                        if has_value {
+                            self.stack.push(TraverseEntry::Value(new_prefix, idx));
+                        }
+                    }
+                    if cur.lt != NIL {
+                        self.stack.push(TraverseEntry::Node(prefix, cur.lt));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.min_size, Some(self.max_size))
+    }
+}
+
+#[derive(Clone)]
+struct WildCardTraverse {
+    stack: Vec<(String, u32, usize)>,
+    max_size: usize,
+    pat: Vec<char>,
+}
+
+impl WildCardTraverse {
+    fn new<Value>(map: &ArenaTSTMap<Value>, pat: &str) -> Self {
+        let mut iter = WildCardTraverse {
+            stack: vec![],
+            max_size: map.len(),
+            pat: pat.chars().collect(),
+        };
+        // an entry with index == pat.len() holds a matched value
+        if iter.pat.is_empty() {
+            if map.has_value(ROOT) {
+                iter.stack.push(("".to_string(), ROOT, 0));
+            }
+        } else if map.node(ROOT).eq != NIL {
+            iter.stack.push(("".to_string(), map.node(ROOT).eq, 0));
+        }
+        iter
+    }
+
+    fn next<Value>(&mut self, nodes: &[ArenaNode], values: &[Option<Value>]) -> Option<(String, u32)> {
+        while let Some((prefix, idx, pos)) = self.stack.pop() {
+            if pos == self.pat.len() {
+                self.max_size -= 1;
+                return Some((prefix, idx));
+            }
+            let cur = &nodes[idx as usize];
+            let ch = self.pat[pos];
+            if (ch == '.' || ch > cur.c) && cur.gt != NIL {
+                self.stack.push((prefix.clone(), cur.gt, pos));
+            }
+            if ch == '.' || ch == cur.c {
+                let mut new_prefix = String::with_capacity(prefix.len() + cur.c.len_utf8());
+                new_prefix.push_str(&prefix);
+                new_prefix.push(cur.c);
+                if pos + 1 < self.pat.len() && cur.eq != NIL {
+                    self.stack.push((new_prefix, cur.eq, pos + 1));
+                } else if pos + 1 == self.pat.len() && values[idx as usize].is_some() {
+                    self.stack.push((new_prefix, idx, pos + 1));
+                }
+            }
+            if (ch == '.' || ch < cur.c) && cur.lt != NIL {
+                self.stack.push((prefix, cur.lt, pos));
+            }
+        }
+        None
+    }
+}
+
+/// `ArenaTSTMap` iterator.
+#[derive(Clone)]
+pub struct Iter<'x, Value: 'x> {
+    nodes: &'x [ArenaNode],
+    values: &'x [Option<Value>],
+    iter: Traverse,
+}
+
+impl<'x, Value> Iterator for Iter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        let values = self.values;
+        self.iter.next(self.nodes, |idx| values[idx as usize].is_some())
+            .map(|(key, idx)| (key, values[idx as usize].as_ref().unwrap()))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// `ArenaTSTMap` mutable iterator.
+pub struct IterMut<'x, Value: 'x> {
+    nodes: &'x [ArenaNode],
+    // references to the values under the start node only, sorted by the index of their node,
+    // each one is moved out when the traversal gets to its node
+    values: Vec<(u32, Option<&'x mut Value>)>,
+    iter: Traverse,
+}
+
+impl<'x, Value> IterMut<'x, Value> {
+    fn new(map: &'x mut ArenaTSTMap<Value>, iter: Traverse) -> Self {
+        // a keyless run of the same traversal finds the indices without building any key
+        let mut indices = Vec::with_capacity(iter.size_hint().0);
+        let mut keyless = Traverse { keys: false, ..iter.clone() };
+        while let Some((_, idx)) = keyless.next(&map.nodes, |idx| map.has_value(idx)) {
+            indices.push(idx);
+        }
+        indices.sort_unstable();
+
+        let mut values = Vec::with_capacity(indices.len());
+        let mut rest = &mut map.values[..];
+        let mut offset = 0;
+        for idx in indices {
+            let (value, tail) = rest[(idx - offset) as usize..].split_first_mut().unwrap();
+            values.push((idx, value.as_mut()));
+            rest = tail;
+            offset = idx + 1;
+        }
+        IterMut {
+            nodes: &map.nodes,
+            values,
+            iter,
+        }
+    }
+}
+
+impl<'x, Value> Iterator for IterMut<'x, Value> {
+    type Item = (String, &'x mut Value);
+    fn next(&mut self) -> Option<(String, &'x mut Value)> {
+        let values = &mut self.values;
+        let find = |values: &[(u32, Option<&mut Value>)], idx| values.binary_search_by_key(&idx, |&(i, _)| i);
+        // the traversal checks a node before it yields its index, and it yields every index once
+        let (key, idx) = self.iter.next(self.nodes, |idx| find(values, idx).is_ok())?;
+        let pos = find(values, idx).unwrap();
+        Some((key, values[pos].1.take().unwrap()))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// `ArenaTSTMap` keys iterator
+#[derive(Clone)]
+pub struct KeysIter<'x, Value: 'x> {
+    iter: Iter<'x, Value>,
+}
+
+impl<'x, Value: 'x> Iterator for KeysIter<'x, Value> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> { self.iter.next().map(|(k, _)| k) }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+/// `ArenaTSTMap` values iterator
+#[derive(Clone)]
+pub struct ValuesIter<'x, Value: 'x> {
+    iter: Iter<'x, Value>,
+}
+
+impl<'x, Value: 'x> Iterator for ValuesIter<'x, Value> {
+    type Item = &'x Value;
+    fn next(&mut self) -> Option<&'x Value> { self.iter.next().map(|(_, v)| v) }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+/// `ArenaTSTMap` wild-card iterator.
+#[derive(Clone)]
+pub struct WildCardIter<'x, Value: 'x> {
+    nodes: &'x [ArenaNode],
+    values: &'x [Option<Value>],
+    iter: WildCardTraverse,
+}
+
+impl<'x, Value> Iterator for WildCardIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        let values = self.values;
+        self.iter.next(self.nodes, values)
+            .map(|(key, idx)| (key, values[idx as usize].as_ref().unwrap()))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (0, Some(self.iter.max_size)) }
+}
+
+/// `ArenaTSTMap` consuming iterator
+pub struct IntoIter<Value> {
+    map: ArenaTSTMap<Value>,
+    iter: Traverse,
+}
+
+impl<Value> Iterator for IntoIter<Value> {
+    type Item = (String, Value);
+
+    fn next(&mut self) -> Option<(String, Value)> {
+        let map = &mut self.map;
+        let values = &map.values;
+        self.iter.next(&map.nodes, |idx| values[idx as usize].is_some())
+            .map(|(key, idx)| (key, map.values[idx as usize].take().unwrap()))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+impl<Value> ExactSizeIterator for IntoIter<Value> {
+    fn len(&self) -> usize { self.iter.max_size }
+}
+
+// internal tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn remove_releases_slots() {
+        let mut m = ArenaTSTMap::new();
+        m.insert("BY", 1);
+        m.insert("BYGONE", 3);
+        m.insert("BYE", 2);
+        assert_eq!(8, m.nodes.len());
+
+        m.remove("BYGONE");
+        m.remove("BY");
+        m.remove("BYE");
+        assert_eq!(NIL, m.nodes[ROOT as usize].eq);
+
+        m.insert("XYZ", 1);
+        m.insert("X", 1);
+        assert_eq!(8, m.nodes.len());
+    }
+}
@@ -0,0 +1,247 @@
+use std::io::{self, Write, BufWriter};
+use std::fmt;
+use std::error::Error;
+use std::marker::PhantomData;
+use super::snapshot::ValueCodec;
+use super::mapped::{self, HEADER_LEN};
+
+/// Error of `TSTBuilder`.
+#[derive(Debug)]
+pub enum BuildError {
+    /// Underlying writer failed.
+    Io(io::Error),
+    /// Key isn't greater than the previous one, nothing was written for it.
+    OutOfOrder {
+        /// The last accepted key.
+        previous: String,
+        /// The rejected key.
+        key: String,
+    },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::Io(ref err) => write!(f, "i/o error: {}", err),
+            BuildError::OutOfOrder { ref previous, ref key } =>
+                write!(f, "key {:?} doesn't go after {:?}", key, previous),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            BuildError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BuildError {
+    fn from(err: io::Error) -> Self {
+        BuildError::Io(err)
+    }
+}
+
+// node on the path of the last key or one of its left siblings,
+// its eq subtree is already written
+struct Pending {
+    c: char,
+    eq: u32,
+    value: Option<Vec<u8>>,
+}
+
+///
+/// Streaming builder of a dictionary in the format of [`MappedTSTMap`](crate::MappedTSTMap)
+/// from keys in sorted order.
+///
+/// Records are written as soon as no later key can change them, the builder keeps
+/// only nodes of the last key's path and their left siblings, so memory use depends
+/// on key length and the alphabet, not on the number of keys. When all the keys
+/// under a prefix are seen, siblings of the next char are written as a balanced tree.
+/// # Examples
+///
+/// ```rust
+/// use tst::{TSTBuilder, MappedTSTMap, ValueCodec};
+///
+/// let mut builder = TSTBuilder::new(Vec::new()).unwrap();
+/// builder.insert("abc", 1u32).unwrap();
+/// builder.insert("abd", 2).unwrap();
+/// builder.insert("xyz", 3).unwrap();
+/// assert!(builder.insert("abe", 4).is_err());
+///
+/// let bytes = builder.finish().unwrap();
+/// let m = MappedTSTMap::new(&bytes[..]).unwrap();
+/// assert_eq!(3, m.len());
+/// assert_eq!(Some(2), m.get("abd").and_then(u32::decode));
+/// ```
+pub struct TSTBuilder<Value, W: Write> {
+    out: BufWriter<W>,
+    // offset of the next record in 4-byte words
+    offset: u32,
+    // levels[i] keeps nodes for the char i of the last key, the last node is on the path
+    levels: Vec<Vec<Pending>>,
+    root_value: Option<Vec<u8>>,
+    last: Option<String>,
+    len: u64,
+    buf: Vec<u8>,
+    _marker: PhantomData<Value>,
+}
+
+impl<Value: ValueCodec, W: Write> TSTBuilder<Value, W> {
+    /// Creates a builder, which writes into `writer`, and writes the header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBuilder;
+    ///
+    /// let builder: TSTBuilder<u32, _> = TSTBuilder::new(Vec::new()).unwrap();
+    /// ```
+    pub fn new(writer: W) -> io::Result<Self> {
+        let mut out = BufWriter::new(writer);
+        mapped::write_header(&mut out)?;
+        Ok(TSTBuilder {
+            out,
+            offset: (HEADER_LEN / 4) as u32,
+            levels: Vec::new(),
+            root_value: None,
+            last: None,
+            len: 0,
+            buf: Vec::new(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Returns the number of inserted keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBuilder;
+    ///
+    /// let mut builder = TSTBuilder::new(Vec::new()).unwrap();
+    /// builder.insert("a", ()).unwrap();
+    /// assert_eq!(1, builder.len());
+    /// ```
+    pub fn len(&self) -> u64 { self.len }
+
+    /// Returns true if no keys were inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBuilder;
+    ///
+    /// let builder: TSTBuilder<(), _> = TSTBuilder::new(Vec::new()).unwrap();
+    /// assert!(builder.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Adds `key` with `value`. Keys must go in strictly increasing order, otherwise
+    /// `BuildError::OutOfOrder` is returned and the builder stays as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTBuilder, BuildError};
+    ///
+    /// let mut builder = TSTBuilder::new(Vec::new()).unwrap();
+    /// builder.insert("b", 1).unwrap();
+    /// assert!(matches!(builder.insert("a", 2), Err(BuildError::OutOfOrder { .. })));
+    /// assert!(matches!(builder.insert("b", 2), Err(BuildError::OutOfOrder { .. })));
+    /// builder.insert("c", 3).unwrap();
+    /// assert_eq!(2, builder.len());
+    /// ```
+    pub fn insert(&mut self, key: &str, value: Value) -> Result<(), BuildError> {
+        let common = match self.last {
+            Some(ref last) if key <= last.as_str() => {
+                return Err(BuildError::OutOfOrder { previous: last.clone(), key: key.to_string() });
+            }
+            Some(ref last) => last.chars().zip(key.chars()).take_while(|(a, b)| a == b).count(),
+            None => 0,
+        };
+        mapped::encode_value(&value, &mut self.buf)?;
+        let value = Some(self.buf.clone());
+
+        if key.is_empty() {
+            // the empty key can be only the first one
+            self.root_value = value;
+        } else {
+            // everything deeper than the common prefix is complete now
+            self.close_levels(common + 1)?;
+            let mut chars = key.chars().skip(common);
+            let ch = chars.next().unwrap();
+            match self.levels.get_mut(common) {
+                Some(level) => level.push(Pending { c: ch, eq: 0, value: None }),
+                None => self.levels.push(vec![Pending { c: ch, eq: 0, value: None }]),
+            }
+            for ch in chars {
+                self.levels.push(vec![Pending { c: ch, eq: 0, value: None }]);
+            }
+            self.levels.last_mut().unwrap().last_mut().unwrap().value = value;
+        }
+
+        match self.last {
+            Some(ref mut last) => {
+                last.clear();
+                last.push_str(key);
+            }
+            None => self.last = Some(key.to_string()),
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Writes the rest of the trie and the footer, returns the writer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTBuilder, MappedTSTMap};
+    ///
+    /// let mut builder = TSTBuilder::new(Vec::new()).unwrap();
+    /// builder.insert("a", ()).unwrap();
+    /// let bytes = builder.finish().unwrap();
+    ///
+    /// assert!(MappedTSTMap::new(bytes).unwrap().contains_key("a"));
+    /// ```
+    pub fn finish(mut self) -> Result<W, BuildError> {
+        self.close_levels(1)?;
+        let top = match self.levels.pop() {
+            Some(level) => self.write_tree(&level)?,
+            None => 0,
+        };
+        let root = self.offset;
+        let value = self.root_value.take();
+        mapped::write_record(&mut self.out, self.offset, '\0', [0, top, 0], value.as_deref())?;
+        mapped::write_footer(&mut self.out, root, self.len)?;
+        self.out.into_inner().map_err(|err| BuildError::Io(err.into_error()))
+    }
+
+    // writes levels from `depth` and deeper, links each written level to its parent node
+    fn close_levels(&mut self, depth: usize) -> io::Result<()> {
+        while self.levels.len() > depth {
+            let level = self.levels.pop().unwrap();
+            let offset = self.write_tree(&level)?;
+            self.levels.last_mut().unwrap().last_mut().unwrap().eq = offset;
+        }
+        Ok(())
+    }
+
+    // writes sorted siblings as a balanced tree in post-order, returns offset of its root
+    fn write_tree(&mut self, nodes: &[Pending]) -> io::Result<u32> {
+        if nodes.is_empty() {
+            return Ok(0);
+        }
+        // recursion depth is log2 of the number of siblings, so it's safe
+        let mid = nodes.len() / 2;
+        let lt = self.write_tree(&nodes[..mid])?;
+        let gt = self.write_tree(&nodes[mid + 1..])?;
+        let node = &nodes[mid];
+        let written = self.offset;
+        self.offset = mapped::write_record(&mut self.out, self.offset, node.c, [lt, node.eq, gt], node.value.as_deref())?;
+        Ok(written)
+    }
+}
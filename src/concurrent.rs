@@ -0,0 +1,285 @@
+use std::fmt::{self, Debug};
+use std::iter::FromIterator;
+use std::sync::{Arc, Mutex, PoisonError};
+use arc_swap::ArcSwap;
+use super::persistent::{PersistentTSTMap, IntoIter};
+
+///
+/// Read-mostly `TSTMap`, which many threads can read and update through a shared reference.
+///
+/// The map keeps its current version as a `PersistentTSTMap` behind an atomically swapped
+/// `Arc`. Readers load it without any lock, so they never wait, not even for a writer
+/// in the middle of an update. Writers build the next version off to the side and swap it in
+/// with one atomic store. Writers are serialized by a mutex, so concurrent updates are never lost.
+///
+/// A snapshot stays the same whatever writers do later, `prefix_iter` walks one.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use std::thread;
+/// use tst::ConcurrentTSTMap;
+///
+/// let m = Arc::new(ConcurrentTSTMap::new());
+/// m.insert("first", 1);
+///
+/// let writer = {
+///     let m = m.clone();
+///     thread::spawn(move || { m.insert("second", 2); })
+/// };
+/// assert_eq!(Some(1), m.get("first").map(|v| *v));
+/// writer.join().unwrap();
+/// assert_eq!(2, m.len());
+/// ```
+pub struct ConcurrentTSTMap<Value> {
+    current: ArcSwap<PersistentTSTMap<Value>>,
+    writer: Mutex<()>,
+}
+
+impl<Value> ConcurrentTSTMap<Value> {
+    /// Constructs a new, empty `ConcurrentTSTMap<Value>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    /// let m: ConcurrentTSTMap<i64> = ConcurrentTSTMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the current version of the map. Later updates don't change it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// m.insert("a", 1);
+    /// let snapshot = m.snapshot();
+    /// m.insert("b", 2);
+    ///
+    /// assert_eq!(1, snapshot.len());
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn snapshot(&self) -> PersistentTSTMap<Value> {
+        PersistentTSTMap::clone(&self.current.load())
+    }
+
+    /// Returns the number of elements in the current version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// m.insert("ab", 2);
+    /// m.insert("x", 1);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.current.load().len()
+    }
+
+    /// Returns true if the current version contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// assert!(m.is_empty());
+    /// m.insert("ab", 2);
+    /// assert!(!m.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts `value` at `key` and returns the previous value of it, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// assert_eq!(None, m.insert("a", 1));
+    /// assert_eq!(Some(1), m.insert("a", 2).map(|v| *v));
+    /// assert_eq!(Some(2), m.get("a").map(|v| *v));
+    /// ```
+    pub fn insert(&self, key: &str, value: Value) -> Option<Arc<Value>> {
+        let mut old = None;
+        self.update(|m| {
+            old = m.find(key).cloned();
+            m.insert(key, value)
+        });
+        old
+    }
+
+    /// Removes `key` and returns its value, if it was there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// m.insert("a", 1);
+    /// assert_eq!(Some(1), m.remove("a").map(|v| *v));
+    /// assert_eq!(None, m.remove("a"));
+    /// ```
+    pub fn remove(&self, key: &str) -> Option<Arc<Value>> {
+        let mut old = None;
+        self.update(|m| {
+            old = m.find(key).cloned();
+            if old.is_some() { m.remove(key) } else { m.clone() }
+        });
+        old
+    }
+
+    /// Replaces the current version with the one made by `f`, as one update.
+    /// Readers see either the version before or the one after, other writers wait.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// m.insert("old", 0);
+    /// m.update(|v| v.remove("old").insert("a", 1).insert("b", 2));
+    /// assert_eq!(2, m.len());
+    /// assert!(!m.contains_key("old"));
+    /// ```
+    pub fn update<F>(&self, f: F)
+    where F: FnOnce(&PersistentTSTMap<Value>) -> PersistentTSTMap<Value> {
+        // nothing is changed before the swap, so a panic of another writer leaves no mess
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let next = f(&self.current.load());
+        // readers, which still hold the previous version, free its nodes when they are done
+        drop(self.current.swap(Arc::new(next)));
+    }
+
+    /// Returns the value of `key` in the current version, it stays alive while it's held.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// m.insert("first", 13);
+    /// assert_eq!(Some(13), m.get("first").map(|v| *v));
+    /// assert_eq!(None, m.get("second"));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<Arc<Value>> {
+        self.current.load().find(key).cloned()
+    }
+
+    /// Returns true if the current version contains a value for the specified `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert!(m.contains_key("abc"));
+    /// assert!(!m.contains_key("ab"));
+    /// ```
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.current.load().contains_key(key)
+    }
+
+    /// Returns the longest prefix of `pref`, which is a key in the current version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// assert_eq!("abcd", m.longest_prefix("abcde"));
+    /// ```
+    pub fn longest_prefix<'x>(&self, pref: &'x str) -> &'x str {
+        self.current.load().longest_prefix(pref)
+    }
+
+    /// Creates an iterator over the keys starting with `pref` in the current version,
+    /// updates made while iterating don't show up in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.insert("b", 3);
+    ///
+    /// let mut keys = vec![];
+    /// for (key, _) in m.prefix_iter("ab") {
+    ///     m.remove(&key);
+    ///     keys.push(key);
+    /// }
+    /// assert_eq!(vec!["abc", "abd"], keys);
+    /// assert_eq!(1, m.len());
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> IntoIter<Value> {
+        self.snapshot().into_prefix_iter(pref)
+    }
+
+    /// Creates an iterator over the entries of the current version in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::ConcurrentTSTMap;
+    ///
+    /// let m = ConcurrentTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    /// let keys: Vec<String> = m.iter().map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["a", "b"], keys);
+    /// ```
+    pub fn iter(&self) -> IntoIter<Value> {
+        self.snapshot().into_iter()
+    }
+}
+
+impl<Value> From<PersistentTSTMap<Value>> for ConcurrentTSTMap<Value> {
+    fn from(map: PersistentTSTMap<Value>) -> Self {
+        ConcurrentTSTMap {
+            current: ArcSwap::from_pointee(map),
+            writer: Mutex::new(()),
+        }
+    }
+}
+
+impl<'x, Value> FromIterator<(&'x str, Value)> for ConcurrentTSTMap<Value> {
+    fn from_iter<I: IntoIterator<Item = (&'x str, Value)>>(iter: I) -> ConcurrentTSTMap<Value> {
+        PersistentTSTMap::from_iter(iter).into()
+    }
+}
+
+impl<Value: Debug> Debug for ConcurrentTSTMap<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.current.load().fmt(f)
+    }
+}
+
+impl<Value> Default for ConcurrentTSTMap<Value> {
+    fn default() -> Self {
+        PersistentTSTMap::new().into()
+    }
+}
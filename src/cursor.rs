@@ -0,0 +1,645 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use super::node::{Node, BoxedNode};
+use super::map::Iter;
+use super::traverse;
+
+///
+/// Cursor, which walks a `TSTMap` down and up one char at a time, made by `TSTMap::cursor`.
+///
+/// The cursor keeps the nodes of all chars it went through, so `push` looks only
+/// at one level of the trie and `pop` just steps back, nothing is searched from the root again.
+///
+/// # Examples
+///
+/// ```rust
+/// use tst::TSTMap;
+///
+/// let mut m = TSTMap::new();
+/// m.insert("car", 1);
+/// m.insert("cart", 2);
+/// m.insert("cat", 3);
+///
+/// let mut cursor = m.cursor();
+/// for ch in "car".chars() {
+///     assert!(cursor.push(ch));
+/// }
+/// assert_eq!(Some(&1), cursor.value());
+/// assert_eq!(vec!['t'], cursor.next_chars().collect::<Vec<_>>());
+///
+/// cursor.pop();
+/// assert!(!cursor.is_key());
+/// assert_eq!(vec!['r', 't'], cursor.next_chars().collect::<Vec<_>>());
+/// ```
+#[derive(Clone)]
+pub struct Cursor<'x, Value: 'x> {
+    // node of every pushed char after the root, None once the key left the trie
+    path: Vec<Option<&'x Node<Value>>>,
+    key: String,
+    // size of the map, an upper bound for completions
+    len: usize,
+}
+
+impl<'x, Value> Cursor<'x, Value> {
+    pub(crate) fn new(root: &'x Node<Value>, len: usize) -> Self {
+        Cursor {
+            path: vec![Some(root)],
+            key: String::new(),
+            len,
+        }
+    }
+
+    fn node(&self) -> Option<&'x Node<Value>> {
+        self.path.last().copied().flatten()
+    }
+
+    /// Appends `ch` to the key of the cursor and moves it down to the node of that char.
+    /// Returns false if there is no such node, then no key of the map starts with
+    /// the key of the cursor until it's popped back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 1);
+    ///
+    /// let mut cursor = m.cursor();
+    /// assert!(cursor.push('a'));
+    /// assert!(!cursor.push('x'));
+    /// assert!(!cursor.push('b'));
+    /// assert_eq!("axb", cursor.key());
+    /// ```
+    pub fn push(&mut self, ch: char) -> bool {
+        let next = self.node().and_then(|node| traverse::level_find(&node.eq, ch));
+        self.path.push(next);
+        self.key.push(ch);
+        next.is_some()
+    }
+
+    /// Removes the last char of the key and moves the cursor back up,
+    /// returns the removed char or None at the root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 1);
+    ///
+    /// let mut cursor = m.cursor();
+    /// cursor.push('a');
+    /// cursor.push('x');
+    /// assert_eq!(Some('x'), cursor.pop());
+    /// assert!(cursor.has_children());
+    /// assert_eq!(Some('a'), cursor.pop());
+    /// assert_eq!(None, cursor.pop());
+    /// ```
+    pub fn pop(&mut self) -> Option<char> {
+        if self.path.len() == 1 {
+            return None;
+        }
+        self.path.pop();
+        self.key.pop()
+    }
+
+    /// Returns the chars pushed so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let m: TSTMap<i32> = TSTMap::new();
+    /// let mut cursor = m.cursor();
+    /// cursor.push('я');
+    /// assert_eq!("я", cursor.key());
+    /// ```
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns true if the key of the cursor is a key of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 1);
+    ///
+    /// let mut cursor = m.cursor();
+    /// cursor.push('a');
+    /// assert!(!cursor.is_key());
+    /// cursor.push('b');
+    /// assert!(cursor.is_key());
+    /// ```
+    pub fn is_key(&self) -> bool {
+        self.value().is_some()
+    }
+
+    /// Returns the value of the key of the cursor, if it's a key of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("", 0);
+    /// m.insert("a", 1);
+    ///
+    /// let mut cursor = m.cursor();
+    /// assert_eq!(Some(&0), cursor.value());
+    /// cursor.push('a');
+    /// assert_eq!(Some(&1), cursor.value());
+    /// ```
+    pub fn value(&self) -> Option<&'x Value> {
+        self.node().and_then(|node| node.value.as_ref())
+    }
+
+    /// Returns true if there are longer keys starting with the key of the cursor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 1);
+    ///
+    /// let mut cursor = m.cursor();
+    /// cursor.push('a');
+    /// assert!(cursor.has_children());
+    /// cursor.push('b');
+    /// assert!(!cursor.has_children());
+    /// ```
+    pub fn has_children(&self) -> bool {
+        self.node().is_some_and(|node| node.eq.is_some())
+    }
+
+    /// Gets an iterator over the chars, which can follow the key of the cursor, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ac", 1);
+    /// m.insert("ab", 2);
+    /// m.insert("abc", 3);
+    ///
+    /// let mut cursor = m.cursor();
+    /// cursor.push('a');
+    /// assert_eq!("bc", cursor.next_chars().collect::<String>());
+    /// ```
+    pub fn next_chars(&self) -> NextChars<'x, Value> {
+        let mut iter = NextChars { stack: Vec::new() };
+        if let Some(node) = self.node() {
+            iter.push_left(node.eq.ptr.as_deref());
+        }
+        iter
+    }
+
+    /// Gets an iterator over the entries, which keys start with the key of the cursor,
+    /// in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 1);
+    /// m.insert("abc", 2);
+    /// m.insert("b", 3);
+    ///
+    /// let mut cursor = m.cursor();
+    /// cursor.push('a');
+    /// cursor.push('b');
+    /// let keys: Vec<String> = cursor.completions().map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["ab", "abc"], keys);
+    /// ```
+    pub fn completions(&self) -> Iter<'x, Value> {
+        Iter::with_prefix(self.node(), &self.key, self.len)
+    }
+}
+
+/// Iterator over the chars following the key of a `Cursor`.
+#[derive(Clone)]
+pub struct NextChars<'x, Value: 'x> {
+    stack: Vec<&'x Node<Value>>,
+}
+
+impl<'x, Value> NextChars<'x, Value> {
+    fn push_left(&mut self, mut node: Option<&'x Node<Value>>) {
+        while let Some(cur) = node {
+            self.stack.push(cur);
+            node = cur.lt.ptr.as_deref();
+        }
+    }
+}
+
+impl<'x, Value> Iterator for NextChars<'x, Value> {
+    type Item = char;
+    fn next(&mut self) -> Option<char> {
+        let node = self.stack.pop()?;
+        self.push_left(node.gt.ptr.as_deref());
+        Some(node.c)
+    }
+}
+
+///
+/// Cursor, which walks a `TSTMap` in order of keys and changes it on the way,
+/// made by `TSTMap::cursor_mut`.
+///
+/// The cursor points to an entry of the map or to the "ghost" position, which is
+/// before the first entry and after the last one at the same time. Moving past
+/// the last entry goes to the ghost, and moving on from the ghost goes to the first entry.
+///
+/// The cursor takes the nodes on the way to the current entry out of the trie, so moving
+/// to a neighbour doesn't search anything from the root. They are put back when the cursor
+/// is dropped, the map stays borrowed until then.
+///
+/// # Examples
+///
+/// ```rust
+/// use tst::TSTMap;
+///
+/// let mut m = TSTMap::new();
+/// m.insert("a", 1);
+/// m.insert("b", 2);
+/// m.insert("c", 3);
+///
+/// {
+///     let mut cursor = m.cursor_mut();
+///     while let Some((_, value)) = cursor.current() {
+///         if *value % 2 == 0 {
+///             cursor.remove_current();
+///         } else {
+///             *cursor.value_mut().unwrap() *= 10;
+///             cursor.move_next();
+///         }
+///     }
+/// }
+/// assert_eq!(vec![10, 30], m.values().cloned().collect::<Vec<_>>());
+/// ```
+pub struct CursorMut<'x, Value: 'x> {
+    root: &'x mut Node<Value>,
+    size: &'x mut usize,
+    // nodes from `root.eq` down to the current one, each taken out of the link of its parent
+    // named by the step, so moving down and up is just a push and a pop; links are put back
+    // as the cursor goes up and when it's dropped
+    path: Vec<(Ordering, Box<Node<Value>>)>,
+    // key of the last node of the path
+    key: String,
+    ghost: bool,
+}
+
+// parts of a node in order of keys: lt subtree, value (None), eq subtree, gt subtree
+const PARTS: [Option<Ordering>; 4] = [Some(Ordering::Less), None, Some(Ordering::Equal), Some(Ordering::Greater)];
+
+fn part(i: usize, rev: bool) -> Option<Ordering> {
+    PARTS[if rev { PARTS.len() - 1 - i } else { i }]
+}
+
+fn position(part: Option<Ordering>, rev: bool) -> usize {
+    let i = PARTS.iter().position(|p| *p == part).unwrap();
+    if rev { PARTS.len() - 1 - i } else { i }
+}
+
+fn link<Value>(node: &mut Node<Value>, step: Ordering) -> &mut BoxedNode<Value> {
+    match step {
+        Ordering::Less => &mut node.lt,
+        Ordering::Equal => &mut node.eq,
+        Ordering::Greater => &mut node.gt,
+    }
+}
+
+impl<'x, Value> CursorMut<'x, Value> {
+    // cursor at the first entry or at the ghost position if the trie is empty
+    pub(crate) fn new(root: &'x mut Node<Value>, size: &'x mut usize) -> Self {
+        let mut cursor = CursorMut { root, size, path: Vec::new(), key: String::new(), ghost: true };
+        cursor.move_next();
+        cursor
+    }
+
+    fn node(&self) -> &Node<Value> {
+        self.path.last().map_or(&*self.root, |(_, node)| node)
+    }
+
+    fn node_mut(&mut self) -> &mut Node<Value> {
+        match self.path.last_mut() {
+            Some((_, node)) => node,
+            None => self.root,
+        }
+    }
+
+    fn down(&mut self, step: Ordering) -> bool {
+        let Some(child) = link(self.node_mut(), step).take() else {
+            return false;
+        };
+        if step != Ordering::Equal {
+            self.key.pop();
+        }
+        self.key.push(child.c);
+        self.path.push((step, child));
+        true
+    }
+
+    // puts the last node of the path back to its parent, a node left with no value
+    // and no children is cut off instead
+    fn up(&mut self) -> Option<Ordering> {
+        let (step, node) = self.path.pop()?;
+        self.key.pop();
+        let parent = self.node_mut();
+        let c = parent.c;
+        if node.value.is_some() || node.lt.is_some() || node.eq.is_some() || node.gt.is_some() {
+            link(parent, step).ptr = Some(node);
+        }
+        if step != Ordering::Equal {
+            self.key.push(c);
+        }
+        Some(step)
+    }
+
+    // moves to the next entry in order of keys, or the previous one if `rev`
+    fn advance(&mut self, rev: bool) {
+        let mut i = if self.ghost { 0 } else { position(None, rev) + 1 };
+        self.ghost = false;
+        loop {
+            if i == PARTS.len() {
+                match self.up() {
+                    Some(step) => i = position(Some(step), rev) + 1,
+                    None => {
+                        self.ghost = true;
+                        return;
+                    }
+                }
+                continue;
+            }
+            match part(i, rev) {
+                None if self.node().value.is_some() => return,
+                Some(step) if self.down(step) => i = 0,
+                _ => i += 1,
+            }
+        }
+    }
+
+    /// Returns the key of the current entry or None at the ghost position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    ///
+    /// let mut cursor = m.cursor_mut();
+    /// assert_eq!(Some("a"), cursor.key());
+    /// cursor.move_next();
+    /// assert_eq!(None, cursor.key());
+    /// ```
+    pub fn key(&self) -> Option<&str> {
+        if self.ghost { None } else { Some(&self.key) }
+    }
+
+    /// Returns the current entry or None at the ghost position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    ///
+    /// let cursor = m.cursor_mut();
+    /// assert_eq!(Some(("a", &1)), cursor.current());
+    /// ```
+    pub fn current(&self) -> Option<(&str, &Value)> {
+        let key = self.key()?;
+        self.node().value.as_ref().map(|value| (key, value))
+    }
+
+    /// Returns a mutable reference to the value of the current entry or None at the ghost position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    ///
+    /// let mut cursor = m.cursor_mut();
+    /// *cursor.value_mut().unwrap() = 2;
+    /// drop(cursor);
+    /// assert_eq!(Some(&2), m.get("a"));
+    /// ```
+    pub fn value_mut(&mut self) -> Option<&mut Value> {
+        if self.ghost {
+            return None;
+        }
+        self.node_mut().value.as_mut()
+    }
+
+    /// Moves the cursor to the next entry, from the last one it goes to the ghost position
+    /// and from the ghost position to the first entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("ab", 2);
+    ///
+    /// let mut cursor = m.cursor_mut();
+    /// cursor.move_next();
+    /// assert_eq!(Some("ab"), cursor.key());
+    /// cursor.move_next();
+    /// assert_eq!(None, cursor.key());
+    /// cursor.move_next();
+    /// assert_eq!(Some("a"), cursor.key());
+    /// ```
+    pub fn move_next(&mut self) {
+        self.advance(false);
+    }
+
+    /// Moves the cursor to the previous entry, from the first one it goes to the ghost position
+    /// and from the ghost position to the last entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("ab", 2);
+    ///
+    /// let mut cursor = m.cursor_mut();
+    /// cursor.move_prev();
+    /// assert_eq!(None, cursor.key());
+    /// cursor.move_prev();
+    /// assert_eq!(Some("ab"), cursor.key());
+    /// ```
+    pub fn move_prev(&mut self) {
+        self.advance(true);
+    }
+
+    /// Removes the current entry and returns it, the cursor moves to the next entry.
+    /// Nodes left without values and children are cut off the same way `TSTMap::remove` does,
+    /// as the cursor leaves them.
+    /// At the ghost position nothing is removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    ///
+    /// let mut cursor = m.cursor_mut();
+    /// assert_eq!(Some(("a".to_string(), 1)), cursor.remove_current());
+    /// assert_eq!(Some("b"), cursor.key());
+    /// drop(cursor);
+    /// assert_eq!(1, m.len());
+    /// ```
+    pub fn remove_current(&mut self) -> Option<(String, Value)> {
+        if self.ghost {
+            return None;
+        }
+        let value = self.node_mut().value.take()?;
+        let key = self.key.clone();
+        *self.size -= 1;
+        self.move_next();
+        Some((key, value))
+    }
+
+    // true if `key` goes strictly between the current entry and the next one,
+    // or the previous one if `rev`; the cursor steps to the neighbour and back
+    fn fits_next_to(&mut self, key: &str, rev: bool) -> bool {
+        let before = |a: &str, b: &str| if rev { b < a } else { a < b };
+        if !(self.ghost || before(&self.key, key)) {
+            return false;
+        }
+        self.advance(rev);
+        let fits = self.ghost || before(key, &self.key);
+        self.advance(!rev);
+        fits
+    }
+
+    // inserts `key`, which goes between the neighbours, the cursor stays where it is;
+    // the new nodes hang off a path node in a link that isn't a part of the path,
+    // so the path itself doesn't change
+    fn insert_between(&mut self, key: &str, value: Value) {
+        *self.size += 1;
+        if self.path.is_empty() || key.is_empty() {
+            traverse::insert(self.root, key).value = Some(value);
+            return;
+        }
+        // the path goes from `root.eq` by the first char of `key`
+        let mut rest = key;
+        let mut i = 0;
+        loop {
+            let ch = rest.chars().next().unwrap();
+            let step = ch.cmp(&self.path[i].1.c);
+            let tail = if step == Ordering::Equal { &rest[ch.len_utf8()..] } else { rest };
+            let next = self.path.get(i + 1).map(|(step, _)| *step);
+            if next != Some(step) || tail.is_empty() {
+                let node = &mut *self.path[i].1;
+                let node = if tail.is_empty() { node } else { traverse::insert_level(link(node, step), tail) };
+                node.value = Some(value);
+                return;
+            }
+            rest = tail;
+            i += 1;
+        }
+    }
+
+    /// Inserts a new entry right after the current one, at the ghost position it becomes
+    /// the first entry. The cursor doesn't move. Fails if the key doesn't go between
+    /// the current entry and the next one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("c", 3);
+    ///
+    /// let mut cursor = m.cursor_mut();
+    /// assert!(cursor.insert_after("b", 2).is_ok());
+    /// assert!(cursor.insert_after("d", 4).is_err());
+    /// assert_eq!(Some("a"), cursor.key());
+    /// drop(cursor);
+    /// assert_eq!(vec!["a", "b", "c"], m.keys().collect::<Vec<_>>());
+    /// ```
+    pub fn insert_after(&mut self, key: &str, value: Value) -> Result<(), UnorderedKeyError> {
+        if !self.fits_next_to(key, false) {
+            return Err(UnorderedKeyError);
+        }
+        self.insert_between(key, value);
+        Ok(())
+    }
+
+    /// Inserts a new entry right before the current one, at the ghost position it becomes
+    /// the last entry. The cursor doesn't move. Fails if the key doesn't go between
+    /// the previous entry and the current one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    ///
+    /// let mut cursor = m.cursor_mut();
+    /// assert!(cursor.insert_before("a", 1).is_ok());
+    /// assert!(cursor.insert_before("b", 0).is_err());
+    /// cursor.move_next();
+    /// assert!(cursor.insert_before("c", 3).is_ok());
+    /// drop(cursor);
+    /// assert_eq!(vec!["a", "b", "c"], m.keys().collect::<Vec<_>>());
+    /// ```
+    pub fn insert_before(&mut self, key: &str, value: Value) -> Result<(), UnorderedKeyError> {
+        if !self.fits_next_to(key, true) {
+            return Err(UnorderedKeyError);
+        }
+        self.insert_between(key, value);
+        Ok(())
+    }
+}
+
+impl<'x, Value> Drop for CursorMut<'x, Value> {
+    fn drop(&mut self) {
+        while self.up().is_some() {}
+    }
+}
+
+/// Error of `CursorMut::insert_after` and `CursorMut::insert_before`:
+/// the key doesn't go between the entries it's inserted between.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnorderedKeyError;
+
+impl fmt::Display for UnorderedKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "key doesn't go between its neighbours in order of keys")
+    }
+}
+
+impl Error for UnorderedKeyError {}
@@ -0,0 +1,49 @@
+use std::io::{self, Write};
+use super::node::Node;
+
+// edge types of the graph with their colours
+const LT: (&str, &str) = ("lt", "blue");
+const EQ: (&str, &str) = ("eq", "black");
+const GT: (&str, &str) = ("gt", "red");
+
+fn write_node<Value, W: Write>(out: &mut W, id: usize, label: &str, node: &Node<Value>) -> io::Result<()> {
+    if node.value.is_some() {
+        writeln!(out, "    n{} [label=\"{}\", shape=doublecircle, style=filled, fillcolor=lightyellow];", id, label)
+    } else {
+        writeln!(out, "    n{} [label=\"{}\"];", id, label)
+    }
+}
+
+// writes the eq subtree of `start` as a DOT graph, `start` itself is labeled with `label`,
+// nodes deeper than `max_depth` links below it are left out
+pub(crate) fn write<Value, W: Write>(
+    start: &Node<Value>,
+    label: &str,
+    max_depth: Option<usize>,
+    out: &mut W,
+) -> io::Result<()> {
+    writeln!(out, "digraph tst {{")?;
+    writeln!(out, "    node [shape=circle];")?;
+    write_node(out, 0, &label.escape_debug().to_string(), start)?;
+
+    let mut next_id = 1;
+    // parent id, edge type, node and its depth
+    let mut stack = vec![(0, EQ, start.eq.ptr.as_deref(), 1)];
+    while let Some((parent, (edge, color), node, depth)) = stack.pop() {
+        let node = match node {
+            Some(node) => node,
+            None => continue,
+        };
+        if max_depth.is_some_and(|max| depth > max) {
+            continue;
+        }
+        let id = next_id;
+        next_id += 1;
+        write_node(out, id, &node.c.escape_debug().to_string(), node)?;
+        writeln!(out, "    n{} -> n{} [label=\"{}\", color={}, fontcolor={}];", parent, id, edge, color, color)?;
+        stack.push((id, GT, node.gt.ptr.as_deref(), depth + 1));
+        stack.push((id, EQ, node.eq.ptr.as_deref(), depth + 1));
+        stack.push((id, LT, node.lt.ptr.as_deref(), depth + 1));
+    }
+    writeln!(out, "}}")
+}
@@ -0,0 +1,603 @@
+use std::ops;
+use std::fmt::{self, Debug};
+use std::cmp::Ordering;
+use super::node::Node;
+
+// node 0 is the top of the trie and never is a lt/gt child, so 0 works as "no link"
+const NIL: u32 = 0;
+const NO_VALUE: u32 = u32::MAX;
+// set in `c`, when eq child follows the node right after it
+const HAS_EQ: u32 = 1 << 31;
+
+// 16 bytes per node: char with the eq flag, lt/gt links and index in the values array
+#[derive(Clone, Copy)]
+struct FrozenNode {
+    c: u32,
+    lt: u32,
+    gt: u32,
+    value: u32,
+}
+
+impl FrozenNode {
+    #[inline]
+    fn code(&self) -> u32 {
+        self.c & !HAS_EQ
+    }
+
+    #[inline]
+    fn ch(&self) -> char {
+        char::from_u32(self.code()).unwrap()
+    }
+
+    #[inline]
+    fn cmp(&self, ch: char) -> Ordering {
+        (ch as u32).cmp(&self.code())
+    }
+}
+
+enum Link {
+    Lt(u32),
+    Gt(u32),
+    Eq,
+}
+
+///
+/// Read-only symbol table with string keys, made by [`TSTMap::freeze`](crate::TSTMap::freeze).
+///
+/// The trie is laid out in two flat arrays: nodes in pre-order, where eq child
+/// always follows its parent, and dense values. A node takes 16 bytes without
+/// any `Box` pointers and `Option<Value>` padding, so a frozen dictionary takes
+/// a fraction of the memory of `TSTMap`. Order of keys and semantics of all queries
+/// are the same as for `TSTMap`.
+/// # Examples
+///
+/// ```rust
+/// use tst::TSTMap;
+///
+/// let mut m = TSTMap::new();
+///
+/// m.insert("first", 1);
+/// m.insert("second", 2);
+/// m.insert("firstthird", 3);
+/// m.insert("firstsecond", 12);
+/// m.insert("xirst", -13);
+///
+/// let m = m.freeze();
+/// assert_eq!(Some(&1), m.get("first"));
+/// assert_eq!(5, m.len());
+/// assert_eq!("firstsecond", m.longest_prefix("firstsecondthird"));
+/// assert_eq!(3, m.prefix_iter("fir").count());
+/// assert_eq!(-12, m.wildcard_iter(".irst").fold(0, |sum, (_, val)| sum + val));
+/// ```
+#[derive(Clone)]
+pub struct FrozenTSTMap<Value> {
+    nodes: Box<[FrozenNode]>,
+    values: Box<[Value]>,
+    // value of the empty key
+    root_value: u32,
+}
+
+impl<Value> FrozenTSTMap<Value> {
+    // `root` is the root of TSTMap, it's consumed node by node,
+    // so deep tries don't overflow the stack
+    pub(crate) fn from_root(mut root: Node<Value>, size: usize) -> Self {
+        assert!(size < NO_VALUE as usize, "TSTMap is too big to freeze");
+        let mut nodes: Vec<FrozenNode> = Vec::new();
+        let mut values = Vec::with_capacity(size);
+        let root_value = match root.value.take() {
+            Some(value) => {
+                values.push(value);
+                0
+            }
+            None => NO_VALUE,
+        };
+        let mut stack = Vec::new();
+        if let Some(node) = root.eq.ptr.take() {
+            stack.push((node, Link::Eq));
+        }
+
+        while let Some((mut node, link)) = stack.pop() {
+            let idx = nodes.len() as u32;
+            assert!(idx < u32::MAX, "TSTMap is too big to freeze");
+            match link {
+                Link::Lt(parent) => nodes[parent as usize].lt = idx,
+                Link::Gt(parent) => nodes[parent as usize].gt = idx,
+                Link::Eq => {}
+            }
+            let value = match node.value.take() {
+                Some(value) => {
+                    values.push(value);
+                    (values.len() - 1) as u32
+                }
+                None => NO_VALUE,
+            };
+            let mut c = node.c as u32;
+            if let Some(gt) = node.gt.ptr.take() {
+                stack.push((gt, Link::Gt(idx)));
+            }
+            if let Some(lt) = node.lt.ptr.take() {
+                stack.push((lt, Link::Lt(idx)));
+            }
+            // pushed last, so it's popped next and gets index idx + 1
+            if let Some(eq) = node.eq.ptr.take() {
+                c |= HAS_EQ;
+                stack.push((eq, Link::Eq));
+            }
+            nodes.push(FrozenNode { c, lt: NIL, gt: NIL, value });
+        }
+
+        FrozenTSTMap {
+            nodes: nodes.into_boxed_slice(),
+            values: values.into_boxed_slice(),
+            root_value,
+        }
+    }
+
+    /// Returns the number of elements in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 2);
+    /// m.insert("x", 1);
+    /// assert_eq!(2, m.freeze().len());
+    /// ```
+    pub fn len(&self) -> usize { self.values.len() }
+
+    /// Returns true if the `FrozenTSTMap` contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let m: TSTMap<i32> = TSTMap::new();
+    /// assert!(m.freeze().is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.values.is_empty() }
+
+    /// Returns a reference to the value corresponding to the `key` or None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("first", 13);
+    /// let m = m.freeze();
+    /// assert_eq!(Some(&13), m.get("first"));
+    /// assert_eq!(None, m.get("second"));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let value = if key.is_empty() {
+            self.root_value
+        } else {
+            self.node(self.search(key)?).value
+        };
+        self.values.get(value as usize)
+    }
+
+    /// Returns true if the `FrozenTSTMap` contains a value for the specified `key`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// let m = m.freeze();
+    /// assert!(!m.contains_key("ab"));
+    /// assert!(m.contains_key("abc"))
+    /// ```
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Method returns longest prefix `pref` in the `FrozenTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// m.insert("abcdef", 1);
+    ///
+    /// assert_eq!("abcd", m.freeze().longest_prefix("abcde"));
+    /// ```
+    pub fn longest_prefix<'x>(&self, pref: &'x str) -> &'x str {
+        let mut length = 0;
+        let mut i = 0;
+        let mut cur = if self.nodes.is_empty() { None } else { Some(0) };
+
+        for ch in pref.chars() {
+            while let Some(idx) = cur {
+                let node = self.node(idx);
+                match node.cmp(ch) {
+                    Ordering::Less => cur = self.link(node.lt),
+                    Ordering::Greater => cur = self.link(node.gt),
+                    Ordering::Equal => {
+                        i += ch.len_utf8();
+                        if node.value != NO_VALUE { length = i; }
+                        cur = self.eq(idx);
+                        break;
+                    }
+                }
+            }
+            if cur.is_none() {
+                break;
+            }
+        }
+        &pref[..length]
+    }
+
+    /// An iterator returning all nodes matching wildcard pattern `pat`.
+    /// Iterator element type is (String, V)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    /// let m = m.freeze();
+    ///
+    /// for (k, v) in m.wildcard_iter(".") {
+    ///     println!("{} -> {}", k, v);
+    /// }
+    /// ```
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<'_, Value> {
+        WildCardIter::new(self, pat)
+    }
+
+    /// Method returns iterator over all values with common prefix `pref` in the `FrozenTSTMap`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// m.insert("add", 1);
+    /// let m = m.freeze();
+    ///
+    /// let keys: Vec<String> = m.prefix_iter("ab").map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["abc", "abcd"], keys);
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_, Value> {
+        if pref.is_empty() {
+            return self.iter();
+        }
+        match self.search(pref) {
+            Some(idx) => Iter::with_prefix(self, Some(idx), pref, 0, self.len()),
+            None => Iter::with_prefix(self, None, pref, 0, 0),
+        }
+    }
+
+    /// Gets an iterator over the entries of the `FrozenTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("bbc", 2);
+    /// m.insert("cccda", 3);
+    /// let m = m.freeze();
+    ///
+    /// let (first_key, first_value) = m.iter().next().unwrap();
+    /// assert_eq!((first_key, *first_value), ("abc".to_string(), 1));
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Value> {
+        let mut iter = Iter::with_prefix(self, None, "", self.len(), self.len());
+        if !self.nodes.is_empty() {
+            iter.stack.push(TraverseEntry::Node(String::new(), 0));
+        }
+        if self.root_value != NO_VALUE {
+            iter.stack.push(TraverseEntry::Value(String::new(), self.root_value));
+        }
+        iter
+    }
+
+    /// An iterator visiting all keys in alphabetical order.
+    /// Iterator element type is String
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// assert_eq!(vec!["a", "b"], m.freeze().keys().collect::<Vec<String>>());
+    /// ```
+    pub fn keys(&self) -> KeysIter<'_, Value> {
+        KeysIter { iter: self.iter() }
+    }
+
+    /// An iterator visiting all values in order of their keys.
+    /// Iterator element type is &V
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// assert_eq!(vec![&1, &2], m.freeze().values().collect::<Vec<&i32>>());
+    /// ```
+    pub fn values(&self) -> ValuesIter<'_, Value> {
+        ValuesIter { iter: self.iter() }
+    }
+
+    #[inline]
+    fn node(&self, idx: u32) -> &FrozenNode {
+        &self.nodes[idx as usize]
+    }
+
+    #[inline]
+    fn link(&self, idx: u32) -> Option<u32> {
+        if idx == NIL { None } else { Some(idx) }
+    }
+
+    #[inline]
+    fn eq(&self, idx: u32) -> Option<u32> {
+        if self.node(idx).c & HAS_EQ != 0 { Some(idx + 1) } else { None }
+    }
+
+    // index of the node with the last char of not empty `key`
+    fn search(&self, key: &str) -> Option<u32> {
+        let mut cur = if self.nodes.is_empty() { None } else { Some(0) };
+        let mut last = None;
+
+        for ch in key.chars() {
+            loop {
+                let idx = cur?;
+                let node = self.node(idx);
+                match node.cmp(ch) {
+                    Ordering::Less => cur = self.link(node.lt),
+                    Ordering::Greater => cur = self.link(node.gt),
+                    Ordering::Equal => {
+                        last = Some(idx);
+                        cur = self.eq(idx);
+                        break;
+                    }
+                }
+            }
+        }
+        last
+    }
+}
+
+impl<'x, Value> IntoIterator for &'x FrozenTSTMap<Value> {
+    type Item = (String, &'x Value);
+    type IntoIter = Iter<'x, Value>;
+
+    fn into_iter(self) -> Iter<'x, Value> {
+        self.iter()
+    }
+}
+
+impl<Value> ops::Index<&str> for FrozenTSTMap<Value> {
+    type Output = Value;
+    #[inline]
+    fn index(&self, idx: &str) -> &Value {
+        self.get(idx).expect("no entry found for key")
+    }
+}
+
+impl<Value: Debug> Debug for FrozenTSTMap<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+//
+// iterators section
+//
+
+#[derive(Clone)]
+enum TraverseEntry {
+    Node(String, u32),
+    Value(String, u32),
+}
+
+/// `FrozenTSTMap` iterator.
+#[derive(Clone)]
+pub struct Iter<'x, Value: 'x> {
+    map: &'x FrozenTSTMap<Value>,
+    stack: Vec<TraverseEntry>,
+    min_size: usize,
+    max_size: usize,
+}
+
+impl<'x, Value> Iter<'x, Value> {
+    // `node` holds the last char of `prefix`
+    fn with_prefix(map: &'x FrozenTSTMap<Value>, node: Option<u32>, prefix: &str, min: usize, max: usize) -> Self {
+        let mut iter = Iter {
+            map,
+            stack: vec![],
+            min_size: min,
+            max_size: max,
+        };
+        if let Some(idx) = node {
+            if let Some(eq) = map.eq(idx) {
+                iter.stack.push(TraverseEntry::Node(prefix.to_string(), eq));
+            }
+            let value = map.node(idx).value;
+            if value != NO_VALUE {
+                iter.min_size = iter.min_size.max(1);
+                iter.stack.push(TraverseEntry::Value(prefix.to_string(), value));
+            }
+        }
+        iter
+    }
+}
+
+impl<'x, Value> Iterator for Iter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        let map = self.map;
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(key, value) => {
+                    self.min_size = self.min_size.saturating_sub(1);
+                    self.max_size -= 1;
+                    return Some((key, &map.values[value as usize]));
+                }
+                TraverseEntry::Node(prefix, idx) => {
+                    let cur = map.node(idx);
+                    if let Some(gt) = map.link(cur.gt) {
+                        self.stack.push(TraverseEntry::Node(prefix.clone(), gt));
+                    }
+                    let eq = map.eq(idx);
+                    if eq.is_some() || cur.value != NO_VALUE {
+                        let mut key = String::with_capacity(prefix.len() + 4);
+                        key.push_str(&prefix);
+                        key.push(cur.ch());
+                        if let Some(eq) = eq {
+                            self.stack.push(TraverseEntry::Node(key.clone(), eq));
+                        }
+                        if cur.value != NO_VALUE {
+                            self.stack.push(TraverseEntry::Value(key, cur.value));
+                        }
+                    }
+                    if let Some(lt) = map.link(cur.lt) {
+                        self.stack.push(TraverseEntry::Node(prefix, lt));
+                    }
+                }
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.min_size, Some(self.max_size))
+    }
+}
+
+/// `FrozenTSTMap` keys iterator
+#[derive(Clone)]
+pub struct KeysIter<'x, Value: 'x> {
+    iter: Iter<'x, Value>,
+}
+
+impl<'x, Value: 'x> Iterator for KeysIter<'x, Value> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> { self.iter.next().map(|(k, _)| k) }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+/// `FrozenTSTMap` values iterator
+#[derive(Clone)]
+pub struct ValuesIter<'x, Value: 'x> {
+    iter: Iter<'x, Value>,
+}
+
+impl<'x, Value: 'x> Iterator for ValuesIter<'x, Value> {
+    type Item = &'x Value;
+    fn next(&mut self) -> Option<&'x Value> { self.iter.next().map(|(_, v)| v) }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+/// `FrozenTSTMap` wild-card iterator.
+#[derive(Clone)]
+pub struct WildCardIter<'x, Value: 'x> {
+    map: &'x FrozenTSTMap<Value>,
+    // an entry with position == pat.len() holds a matched value
+    stack: Vec<(String, u32, usize)>,
+    max_size: usize,
+    pat: Vec<char>,
+}
+
+impl<'x, Value> WildCardIter<'x, Value> {
+    fn new(map: &'x FrozenTSTMap<Value>, pat: &str) -> Self {
+        let mut iter = WildCardIter {
+            map,
+            stack: vec![],
+            max_size: map.len(),
+            pat: pat.chars().collect(),
+        };
+        if iter.pat.is_empty() {
+            if map.root_value != NO_VALUE {
+                iter.stack.push((String::new(), map.root_value, 0));
+            }
+        } else if !map.nodes.is_empty() {
+            iter.stack.push((String::new(), 0, 0));
+        }
+        iter
+    }
+}
+
+impl<'x, Value> Iterator for WildCardIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        let map = self.map;
+        while let Some((prefix, idx, pos)) = self.stack.pop() {
+            if pos == self.pat.len() {
+                self.max_size -= 1;
+                return Some((prefix, &map.values[idx as usize]));
+            }
+            let cur = map.node(idx);
+            let ch = self.pat[pos];
+            let ord = cur.cmp(ch);
+            if (ch == '.' || ord == Ordering::Greater) && let Some(gt) = map.link(cur.gt) {
+                self.stack.push((prefix.clone(), gt, pos));
+            }
+            if ch == '.' || ord == Ordering::Equal {
+                let mut key = String::with_capacity(prefix.len() + 4);
+                key.push_str(&prefix);
+                key.push(cur.ch());
+                if pos + 1 < self.pat.len() {
+                    if let Some(eq) = map.eq(idx) {
+                        self.stack.push((key, eq, pos + 1));
+                    }
+                } else if cur.value != NO_VALUE {
+                    self.stack.push((key, cur.value, pos + 1));
+                }
+            }
+            if (ch == '.' || ord == Ordering::Less) && let Some(lt) = map.link(cur.lt) {
+                self.stack.push((prefix, lt, pos));
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (0, Some(self.max_size)) }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem;
+    use super::FrozenNode;
+    use crate::TSTMap;
+
+    #[test]
+    fn layout() {
+        assert_eq!(16, mem::size_of::<FrozenNode>());
+
+        let mut m = TSTMap::new();
+        m.insert("abc", 1);
+        m.insert("abd", 2);
+        m.insert("b", 3);
+        let m = m.freeze();
+        // a, b, c, d and b at the top level
+        assert_eq!(5, m.nodes.len());
+        assert_eq!(3, m.values.len());
+    }
+}
@@ -0,0 +1,1030 @@
+use std::ops::{self, ControlFlow, Range};
+use std::fmt::{self, Debug};
+use std::iter::{Map, FromIterator, Enumerate, Peekable};
+use std::{mem, slice, vec};
+use super::node::{Node, BoxedNode};
+use super::traverse::{self, NodeRef, Traverse, WildCardTraverse, IntoTraverse, DropTraverse};
+use crate::tst_map::Entry;
+
+// number of chars in a table level
+const ASCII: usize = 128;
+
+// root of a table entry, None until the entry gets a key
+type TableSlot<Value> = Option<Box<Node<Value>>>;
+
+///
+/// `TSTMap` with a direct-indexed root.
+///
+/// Keys starting with `depth` ASCII chars (`depth` is 1 or 2) go to a table of
+/// sub-tries indexed by these chars, so the first levels cost one array access
+/// instead of a walk down `lt`/`gt` links; below the table they are ternary nodes.
+/// Shorter keys and keys with other chars in the first `depth` positions
+/// go to one more ordinary trie. Iteration merges both in order of keys,
+/// so all queries work the same way as in `TSTMap`.
+///
+/// The table takes 128 or 16384 slots, a trie root is allocated only for a slot
+/// with keys; still, `depth` 2 pays off only for large dictionaries.
+/// # Examples
+///
+/// ```rust
+/// use tst::HybridTSTMap;
+///
+/// let mut m = HybridTSTMap::with_depth(2);
+/// m.insert("first", 1);
+/// m.insert("second", 2);
+/// m.insert("f", 3);
+/// m.insert("день", 4);
+///
+/// assert_eq!(Some(&1), m.get("first"));
+/// assert_eq!(vec!["f", "first"], m.prefix_iter("f").map(|(k, _)| k).collect::<Vec<_>>());
+/// assert_eq!(4, m.iter().count());
+/// ```
+#[derive(Clone)]
+pub struct HybridTSTMap<Value> {
+    // root of each table entry keeps the value of the key made of its chars
+    // and the rest of the keys in the eq link, like the root of `TSTMap`;
+    // it's allocated by the first key of the entry and freed with the last one
+    tables: Box<[TableSlot<Value>]>,
+    rest: Node<Value>,
+    depth: usize,
+    size: usize,
+}
+
+impl<Value> HybridTSTMap<Value> {
+    /// Constructs a new, empty `HybridTSTMap<Value>` with a table for the first char.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    /// let mut m: HybridTSTMap<i64> = HybridTSTMap::new();
+    /// ```
+    pub fn new() -> Self {
+        HybridTSTMap::with_depth(1)
+    }
+
+    /// Constructs a new, empty `HybridTSTMap<Value>` with a table for the first `depth` chars.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` is not 1 or 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    /// let mut m: HybridTSTMap<i64> = HybridTSTMap::with_depth(2);
+    /// assert_eq!(2, m.depth());
+    /// ```
+    pub fn with_depth(depth: usize) -> Self {
+        assert!(depth == 1 || depth == 2, "depth of the table must be 1 or 2");
+        HybridTSTMap {
+            tables: (0..ASCII.pow(depth as u32)).map(|_| None).collect(),
+            rest: Node::default(),
+            depth,
+            size: 0,
+        }
+    }
+
+    /// Returns the number of chars resolved by the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    /// let m: HybridTSTMap<i64> = HybridTSTMap::new();
+    /// assert_eq!(1, m.depth());
+    /// ```
+    pub fn depth(&self) -> usize { self.depth }
+
+    /// Returns the number of elements in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// assert_eq!(0, m.len());
+    /// m.insert("ab", 2);
+    /// m.insert("x", 1);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn len(&self) -> usize { self.size }
+
+    /// Returns true if the `HybridTSTMap` contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// assert!(m.is_empty());
+    /// m.insert("ab", 2);
+    /// assert!(!m.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool { self.size == 0 }
+
+    /// Clears the `HybridTSTMap`, the depth stays the same.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.clear();
+    /// assert!(m.is_empty());
+    /// assert_eq!(None, m.get("abc"));
+    /// ```
+    pub fn clear(&mut self) { *self = HybridTSTMap::with_depth(self.depth); }
+
+    // index of the table entry for `key` and the rest of the key, if the key goes to the table
+    fn route<'k>(&self, key: &'k str) -> Option<(usize, &'k str)> {
+        let head = key.as_bytes().get(..self.depth)?;
+        if !head.is_ascii() {
+            return None;
+        }
+        let idx = head.iter().fold(0, |idx, &b| idx * ASCII + b as usize);
+        Some((idx, &key[self.depth..]))
+    }
+
+    // None if `key` goes to a table entry with no keys
+    fn root_of<'k>(&self, key: &'k str) -> Option<(&Node<Value>, &'k str)> {
+        match self.route(key) {
+            Some((idx, rest)) => self.tables[idx].as_deref().map(|root| (root, rest)),
+            None => Some((&self.rest, key)),
+        }
+    }
+
+    fn root_of_mut<'k>(&mut self, key: &'k str) -> Option<(&mut Node<Value>, &'k str)> {
+        match self.route(key) {
+            Some((idx, rest)) => self.tables[idx].as_deref_mut().map(|root| (root, rest)),
+            None => Some((&mut self.rest, key)),
+        }
+    }
+
+    /// Inserts an element at key `key` with value `val`, returns the old value if there was one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// assert_eq!(None, m.insert("SOmeWOrd", 2));
+    /// assert_eq!(Some(2), m.insert("SOmeWOrd", 4));
+    /// assert_eq!(1, m.len());
+    /// ```
+    pub fn insert(&mut self, key: &str, value: Value) -> Option<Value> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Gets the given `key`'s corresponding entry for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut count: HybridTSTMap<usize> = HybridTSTMap::new();
+    ///
+    /// for x in vec!["abc","bad","abd","cdddd","abc","bade"] {
+    ///     *count.entry(x).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(2, count["abc"]);
+    /// assert_eq!(1, count["abd"]);
+    /// ```
+    pub fn entry(&mut self, key: &str) -> Entry<'_, Value> {
+        let (idx, key) = match self.route(key) {
+            Some((idx, rest)) => (Some(idx), rest),
+            None => (None, key),
+        };
+        let root = match idx {
+            Some(idx) => self.tables[idx].get_or_insert_with(Default::default),
+            None => &mut self.rest,
+        };
+        let cur = traverse::insert(root, key);
+        Entry::new(&mut cur.value, &mut self.size)
+    }
+
+    /// Removes a `key`, returning the value at the key if the key was in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("abc", 100);
+    /// assert_eq!(Some(100), m.remove("abc"));
+    /// assert_eq!(None, m.remove("abc"));
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let ret = match self.route(key) {
+            Some((idx, rest)) => {
+                let slot = &mut self.tables[idx];
+                let ret = traverse::remove(slot.as_deref_mut()?, rest);
+                if slot.as_deref().is_some_and(is_empty) {
+                    *slot = None;
+                }
+                ret
+            },
+            None => traverse::remove(&mut self.rest, key),
+        };
+        if ret.is_some() {
+            self.size -= 1;
+        }
+        ret
+    }
+
+    /// Returns a reference to the value corresponding to the `key` or None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("first", 13);
+    /// assert_eq!(Some(&13), m.get("first"));
+    /// assert_eq!(None, m.get("second"));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let (root, key) = self.root_of(key)?;
+        traverse::search(root, key.chars()).and_then(|node| node.value.as_ref())
+    }
+
+    /// Returns a mutable reference to the value corresponding to the `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("first", 13);
+    /// if let Some(x) = m.get_mut("first") {
+    ///     *x = -13;
+    /// }
+    /// assert_eq!(-13, m["first"]);
+    /// ```
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        let (root, key) = self.root_of_mut(key)?;
+        traverse::search_mut(root, key).and_then(|node| node.value.as_mut())
+    }
+
+    /// Returns true if the `HybridTSTMap` contains a value for the specified `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert!(m.contains_key("abc"));
+    /// assert!(!m.contains_key("ab"));
+    /// ```
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the longest prefix of `pref`, which is a key in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::with_depth(2);
+    /// m.insert("a", 1);
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    ///
+    /// assert_eq!("abcd", m.longest_prefix("abcde"));
+    /// assert_eq!("a", m.longest_prefix("ab"));
+    /// ```
+    pub fn longest_prefix<'x>(&self, pref: &'x str) -> &'x str where Value: 'x {
+        if let Some((idx, rest)) = self.route(pref) && let Some(root) = self.tables[idx].as_deref() {
+            // table keys are longer than the keys of `self.rest`, which are prefixes of `pref`
+            let found = traverse::longest_prefix(root, rest);
+            if !found.is_empty() {
+                return &pref[..self.depth + found.len()];
+            }
+            if root.value.is_some() {
+                return &pref[..self.depth];
+            }
+        }
+        traverse::longest_prefix(&self.rest, pref)
+    }
+
+    // the run of table entries with keys starting with `pref`, which is shorter than the depth;
+    // other prefixes don't select any table entries as a whole
+    fn prefix_span(&self, pref: &str) -> Range<usize> {
+        if !pref.is_ascii() || pref.len() >= self.depth {
+            return 0..0;
+        }
+        let span = ASCII.pow((self.depth - pref.len()) as u32);
+        let start = pref.bytes().fold(0, |idx, b| idx * ASCII + b as usize) * span;
+        start..start + span
+    }
+
+    // first `depth` chars of `pat` and the rest of it, if keys of the table can match `pat`
+    fn wildcard_head(&self, pat: &str) -> Option<(Vec<char>, String)> {
+        let chars: Vec<char> = pat.chars().collect();
+        let head = chars.get(..self.depth).filter(|head| head.iter().all(|&c| c == '.' || c.is_ascii()))?;
+        Some((head.to_vec(), chars[self.depth..].iter().collect()))
+    }
+
+    /// An iterator returning all nodes matching wildcard pattern `pat` in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("ц", 3);
+    /// m.insert("bc", 4);
+    ///
+    /// assert_eq!(6, m.wildcard_iter(".").fold(0, |sum, (_, val)| sum + val));
+    /// ```
+    pub fn wildcard_iter(&self, pat: &str) -> Iter<'_, Value> {
+        let rest = Source::WildCard(WildCardTraverse::new(&self.rest, pat, self.size));
+        let (tables, span) = match self.wildcard_head(pat) {
+            Some((head, sub)) => (Tables::WildCard(head, sub), 0..self.tables.len()),
+            None => (Tables::Prefix, 0..0),
+        };
+        let roots = TableIter::new(&self.tables[span.clone()], span.start, tables, self.depth, self.size);
+        Iter { iter: Merge::new(roots, rest) }
+    }
+
+    /// A mutable iterator returning all nodes matching wildcard pattern `pat` in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("bc", 2);
+    /// m.insert("ц", 3);
+    ///
+    /// for (_, value) in m.wildcard_iter_mut(".") {
+    ///     *value += 10;
+    /// }
+    /// assert_eq!(11, m["a"]);
+    /// assert_eq!(2, m["bc"]);
+    /// assert_eq!(13, m["ц"]);
+    /// ```
+    pub fn wildcard_iter_mut(&mut self, pat: &str) -> IterMut<'_, Value> {
+        let (tables, span) = match self.wildcard_head(pat) {
+            Some((head, sub)) => (Tables::WildCard(head, sub), 0..self.tables.len()),
+            None => (Tables::Prefix, 0..0),
+        };
+        let HybridTSTMap { tables: ref mut table, ref mut rest, depth, size } = *self;
+        let rest = Source::WildCard(WildCardTraverse::new(rest, pat, size));
+        let roots = TableIter::new(&mut table[span.clone()], span.start, tables, depth, size);
+        IterMut { iter: Merge::new(roots, rest) }
+    }
+
+    /// An iterator over the keys starting with `pref` in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::with_depth(2);
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.insert("a", 3);
+    /// m.insert("b", 4);
+    ///
+    /// let keys: Vec<String> = m.prefix_iter("a").map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["a", "abc", "abd"], keys);
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_, Value> {
+        if let Some((idx, sub)) = self.route(pref) {
+            // all the keys with such a prefix are in one table entry
+            let node = self.tables[idx].as_deref().and_then(|root| traverse::search(root, sub.chars()));
+            let mut roots = TableIter::new(&self.tables[..0], 0, Tables::Prefix, self.depth, self.size);
+            roots.current = Some(Source::Prefix(Traverse::with_prefix(node, pref, self.size)));
+            return Iter { iter: Merge::new(roots, Source::Prefix(Default::default())) };
+        }
+        let node = traverse::search(&self.rest, pref.chars());
+        let rest = Source::Prefix(Traverse::with_prefix(node, pref, self.size));
+        let span = self.prefix_span(pref);
+        let roots = TableIter::new(&self.tables[span.clone()], span.start, Tables::Prefix, self.depth, self.size);
+        Iter { iter: Merge::new(roots, rest) }
+    }
+
+    /// A mutable iterator over the keys starting with `pref` in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::with_depth(2);
+    /// m.insert("abc", 1);
+    /// m.insert("a", 2);
+    /// m.insert("b", 3);
+    ///
+    /// for (_, value) in m.prefix_iter_mut("a") {
+    ///     *value *= 10;
+    /// }
+    /// assert_eq!(10, m["abc"]);
+    /// assert_eq!(20, m["a"]);
+    /// assert_eq!(3, m["b"]);
+    /// ```
+    pub fn prefix_iter_mut(&mut self, pref: &str) -> IterMut<'_, Value> {
+        let route = self.route(pref);
+        let span = self.prefix_span(pref);
+        let HybridTSTMap { tables: ref mut table, ref mut rest, depth, size } = *self;
+        if let Some((idx, sub)) = route {
+            // all the keys with such a prefix are in one table entry
+            let (head, tail) = table.split_at_mut(idx);
+            let node = tail[0].as_deref_mut().and_then(|root| traverse::search_mut(root, sub));
+            let mut roots = TableIter::new(&mut head[..0], 0, Tables::Prefix, depth, size);
+            roots.current = Some(Source::Prefix(Traverse::with_prefix(node, pref, size)));
+            return IterMut { iter: Merge::new(roots, Source::Prefix(Default::default())) };
+        }
+        let node = traverse::search_mut(rest, pref);
+        let rest = Source::Prefix(Traverse::with_prefix(node, pref, size));
+        let roots = TableIter::new(&mut table[span.clone()], span.start, Tables::Prefix, depth, size);
+        IterMut { iter: Merge::new(roots, rest) }
+    }
+
+    /// Gets an iterator over the entries in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("ж", 3);
+    /// m.insert("a", 1);
+    ///
+    /// let keys: Vec<String> = m.iter().map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["a", "b", "ж"], keys);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Value> {
+        self.prefix_iter("")
+    }
+
+    /// Gets a mutable iterator over the entries in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("ж", 2);
+    ///
+    /// for (key, value) in m.iter_mut() {
+    ///     if key != "a" {
+    ///         *value += 10;
+    ///     }
+    /// }
+    /// assert_eq!(1, m["a"]);
+    /// assert_eq!(12, m["ж"]);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, Value> {
+        self.prefix_iter_mut("")
+    }
+
+    /// Calls `f` for every entry in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("ab", 2);
+    /// m.insert("ж", 3);
+    ///
+    /// let mut longest = String::new();
+    /// m.for_each(|key, _| if key.len() > longest.len() { longest = key.to_string() });
+    /// assert_eq!("ab", longest);
+    /// ```
+    pub fn for_each<'x, F: FnMut(&str, &'x Value)>(&'x self, mut f: F) {
+        let _ = self.try_for_each(|key, value| -> ControlFlow<()> {
+            f(key, value);
+            ControlFlow::Continue(())
+        });
+    }
+
+    /// Calls `f` for entries in order of keys until it returns `ControlFlow::Break`,
+    /// returns what it broke with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", -2);
+    /// m.insert("c", 3);
+    ///
+    /// let negative = m.try_for_each(|key, value| {
+    ///     if *value < 0 { ControlFlow::Break(key.to_string()) } else { ControlFlow::Continue(()) }
+    /// });
+    /// assert_eq!(ControlFlow::Break("b".to_string()), negative);
+    /// ```
+    pub fn try_for_each<'x, B, F>(&'x self, mut f: F) -> ControlFlow<B>
+        where F: FnMut(&str, &'x Value) -> ControlFlow<B>
+    {
+        // one key buffer for all the table entries and the rest of the keys
+        self.visit_span(Some(&self.rest), 0, &mut String::new(), &mut f)
+    }
+
+    // visits the keys starting with `key`, which is made of fewer than `depth` ASCII chars and
+    // leads to the table entries from `idx * ASCII^(depth - key.len())` on; `rest` is the node
+    // of `key` in `self.rest`
+    fn visit_span<'x, B, F>(&'x self, rest: Option<&'x Node<Value>>, idx: usize, key: &mut String, f: &mut F)
+        -> ControlFlow<B>
+        where F: FnMut(&str, &'x Value) -> ControlFlow<B>
+    {
+        if key.len() == self.depth {
+            return traverse::visit_with(self.tables[idx].as_deref(), key, None, &mut *f);
+        }
+        // `key` itself goes before the keys of the table entries, which go on with an ASCII char,
+        // and the other keys of `self.rest` go on with a non-ASCII one after them
+        if let Some(value) = rest.and_then(|node| node.value.as_ref()) {
+            f(key, value)?;
+        }
+        for c in (0..ASCII as u8).map(char::from) {
+            key.push(c);
+            let node = rest.and_then(|node| traverse::level_find(&node.eq, c));
+            let ret = self.visit_span(node, idx * ASCII + c as usize, key, f);
+            key.pop();
+            ret?;
+        }
+        match rest {
+            Some(node) => visit_level(&node.eq, char::from(ASCII as u8), key, f),
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    /// An iterator visiting all keys in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// let keys: Vec<String> = m.keys().collect();
+    /// assert_eq!(vec!["a", "b"], keys);
+    /// ```
+    pub fn keys(&self) -> KeysIter<'_, Value> {
+        fn first<A, B>((k, _): (A, B)) -> A { k }
+        KeysIter { iter: self.iter().map(first) }
+    }
+
+    /// An iterator visiting all values in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// let values: Vec<i32> = m.values().cloned().collect();
+    /// assert_eq!(vec![1, 2], values);
+    /// ```
+    pub fn values(&self) -> ValuesIter<'_, Value> {
+        fn second<A, B>((_, v): (A, B)) -> B { v }
+        ValuesIter { iter: self.iter().map(second) }
+    }
+}
+
+impl<Value> IntoIterator for HybridTSTMap<Value> {
+    type Item = (String, Value);
+    type IntoIter = IntoIter<Value>;
+
+    /// Creates a consuming iterator, which moves each key-value pair out of
+    /// the `HybridTSTMap` in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::HybridTSTMap;
+    ///
+    /// let mut m = HybridTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("ж", 3);
+    /// m.insert("a", 1);
+    ///
+    /// let entries: Vec<(String, i32)> = m.into_iter().collect();
+    /// assert_eq!(vec![("a".to_string(), 1), ("b".to_string(), 2), ("ж".to_string(), 3)], entries);
+    /// ```
+    fn into_iter(self) -> IntoIter<Value> {
+        IntoIter::new(self)
+    }
+}
+
+impl<'x, Value> FromIterator<(&'x str, Value)> for HybridTSTMap<Value> {
+    fn from_iter<I: IntoIterator<Item = (&'x str, Value)>>(iter: I) -> HybridTSTMap<Value> {
+        let mut m = HybridTSTMap::new();
+        m.extend(iter);
+        m
+    }
+}
+
+impl<'x, Value> Extend<(&'x str, Value)> for HybridTSTMap<Value> {
+    #[inline]
+    fn extend<I: IntoIterator<Item=(&'x str, Value)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<Value> ops::Index<&str> for HybridTSTMap<Value> {
+    type Output = Value;
+    #[inline]
+    fn index(&self, idx: &str) -> &Value {
+        self.get(idx).expect("no entry found for key")
+    }
+}
+
+impl<Value> ops::IndexMut<&str> for HybridTSTMap<Value> {
+    #[inline]
+    fn index_mut(&mut self, idx: &str) -> &mut Value {
+        self.get_mut(idx).expect("no entry found for key")
+    }
+}
+
+impl<Value> Drop for HybridTSTMap<Value> {
+    fn drop(&mut self) {
+        let roots = self.tables.iter_mut().filter_map(|slot| slot.as_deref_mut()).chain(Some(&mut self.rest));
+        for root in roots.filter(|root| root.eq.is_some()) {
+            let mut iter = DropTraverse::new(root.eq.take());
+            while iter.next().is_some() { }
+        }
+    }
+}
+
+impl<Value: Debug> Debug for HybridTSTMap<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<Value> Default for HybridTSTMap<Value> {
+    fn default() -> Self {
+        HybridTSTMap::new()
+    }
+}
+
+//
+// iterators section
+//
+
+// visits the keys under the nodes of the level of `link` with chars from `min` on, in order;
+// the prefix of the level is in `key`
+fn visit_level<'x, Value, B, F>(link: &'x BoxedNode<Value>, min: char, key: &mut String, f: &mut F)
+    -> ControlFlow<B>
+    where F: FnMut(&str, &'x Value) -> ControlFlow<B>
+{
+    let mut stack = Vec::new();
+    let mut node = link.ptr.as_deref();
+    loop {
+        while let Some(cur) = node {
+            if cur.c >= min {
+                stack.push(cur);
+                node = cur.lt.ptr.as_deref();
+            } else {
+                node = cur.gt.ptr.as_deref();
+            }
+        }
+        let Some(cur) = stack.pop() else {
+            return ControlFlow::Continue(());
+        };
+        key.push(cur.c);
+        let ret = traverse::visit_with(Some(cur), key, None, &mut *f);
+        key.pop();
+        ret?;
+        node = cur.gt.ptr.as_deref();
+    }
+}
+
+// prefix of the keys in the table entry `idx`
+fn table_prefix(depth: usize, idx: usize) -> String {
+    (0..depth).rev().map(|i| ((idx / ASCII.pow(i as u32)) % ASCII) as u8 as char).collect()
+}
+
+fn is_empty<Value>(root: &Node<Value>) -> bool {
+    root.value.is_none() && !root.eq.is_some()
+}
+
+// shared or unique reference to a table slot
+trait Slot {
+    type Root: NodeRef;
+    // the root of the entry, if it has keys
+    fn root(self) -> Option<Self::Root>;
+}
+
+impl<'x, Value> Slot for &'x TableSlot<Value> {
+    type Root = &'x Node<Value>;
+    fn root(self) -> Option<&'x Node<Value>> { self.as_deref().filter(|root| !is_empty(root)) }
+}
+
+impl<'x, Value> Slot for &'x mut TableSlot<Value> {
+    type Root = &'x mut Node<Value>;
+    fn root(self) -> Option<&'x mut Node<Value>> { self.as_deref_mut().filter(|root| !is_empty(root)) }
+}
+
+enum Source<N: NodeRef> {
+    Prefix(Traverse<N>),
+    WildCard(WildCardTraverse<N>),
+}
+
+impl<N: NodeRef> Iterator for Source<N> {
+    type Item = (String, N::Value);
+    fn next(&mut self) -> Option<(String, N::Value)> {
+        match *self {
+            Source::Prefix(ref mut iter) => iter.next(),
+            Source::WildCard(ref mut iter) => iter.next(),
+        }
+    }
+}
+
+impl<N: NodeRef> Clone for Source<N> where Traverse<N>: Clone, WildCardTraverse<N>: Clone {
+    fn clone(&self) -> Self {
+        match *self {
+            Source::Prefix(ref iter) => Source::Prefix(iter.clone()),
+            Source::WildCard(ref iter) => Source::WildCard(iter.clone()),
+        }
+    }
+}
+
+// which keys of the table entries go into the iteration
+#[derive(Clone)]
+enum Tables {
+    Prefix,
+    // first `depth` chars of the pattern and the rest of it
+    WildCard(Vec<char>, String),
+}
+
+// entries of a run of table entries, they go in order of their prefixes
+struct TableIter<N: NodeRef, I> {
+    roots: Enumerate<I>,
+    // index of the first entry of the run in the table
+    offset: usize,
+    tables: Tables,
+    depth: usize,
+    size: usize,
+    current: Option<Source<N>>,
+}
+
+impl<N: NodeRef, I: Iterator<Item: Slot<Root = N>>> TableIter<N, I> {
+    fn new<R>(roots: R, offset: usize, tables: Tables, depth: usize, size: usize) -> Self
+        where R: IntoIterator<IntoIter = I>
+    {
+        TableIter {
+            roots: roots.into_iter().enumerate(),
+            offset,
+            tables,
+            depth,
+            size,
+            current: None,
+        }
+    }
+}
+
+impl<N: NodeRef, I: Iterator<Item: Slot<Root = N>>> Iterator for TableIter<N, I> {
+    type Item = (String, N::Value);
+    fn next(&mut self) -> Option<(String, N::Value)> {
+        loop {
+            if let Some(entry) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(entry);
+            }
+            self.current = None;
+            let (idx, slot) = self.roots.next()?;
+            let Some(root) = slot.root() else {
+                continue;
+            };
+            let prefix = table_prefix(self.depth, self.offset + idx);
+            self.current = match self.tables {
+                Tables::Prefix => Some(Source::Prefix(Traverse::with_prefix(Some(root), &prefix, self.size))),
+                Tables::WildCard(ref head, ref sub) => {
+                    if head.iter().zip(prefix.chars()).all(|(&p, c)| p == '.' || p == c) {
+                        Some(Source::WildCard(WildCardTraverse::with_prefix(root, &prefix, sub, self.size)))
+                    } else {
+                        None
+                    }
+                },
+            };
+        }
+    }
+}
+
+impl<N: NodeRef, I: Clone> Clone for TableIter<N, I> where Source<N>: Clone {
+    fn clone(&self) -> Self {
+        TableIter {
+            roots: self.roots.clone(),
+            offset: self.offset,
+            tables: self.tables.clone(),
+            depth: self.depth,
+            size: self.size,
+            current: self.current.clone(),
+        }
+    }
+}
+
+// merges the keys of the table and the other keys, both go in order
+struct Merge<T: Iterator, R: Iterator> {
+    tables: Peekable<T>,
+    rest: Peekable<R>,
+}
+
+impl<Item, T: Iterator<Item = (String, Item)>, R: Iterator<Item = (String, Item)>> Merge<T, R> {
+    fn new(tables: T, rest: R) -> Self {
+        Merge {
+            tables: tables.peekable(),
+            rest: rest.peekable(),
+        }
+    }
+}
+
+impl<Item, T: Iterator<Item = (String, Item)>, R: Iterator<Item = (String, Item)>> Iterator for Merge<T, R> {
+    type Item = (String, Item);
+    fn next(&mut self) -> Option<(String, Item)> {
+        let from_tables = match (self.tables.peek(), self.rest.peek()) {
+            (Some(table), Some(rest)) => table.0 < rest.0,
+            (table, _) => table.is_some(),
+        };
+        if from_tables { self.tables.next() } else { self.rest.next() }
+    }
+}
+
+impl<T: Iterator + Clone, R: Iterator + Clone> Clone for Merge<T, R> where T::Item: Clone, R::Item: Clone {
+    fn clone(&self) -> Self {
+        Merge {
+            tables: self.tables.clone(),
+            rest: self.rest.clone(),
+        }
+    }
+}
+
+type Merged<N, I> = Merge<TableIter<N, I>, Source<N>>;
+
+/// `HybridTSTMap` iterator, merges the keys of the table and the other keys in order.
+#[derive(Clone)]
+pub struct Iter<'x, Value: 'x> {
+    iter: Merged<&'x Node<Value>, slice::Iter<'x, TableSlot<Value>>>,
+}
+
+impl<'x, Value> Iterator for Iter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> { self.iter.next() }
+}
+
+/// `HybridTSTMap` mutable iterator, merges the keys of the table and the other keys in order.
+pub struct IterMut<'x, Value: 'x> {
+    iter: Merged<&'x mut Node<Value>, slice::IterMut<'x, TableSlot<Value>>>,
+}
+
+impl<'x, Value> Iterator for IterMut<'x, Value> {
+    type Item = (String, &'x mut Value);
+    fn next(&mut self) -> Option<(String, &'x mut Value)> { self.iter.next() }
+}
+
+// moves the entries out of a trie root, puts `prefix` before the keys
+struct Drain<Value> {
+    prefix: String,
+    iter: IntoTraverse<Value>,
+}
+
+impl<Value> Iterator for Drain<Value> {
+    type Item = (String, Value);
+    fn next(&mut self) -> Option<(String, Value)> {
+        let (key, value) = self.iter.next()?;
+        if self.prefix.is_empty() {
+            return Some((key, value));
+        }
+        let mut full = String::with_capacity(self.prefix.len() + key.len());
+        full.push_str(&self.prefix);
+        full.push_str(&key);
+        Some((full, value))
+    }
+}
+
+// moves the entries out of the table entries in order of their prefixes
+struct TableDrain<Value> {
+    roots: Enumerate<vec::IntoIter<TableSlot<Value>>>,
+    depth: usize,
+    size: usize,
+    current: Option<Drain<Value>>,
+}
+
+impl<Value> Iterator for TableDrain<Value> {
+    type Item = (String, Value);
+    fn next(&mut self) -> Option<(String, Value)> {
+        loop {
+            if let Some(entry) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(entry);
+            }
+            let (idx, root) = self.roots.by_ref()
+                .find_map(|(idx, slot)| slot.filter(|root| !is_empty(root)).map(|root| (idx, root)))?;
+            self.current = Some(Drain {
+                prefix: table_prefix(self.depth, idx),
+                iter: IntoTraverse::new(*root, self.size),
+            });
+        }
+    }
+}
+
+/// `HybridTSTMap` consuming iterator, moves the entries out in order of keys.
+pub struct IntoIter<Value> {
+    iter: Merge<TableDrain<Value>, Drain<Value>>,
+    size: usize,
+}
+
+impl<Value> IntoIter<Value> {
+    fn new(mut map: HybridTSTMap<Value>) -> Self {
+        let size = map.size;
+        let tables = TableDrain {
+            roots: mem::take(&mut map.tables).into_vec().into_iter().enumerate(),
+            depth: map.depth,
+            size,
+            current: None,
+        };
+        let rest = Drain {
+            prefix: String::new(),
+            iter: IntoTraverse::new(mem::take(&mut map.rest), size),
+        };
+        IntoIter {
+            iter: Merge::new(tables, rest),
+            size,
+        }
+    }
+}
+
+impl<Value> Iterator for IntoIter<Value> {
+    type Item = (String, Value);
+    fn next(&mut self) -> Option<(String, Value)> {
+        let entry = self.iter.next()?;
+        self.size -= 1;
+        Some(entry)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.size, Some(self.size)) }
+}
+
+impl<Value> ExactSizeIterator for IntoIter<Value> {
+    fn len(&self) -> usize { self.size }
+}
+
+/// `HybridTSTMap` keys iterator
+#[derive(Clone)]
+pub struct KeysIter<'x, Value: 'x> {
+    #[allow(clippy::type_complexity)]
+    iter: Map<Iter<'x, Value>, fn((String, &'x Value)) -> String>,
+}
+
+impl<'x, Value: 'x> Iterator for KeysIter<'x, Value> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> { self.iter.next() }
+}
+
+/// `HybridTSTMap` values iterator
+#[derive(Clone)]
+pub struct ValuesIter<'x, Value: 'x> {
+    #[allow(clippy::type_complexity)]
+    iter: Map<Iter<'x, Value>, fn((String, &'x Value)) -> &'x Value>,
+}
+
+impl<'x, Value: 'x> Iterator for ValuesIter<'x, Value> {
+    type Item = &'x Value;
+    fn next(&mut self) -> Option<&'x Value> { self.iter.next() }
+}
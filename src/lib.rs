@@ -74,17 +74,101 @@ extern crate core;
 
 pub use tst_map::TSTMap;
 pub use tst_set::TSTSet;
+pub use arena_map::ArenaTSTMap;
+pub use radix_map::RadixTSTMap;
+pub use balanced_map::BalancedTSTMap;
+pub use splay_map::SplayTSTMap;
+pub use hybrid_map::HybridTSTMap;
+pub use persistent_map::PersistentTSTMap;
+#[cfg(feature = "concurrent")]
+pub use concurrent_map::ConcurrentTSTMap;
+pub use frozen_map::FrozenTSTMap;
+pub use tst_snapshot::{ValueCodec, SnapshotError};
+pub use mapped_map::MappedTSTMap;
+pub use tst_builder::{TSTBuilder, BuildError};
+pub use tst_stats::{Stats, HeapSize};
 
 /// TST container map and set implementation.
 mod node;
 mod traverse;
 mod map;
 mod set;
+mod arena;
+mod radix;
+mod balanced;
+mod splay;
+mod hybrid;
+mod persistent;
+#[cfg(feature = "concurrent")]
+mod concurrent;
+mod frozen;
+mod snapshot;
+mod mapped;
+mod builder;
+mod stats;
+mod dot;
+mod cursor;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "serde")]
+mod serde_impl;
 
 pub mod tst_map {
     pub use crate::map::*;
+    pub use crate::cursor::{Cursor, NextChars, CursorMut, UnorderedKeyError};
+    #[cfg(feature = "rayon")]
+    pub use crate::par::{ParIter, ParIterMut};
 }
 
 pub mod tst_set {
     pub use crate::set::*;
 }
+
+pub mod arena_map {
+    pub use crate::arena::*;
+}
+
+pub mod radix_map {
+    pub use crate::radix::*;
+}
+
+pub mod balanced_map {
+    pub use crate::balanced::*;
+}
+
+pub mod splay_map {
+    pub use crate::splay::*;
+}
+
+pub mod hybrid_map {
+    pub use crate::hybrid::*;
+}
+
+pub mod persistent_map {
+    pub use crate::persistent::*;
+}
+
+#[cfg(feature = "concurrent")]
+pub mod concurrent_map {
+    pub use crate::concurrent::*;
+}
+
+pub mod frozen_map {
+    pub use crate::frozen::*;
+}
+
+pub mod mapped_map {
+    pub use crate::mapped::*;
+}
+
+pub mod tst_builder {
+    pub use crate::builder::*;
+}
+
+pub mod tst_stats {
+    pub use crate::stats::{Stats, HeapSize};
+}
+
+pub mod tst_snapshot {
+    pub use crate::snapshot::{ValueCodec, SnapshotError};
+}
@@ -1,10 +1,23 @@
 use std::mem;
-use std::ops;
+use std::ops::{self, ControlFlow};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
 use std::fmt::{self, Debug};
 use std::default::Default;
 use self::Entry::*;
 use std::iter::{Map, FromIterator};
-use super::node::{Node, NodeRef, NodeRefMut, BoxedNode};
+use super::node::Node;
+use super::frozen::FrozenTSTMap;
+use super::snapshot::{self, ValueCodec, SnapshotError};
+use std::io::{self, Read, Write};
+use super::mapped;
+use super::dot;
+use super::cursor::{Cursor, CursorMut};
+use super::stats::{self, Stats, HeapSize};
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelExtend, ParallelIterator};
+#[cfg(feature = "rayon")]
+use super::par::{self, ParIter, ParIterMut};
 use super::traverse::{self, Traverse, ValuesTraverse, IntoTraverse, WildCardTraverse, DropTraverse};
 
 ///
@@ -46,13 +59,18 @@ use super::traverse::{self, Traverse, ValuesTraverse, IntoTraverse, WildCardTrav
 /// // get sum by wildcard iterator
 /// assert_eq!(-12, m.wildcard_iter(".irst").fold(0, |sum, (_, val)| sum + val));
 /// ```
-
-// by design TSTMap depends on order of inserts in it, not only on keys and data itself
-
+///
 /// Root struct for `TSTMap`, which holds root and size.
-#[derive(Clone, PartialEq, Eq)]
+///
+/// Maps are compared and hashed by their entries in order of keys, the shape
+/// of the trie, which depends on the order of inserts, doesn't matter.
+// the shape of the trie depends on the order of inserts, not only on keys and data itself,
+// `from_sorted_iter` is the way to get a balanced one from sorted input
+#[derive(Clone)]
 pub struct TSTMap<Value> {
-    root: BoxedNode<Value>,
+    // root node never has a char of its own, it keeps value of the empty key
+    // and the trie itself in the eq link
+    root: Node<Value>,
     size: usize,
 }
 
@@ -68,6 +86,38 @@ impl<Value> TSTMap<Value> {
         Default::default()
     }
 
+    /// Constructs a `TSTMap<Value>` from key-value pairs sorted by key.
+    ///
+    /// Unlike `collect`, keys are inserted median-first, so the `lt`/`gt` tree
+    /// of every level comes out balanced instead of degenerating into a `gt` chain.
+    /// The whole input is collected and stably sorted before building, which takes
+    /// one pass if it's sorted already. If a key repeats, the last value wins.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let m = TSTMap::from_sorted_iter(vec![("abc", 1), ("abd", 2), ("b", 3), ("b", 4)]);
+    /// assert_eq!(3, m.len());
+    /// assert_eq!(Some(&4), m.get("b"));
+    /// ```
+    pub fn from_sorted_iter<K: AsRef<str>, I: IntoIterator<Item = (K, Value)>>(iter: I) -> Self {
+        let mut sorted: Vec<(K, Value)> = iter.into_iter().collect();
+        // stable sort keeps the last of repeated keys last
+        sorted.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+        let mut items: Vec<(K, Value)> = Vec::with_capacity(sorted.len());
+        for (key, value) in sorted {
+            match items.last_mut() {
+                Some(last) if last.0.as_ref() == key.as_ref() => *last = (key, value),
+                _ => items.push((key, value)),
+            }
+        }
+        let mut m = TSTMap::new();
+        m.insert_balanced(items);
+        m
+    }
+
     /// Returns the number of elements in the container.
     ///
     /// # Examples
@@ -84,10 +134,7 @@ impl<Value> TSTMap<Value> {
     pub fn len(&self) -> usize { self.size }
 
     /// Inserts an element at key `key` with value `val`.
-    ///
-    /// # Panics
-    ///
-    /// Panics if `key` is empty or more then 2000 symbols(because of reccursion).
+    /// The empty string is a valid key too, and keys may be of any length.
     ///
     /// # Examples
     ///
@@ -100,7 +147,6 @@ impl<Value> TSTMap<Value> {
     /// assert_eq!(2, m.len());
     /// ```
     pub fn insert(&mut self, key: &str, value: Value) -> Option<Value> {
-        assert!(!key.is_empty(), "Empty key");
         match self.entry(key) {
             Occupied(mut entry) => Some(entry.insert(value)),
             Vacant(entry) => {
@@ -126,11 +172,10 @@ impl<Value> TSTMap<Value> {
     /// assert_eq!(2, count["abc"]);
     /// assert_eq!(1, count["abd"]);
     /// ```
-    pub fn entry(&mut self, key: &str) -> Entry<Value> {
-        assert!(!key.is_empty(), "Empty key");
+    pub fn entry(&mut self, key: &str) -> Entry<'_, Value> {
         let l = &mut self.size;
-        let cur = traverse::insert(self.root.as_mut(), key);
-        Entry::<Value>::new(cur, l)
+        let cur = traverse::insert(&mut self.root, key);
+        Entry::<Value>::new(&mut cur.value, l)
     }
 
     /// Removes a `key` from the TSTMap, returning the value at the key if the key
@@ -147,13 +192,50 @@ impl<Value> TSTMap<Value> {
     /// assert_eq!(None, m.remove("abc"));
     /// ```
     pub fn remove(&mut self, key: &str) -> Option<Value> {
-        let ret = traverse::remove(self.root.as_mut(), key);
+        let ret = traverse::remove(&mut self.root, key);
+        if ret.is_some() {
+            self.size -= 1;
+        }
+        ret
+    }
+
+    // entry of `BalancedTSTMap`, keeps every level a treap
+    pub(crate) fn treap_entry(&mut self, key: &str) -> Entry<'_, Value> {
+        let l = &mut self.size;
+        let cur = traverse::treap_insert(&mut self.root, key);
+        Entry::<Value>::new(&mut cur.value, l)
+    }
+
+    // remove of `BalancedTSTMap`, keeps every level a treap
+    pub(crate) fn treap_remove(&mut self, key: &str) -> Option<Value> {
+        let ret = traverse::treap_remove(&mut self.root, key);
         if ret.is_some() {
             self.size -= 1;
         }
         ret
     }
 
+    // entry of `SplayTSTMap`, moves the nodes of `key` to the tops of their levels
+    pub(crate) fn splay_entry(&mut self, key: &str) -> Entry<'_, Value> {
+        let l = &mut self.size;
+        let cur = traverse::splay_insert(&mut self.root, key);
+        Entry::<Value>::new(&mut cur.value, l)
+    }
+
+    // remove of `SplayTSTMap`
+    pub(crate) fn splay_remove(&mut self, key: &str) -> Option<Value> {
+        let ret = traverse::splay_remove(&mut self.root, key);
+        if ret.is_some() {
+            self.size -= 1;
+        }
+        ret
+    }
+
+    // lookup of `SplayTSTMap`, moves the nodes of `key` to the tops of their levels
+    pub(crate) fn splay_get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        traverse::splay_search(&mut self.root, key).and_then(|node| node.value.as_mut())
+    }
+
     /// Returns a reference to the value corresponding to the `key` or None.
     ///
     /// # Examples
@@ -167,7 +249,7 @@ impl<Value> TSTMap<Value> {
     /// assert_eq!(None, m.get("second"));
     /// ```
     pub fn get(&self, key: &str) -> Option<&Value> {
-        match traverse::search(self.root.as_ref(), key) {
+        match traverse::search(&self.root, key.chars()) {
             None => None,
             Some(ptr) => ptr.value.as_ref(),
         }
@@ -188,7 +270,7 @@ impl<Value> TSTMap<Value> {
     /// assert_eq!(-13, m["first"]);
     /// ```
     pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
-        match traverse::search_mut(self.root.as_ref_mut(), key) {
+        match traverse::search_mut(&mut self.root, key) {
             None => None,
             Some(ptr) => ptr.value.as_mut(),
         }
@@ -210,6 +292,45 @@ impl<Value> TSTMap<Value> {
         self.get(key).is_some()
     }
 
+    /// Returns a reference to the value corresponding to the key given as a sequence of chars,
+    /// so there is no need to build a `String` for lookup.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("first", 13);
+    ///
+    /// let buf = ['f', 'i', 'r', 's', 't'];
+    /// assert_eq!(Some(&13), m.get_chars(buf.iter().cloned()));
+    /// assert_eq!(None, m.get_chars("fir".chars()));
+    /// ```
+    pub fn get_chars<I: IntoIterator<Item = char>>(&self, key: I) -> Option<&Value> {
+        match traverse::search(&self.root, key) {
+            None => None,
+            Some(ptr) => ptr.value.as_ref(),
+        }
+    }
+
+    /// Returns true if the `TSTMap` contains a value for the key given as a sequence of chars.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert!(!m.contains_chars(['a', 'b']));
+    /// assert!(m.contains_chars(['a', 'b', 'c']));
+    /// ```
+    #[inline]
+    pub fn contains_chars<I: IntoIterator<Item = char>>(&self, key: I) -> bool {
+        self.get_chars(key).is_some()
+    }
+
     /// Returns true if the `TSTMap` contains no elements.
     ///
     /// # Examples
@@ -260,8 +381,8 @@ impl<Value> TSTMap<Value> {
     ///     println!("{} -> {}", k, v);
     /// }
     /// ```
-    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<Value> {
-        WildCardIter::new(self.root.as_ref(), pat, self.len())
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<'_, Value> {
+        WildCardIter::new(&self.root, pat, self.len())
     }
 
     /// An mutable iterator returning all nodes matching wildcard pattern `pat`.
@@ -283,8 +404,9 @@ impl<Value> TSTMap<Value> {
     /// assert_eq!(12, m["b"]);
     /// assert_eq!(13, m["c"]);
     /// ```
-    pub fn wildcard_iter_mut(&mut self, pat: &str) -> WildCardIterMut<Value> {
-        WildCardIterMut::new(self.root.as_ref_mut(), pat, self.len())
+    pub fn wildcard_iter_mut(&mut self, pat: &str) -> WildCardIterMut<'_, Value> {
+        let len = self.len();
+        WildCardIterMut::new(&mut self.root, pat, len)
     }
 
     /// Method returns iterator over all values with common prefix `pref` in the `TSTMap`.
@@ -306,11 +428,32 @@ impl<Value> TSTMap<Value> {
     /// }
     ///
     /// ```
-    pub fn prefix_iter(&self, pref: &str) -> Iter<Value> {
-        let node = traverse::search(self.root.as_ref(), pref);
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_, Value> {
+        let node = traverse::search(&self.root, pref.chars());
         Iter::with_prefix(node, pref, self.len())
     }
 
+    /// Method returns iterator over all values with common prefix in the `TSTMap`,
+    /// the prefix is given as a sequence of chars.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 2);
+    /// m.insert("add", 3);
+    ///
+    /// let buf = vec!['a', 'b', 'c'];
+    /// let keys: Vec<String> = m.prefix_iter_chars(buf).map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["abc", "abcd"], keys);
+    /// ```
+    pub fn prefix_iter_chars<I: IntoIterator<Item = char>>(&self, pref: I) -> Iter<'_, Value> {
+        let mut prefix = String::new();
+        let node = traverse::search(&self.root, pref.into_iter().inspect(|&ch| prefix.push(ch)));
+        Iter::with_prefix(node, &prefix, self.len())
+    }
+
     /// Method returns mutable iterator over all values with common prefix `pref` in the `TSTMap`.
     /// # Examples
     ///
@@ -331,12 +474,58 @@ impl<Value> TSTMap<Value> {
     /// assert_eq!(101, m["abc"]);
     /// assert_eq!(101, m["abcdef"]);
     /// ```
-    pub fn prefix_iter_mut(&mut self, pref: &str) -> IterMut<Value> {
+    pub fn prefix_iter_mut(&mut self, pref: &str) -> IterMut<'_, Value> {
         let len = self.len();
-        let node = traverse::search(self.root.as_ref(), pref);
+        let node = traverse::search_mut(&mut self.root, pref);
         IterMut::with_prefix(node, pref, len)
     }
 
+    /// Gets a `Cursor` at the empty key, which moves down and up the trie one char at a time,
+    /// so a key typed char by char isn't searched from the root again for each char.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("tea", 1);
+    /// m.insert("ten", 2);
+    /// m.insert("to", 3);
+    ///
+    /// let mut cursor = m.cursor();
+    /// cursor.push('t');
+    /// cursor.push('e');
+    /// let keys: Vec<String> = cursor.completions().map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["tea", "ten"], keys);
+    /// ```
+    pub fn cursor(&self) -> Cursor<'_, Value> {
+        Cursor::new(&self.root, self.len())
+    }
+
+    /// Gets a `CursorMut` at the first entry, which walks the map in order of keys
+    /// and can change, remove and insert entries on the way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("old", 1);
+    /// m.insert("new", 2);
+    ///
+    /// let mut cursor = m.cursor_mut();
+    /// assert_eq!(Some("new"), cursor.key());
+    /// cursor.move_next();
+    /// cursor.remove_current();
+    /// drop(cursor);
+    /// assert_eq!(vec!["new"], m.keys().collect::<Vec<_>>());
+    /// ```
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, Value> {
+        CursorMut::new(&mut self.root, &mut self.size)
+    }
+
     /// Gets an iterator over the entries of the TSTMap.
     ///
     /// # Examples
@@ -356,9 +545,9 @@ impl<Value> TSTMap<Value> {
     /// let (first_key, first_value) = m.iter().next().unwrap();
     /// assert_eq!((first_key, *first_value), ("abc".to_string(), 1));
     /// ```
-    pub fn iter(&self) -> Iter<Value> {
+    pub fn iter(&self) -> Iter<'_, Value> {
         let len = self.len();
-        Iter::new(self.root.as_ref(), len, len)
+        Iter::new(&self.root, len, len)
     }
 
     /// Gets a mutable iterator over the entries of the `TSTMap`.
@@ -381,9 +570,152 @@ impl<Value> TSTMap<Value> {
     /// assert_eq!(1, m["a"]);
     /// assert_eq!(12, m["b"]);
     /// ```
-    pub fn iter_mut(&mut self) -> IterMut<Value> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, Value> {
         let len = self.len();
-        IterMut::new(self.root.as_ref_mut(), len, len)
+        IterMut::new(&mut self.root, len, len)
+    }
+
+    /// Calls `f` for every entry in order of keys. Unlike `iter`, it doesn't allocate a `String`
+    /// per key: keys are made in one buffer, which is lent to `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("ab", 2);
+    /// m.insert("b", 3);
+    ///
+    /// let mut longest = String::new();
+    /// m.for_each(|key, _| if key.len() > longest.len() { longest = key.to_string() });
+    /// assert_eq!("ab", longest);
+    /// ```
+    pub fn for_each<'x, F: FnMut(&str, &'x Value)>(&'x self, mut f: F) {
+        let _ = self.try_for_each(|key, value| -> ControlFlow<()> {
+            f(key, value);
+            ControlFlow::Continue(())
+        });
+    }
+
+    /// Calls `f` for entries in order of keys until it returns `ControlFlow::Break`,
+    /// returns what it broke with. Keys are made in one buffer, which is lent to `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", -2);
+    /// m.insert("c", 3);
+    ///
+    /// let negative = m.try_for_each(|key, value| {
+    ///     if *value < 0 { ControlFlow::Break(key.to_string()) } else { ControlFlow::Continue(()) }
+    /// });
+    /// assert_eq!(ControlFlow::Break("b".to_string()), negative);
+    /// ```
+    pub fn try_for_each<'x, B, F>(&'x self, f: F) -> ControlFlow<B>
+        where F: FnMut(&str, &'x Value) -> ControlFlow<B>
+    {
+        traverse::visit(Some(&self.root), "", None, f)
+    }
+
+    /// Calls `f` for every entry, which key starts with `pref`, in order of keys.
+    /// Keys are made in one buffer, which is lent to `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.insert("b", 3);
+    ///
+    /// let mut count = 0;
+    /// m.prefix_for_each("ab", |_, _| count += 1);
+    /// assert_eq!(2, count);
+    /// ```
+    pub fn prefix_for_each<'x, F: FnMut(&str, &'x Value)>(&'x self, pref: &str, mut f: F) {
+        let _ = self.try_prefix_for_each(pref, |key, value| -> ControlFlow<()> {
+            f(key, value);
+            ControlFlow::Continue(())
+        });
+    }
+
+    /// Calls `f` for entries, which keys start with `pref`, in order of keys until it returns
+    /// `ControlFlow::Break`, returns what it broke with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    ///
+    /// let first = m.try_prefix_for_each("ab", |key, _| ControlFlow::Break(key.to_string()));
+    /// assert_eq!(ControlFlow::Break("abc".to_string()), first);
+    /// ```
+    pub fn try_prefix_for_each<'x, B, F>(&'x self, pref: &str, f: F) -> ControlFlow<B>
+        where F: FnMut(&str, &'x Value) -> ControlFlow<B>
+    {
+        traverse::visit(traverse::search(&self.root, pref.chars()), pref, None, f)
+    }
+
+    /// Calls `f` for every entry, which key matches the wildcard pattern `pat`, in order of keys.
+    /// `.` in `pat` matches any char. Keys are made in one buffer, which is lent to `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("bbc", 2);
+    /// m.insert("abcd", 3);
+    ///
+    /// let mut keys = vec![];
+    /// m.wildcard_for_each(".bc", |key, _| keys.push(key.to_string()));
+    /// assert_eq!(vec!["abc", "bbc"], keys);
+    /// ```
+    pub fn wildcard_for_each<'x, F: FnMut(&str, &'x Value)>(&'x self, pat: &str, mut f: F) {
+        let _ = self.try_wildcard_for_each(pat, |key, value| -> ControlFlow<()> {
+            f(key, value);
+            ControlFlow::Continue(())
+        });
+    }
+
+    /// Calls `f` for entries, which keys match the wildcard pattern `pat`, in order of keys
+    /// until it returns `ControlFlow::Break`, returns what it broke with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("bbc", 2);
+    ///
+    /// let found = m.try_wildcard_for_each(".bc", |_, value| {
+    ///     if *value > 1 { ControlFlow::Break(*value) } else { ControlFlow::Continue(()) }
+    /// });
+    /// assert_eq!(ControlFlow::Break(2), found);
+    /// ```
+    pub fn try_wildcard_for_each<'x, B, F>(&'x self, pat: &str, f: F) -> ControlFlow<B>
+        where F: FnMut(&str, &'x Value) -> ControlFlow<B>
+    {
+        traverse::visit(Some(&self.root), "", Some(pat), f)
     }
 
     /// An iterator visiting all keys in arbitrary order.
@@ -403,7 +735,7 @@ impl<Value> TSTMap<Value> {
     ///     println!("{}", key);
     /// }
     /// ```
-    pub fn keys(&self) -> KeysIter<Value> {
+    pub fn keys(&self) -> KeysIter<'_, Value> {
         fn first<A, B>((k, _): (A, B)) -> A { k }
         KeysIter { iter: self.iter().map(first) }
     }
@@ -425,8 +757,8 @@ impl<Value> TSTMap<Value> {
     ///     println!("{}", value);
     /// }
     /// ```
-    pub fn values(&self) -> ValuesIter<Value> {
-        ValuesIter { iter: ValuesTraverse::new(self.root.as_ref(), self.len(), self.len()) }
+    pub fn values(&self) -> ValuesIter<'_, Value> {
+        ValuesIter { iter: ValuesTraverse::new(&self.root, self.len(), self.len()) }
     }
 }
 
@@ -449,7 +781,283 @@ impl<'x, Value: 'x> TSTMap<Value> {
     /// assert_eq!("abcd", m.longest_prefix("abcde"));
     /// ```
     pub fn longest_prefix(&self, pref: &'x str) -> &'x str {
-        traverse::longest_prefix(self.root.as_ref(), pref)
+        traverse::longest_prefix(&self.root, pref)
+    }
+
+    /// Writes the nodes of the trie as a graph in the DOT format of Graphviz, to see its
+    /// actual shape. `lt` links are blue, `eq` links are black and `gt` links are red,
+    /// nodes with values are double circles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 1);
+    /// m.insert("b", 2);
+    ///
+    /// let mut dot = Vec::new();
+    /// m.to_dot(&mut dot).unwrap();
+    /// let dot = String::from_utf8(dot).unwrap();
+    /// assert!(dot.starts_with("digraph tst {"));
+    /// assert!(dot.contains("[label=\"gt\", color=red, fontcolor=red]"));
+    /// ```
+    pub fn to_dot<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.to_dot_filtered(writer, "", None)
+    }
+
+    /// Like `to_dot`, but writes only the nodes below the key `prefix` and
+    /// no deeper than `max_depth` links below it. The graph is empty
+    /// if there is no such prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.insert("b", 3);
+    ///
+    /// let mut dot = Vec::new();
+    /// m.to_dot_filtered(&mut dot, "ab", Some(1)).unwrap();
+    /// let dot = String::from_utf8(dot).unwrap();
+    /// assert!(dot.contains("n0 [label=\"ab\"]"));
+    /// assert!(dot.contains("n1 [label=\"c\", shape=doublecircle"));
+    /// // "d" is two links below "ab"
+    /// assert!(!dot.contains("n2"));
+    /// ```
+    pub fn to_dot_filtered<W: Write>(&self, writer: &mut W, prefix: &str, max_depth: Option<usize>) -> io::Result<()> {
+        match traverse::search(&self.root, prefix.chars()) {
+            Some(node) => dot::write(node, prefix, max_depth, writer),
+            None => writeln!(writer, "digraph tst {{\n}}"),
+        }
+    }
+
+    /// Rebuilds the binary search tree of every character level into a balanced one,
+    /// so lookups don't walk long `lt`/`gt` chains left by near-sorted inserts.
+    /// Nodes are only relinked, not reallocated, keys and values stay the same.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// for (i, key) in ["a", "b", "c", "d", "e"].iter().enumerate() {
+    ///     m.insert(key, i);
+    /// }
+    ///
+    /// m.rebalance();
+    /// assert_eq!(5, m.len());
+    /// assert_eq!(Some(&4), m.get("e"));
+    /// ```
+    pub fn rebalance(&mut self) {
+        traverse::rebalance(&mut self.root)
+    }
+
+    /// Converts the `TSTMap` into read-only `FrozenTSTMap`, which keeps the same trie
+    /// in flat arrays and takes a fraction of the memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    ///
+    /// let m = m.freeze();
+    /// assert_eq!(Some(&2), m.get("abd"));
+    /// assert_eq!(2, m.prefix_iter("ab").count());
+    /// ```
+    pub fn freeze(mut self) -> FrozenTSTMap<Value> {
+        FrozenTSTMap::from_root(mem::take(&mut self.root), self.size)
+    }
+
+    // inserts sorted `items` median-first, so every level's lt/gt tree comes out balanced
+    fn insert_balanced<K: AsRef<str>>(&mut self, items: Vec<(K, Value)>) {
+        let mut items: Vec<Option<(K, Value)>> = items.into_iter().map(Some).collect();
+        let mut ranges = vec![(0, items.len())];
+
+        while let Some((lo, hi)) = ranges.pop() {
+            if lo >= hi {
+                continue;
+            }
+            let mid = lo + (hi - lo) / 2;
+            let (key, value) = items[mid].take().unwrap();
+            self.insert(key.as_ref(), value);
+            ranges.push((mid + 1, hi));
+            ranges.push((lo, mid));
+        }
+    }
+}
+
+impl<Value: HeapSize> TSTMap<Value> {
+    /// Returns statistics of the trie shape and its estimated memory use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 1);
+    /// m.insert("b", 2);
+    ///
+    /// let stats = m.stats();
+    /// assert_eq!(3, stats.nodes);
+    /// assert_eq!(2, stats.values);
+    /// assert_eq!(vec![0, 1, 1], stats.key_lengths);
+    /// // "b" is behind the "a" node on the first level
+    /// assert_eq!(vec![2, 1], stats.chain_lengths);
+    /// ```
+    pub fn stats(&self) -> Stats {
+        stats::collect(&self.root)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<Value: Sync> TSTMap<Value> {
+    /// Gets a parallel iterator over the entries of the `TSTMap`, enabled by the `rayon` feature.
+    /// Subtrees are handed to other threads, each entry comes with its whole key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    /// use tst::TSTMap;
+    ///
+    /// let m: TSTMap<usize> = (0..1000).map(|i| (format!("key{}", i), i))
+    ///     .collect::<Vec<_>>().iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    ///
+    /// let sum: usize = m.par_iter().map(|(_, value)| *value).sum();
+    /// assert_eq!(499500, sum);
+    /// let keys: Vec<String> = m.par_iter().map(|(key, _)| key).collect();
+    /// assert_eq!(m.keys().collect::<Vec<_>>(), keys);
+    /// ```
+    pub fn par_iter(&self) -> ParIter<'_, Value> {
+        ParIter::new(Some(&self.root), "")
+    }
+
+    /// Gets a parallel iterator over the entries with keys starting with `pref`,
+    /// enabled by the `rayon` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.insert("abde", 3);
+    /// m.insert("b", 4);
+    ///
+    /// let mut keys: Vec<String> = m.par_prefix_iter("abd").map(|(key, _)| key).collect();
+    /// keys.sort();
+    /// assert_eq!(vec!["abd", "abde"], keys);
+    /// ```
+    pub fn par_prefix_iter(&self, pref: &str) -> ParIter<'_, Value> {
+        ParIter::new(traverse::search(&self.root, pref.chars()), pref)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<Value: Send> TSTMap<Value> {
+    /// Gets a parallel iterator over the entries of the `TSTMap` with mutable references
+    /// to values, enabled by the `rayon` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    ///
+    /// m.par_iter_mut().for_each(|(_, value)| *value *= 10);
+    /// assert_eq!(Some(&20), m.get("b"));
+    /// ```
+    pub fn par_iter_mut(&mut self) -> ParIterMut<'_, Value> {
+        ParIterMut::new(&mut self.root)
+    }
+}
+
+impl<Value: ValueCodec> TSTMap<Value> {
+    /// Writes the `TSTMap` to `writer` in a binary snapshot format, which keeps
+    /// the node structure, so `read_snapshot` doesn't need to insert keys one by one.
+    ///
+    /// Format, all numbers are little-endian:
+    ///
+    /// - magic `b"TSTM"`, format version `u16` (now `1`) and number of values `u64`;
+    /// - nodes in pre-order (node, then its lt, eq and gt subtrees), the root node goes first.
+    ///   Each node is a flags byte (`1` - has lt, `2` - has eq, `4` - has gt, `8` - has value),
+    ///   its char as `u32` and, if it has a value, `u32` length and bytes made by `ValueCodec::encode`;
+    /// - CRC-32 (IEEE) `u32` of all the bytes before it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    ///
+    /// let mut buf = Vec::new();
+    /// m.write_snapshot(&mut buf).unwrap();
+    /// assert_eq!(b"TSTM", &buf[..4]);
+    /// ```
+    pub fn write_snapshot<W: Write>(&self, writer: &mut W) -> Result<(), SnapshotError> {
+        snapshot::write(&self.root, self.size, writer)
+    }
+
+    /// Reads a `TSTMap` from a snapshot made by `write_snapshot`.
+    ///
+    /// Returns an error, if the input is truncated, corrupted or was written in
+    /// another format version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, SnapshotError};
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    ///
+    /// let mut buf = Vec::new();
+    /// m.write_snapshot(&mut buf).unwrap();
+    /// let copy = TSTMap::<i32>::read_snapshot(&buf[..]).unwrap();
+    /// assert_eq!(Some(&2), copy.get("abd"));
+    ///
+    /// buf.pop();
+    /// assert!(matches!(TSTMap::<i32>::read_snapshot(&buf[..]), Err(SnapshotError::Truncated)));
+    /// ```
+    pub fn read_snapshot<R: Read>(reader: R) -> Result<Self, SnapshotError> {
+        let (root, size) = snapshot::read(reader)?;
+        Ok(TSTMap { root, size })
+    }
+
+    /// Writes the `TSTMap` in the format of `MappedTSTMap`, which can be used
+    /// right from the written bytes or a memory-mapped file without loading.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1u32);
+    /// m.insert("abd", 2);
+    ///
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    /// let mapped = MappedTSTMap::new(&bytes[..]).unwrap();
+    /// assert_eq!(Some(&2u32.to_le_bytes()[..]), mapped.get("abd"));
+    /// ```
+    pub fn write_mapped<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        mapped::write(&self.root, self.size, writer)
     }
 }
 
@@ -497,7 +1105,58 @@ impl<'x, Value> Extend<(&'x str, Value)> for TSTMap<Value> {
     }
 }
 
-impl<'x, Value> ops::Index<&'x str> for TSTMap<Value> {
+#[cfg(feature = "rayon")]
+impl<'x, Value: Send> ParallelExtend<(&'x str, Value)> for TSTMap<Value> {
+    /// Inserts entries of a parallel iterator, keys with different first chars are
+    /// inserted in parallel. Like `extend`, later entries of a key win.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 0);
+    /// m.par_extend(vec![("a", 1), ("b", 2), ("a", 3)]);
+    /// assert_eq!(2, m.len());
+    /// assert_eq!(Some(&3), m.get("a"));
+    /// ```
+    fn par_extend<I: IntoParallelIterator<Item=(&'x str, Value)>>(&mut self, par_iter: I) {
+        self.size += par::extend(&mut self.root, par_iter.into_par_iter().collect());
+    }
+}
+
+impl<Value: PartialEq> PartialEq for TSTMap<Value> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<Value: Eq> Eq for TSTMap<Value> {}
+
+impl<Value: Hash> Hash for TSTMap<Value> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for entry in self.iter() {
+            entry.hash(state);
+        }
+    }
+}
+
+impl<Value: PartialOrd> PartialOrd for TSTMap<Value> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<Value: Ord> Ord for TSTMap<Value> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<Value> ops::Index<&str> for TSTMap<Value> {
     type Output = Value;
     #[inline]
     fn index(&self, idx: &str) -> &Value {
@@ -505,7 +1164,7 @@ impl<'x, Value> ops::Index<&'x str> for TSTMap<Value> {
     }
 }
 
-impl<'x, Value> ops::IndexMut<&'x str> for TSTMap<Value> {
+impl<Value> ops::IndexMut<&str> for TSTMap<Value> {
     #[inline]
     fn index_mut(&mut self, idx: &str) -> &mut Value {
         self.get_mut(idx).expect("no entry found for key")
@@ -514,9 +1173,9 @@ impl<'x, Value> ops::IndexMut<&'x str> for TSTMap<Value> {
 
 impl<Value> Drop for TSTMap<Value> {
     fn drop(&mut self) {
-        let root = self.root.take();
+        let root = self.root.eq.take();
         let mut iter = DropTraverse::new(root);
-        while let Some(_) = iter.next() { }
+        while iter.next().is_some() { }
     }
 }
 
@@ -549,16 +1208,16 @@ impl<Value> Default for TSTMap<Value> {
 /// `TSTMap` iterator.
 #[derive(Clone, Default)]
 pub struct Iter<'x, Value: 'x> {
-    iter: Traverse<'x, Value>,
+    iter: Traverse<&'x Node<Value>>,
 }
 
 impl<'x, Value> Iter<'x, Value> {
-    fn new(node: NodeRef<'x, Value>, min: usize, max: usize) -> Self {
+    fn new(node: &'x Node<Value>, min: usize, max: usize) -> Self {
         Iter {
             iter: Traverse::new(node, min, max),
         }
     }
-    fn with_prefix(node: Option<&'x Node<Value>>, prefix: &str, max: usize) -> Self {
+    pub(crate) fn with_prefix(node: Option<&'x Node<Value>>, prefix: &str, max: usize) -> Self {
         Iter {
             iter: Traverse::with_prefix(node, prefix, max),
         }
@@ -576,18 +1235,18 @@ impl<'x, Value> Iterator for Iter<'x, Value> {
 }
 
 /// `TSTMap` mutable iterator.
-#[derive(Clone, Default)]
+#[derive(Default)]
 pub struct IterMut<'x, Value: 'x> {
-    iter: Traverse<'x, Value>,
+    iter: Traverse<&'x mut Node<Value>>,
 }
 
 impl<'x, Value> IterMut<'x, Value> {
-    fn new(node: NodeRefMut<'x, Value>, min: usize, max: usize) -> Self {
+    fn new(node: &'x mut Node<Value>, min: usize, max: usize) -> Self {
         IterMut {
-            iter: Traverse::new(node.into_immut(), min, max),
+            iter: Traverse::new(node, min, max),
         }
     }
-    fn with_prefix(ptr: Option<&'x Node<Value>>, prefix: &str, max: usize) -> Self {
+    fn with_prefix(ptr: Option<&'x mut Node<Value>>, prefix: &str, max: usize) -> Self {
         IterMut {
             iter: Traverse::with_prefix(ptr, prefix, max),
         }
@@ -597,8 +1256,7 @@ impl<'x, Value> IterMut<'x, Value> {
 impl<'x, Value> Iterator for IterMut<'x, Value> {
     type Item = (String, &'x mut Value);
     fn next(&mut self) -> Option<(String, &'x mut Value)> {
-        // just add mut, avoid copy-paste
-        unsafe { mem::transmute(self.iter.next()) }
+        self.iter.next()
     }
     fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
 }
@@ -606,6 +1264,7 @@ impl<'x, Value> Iterator for IterMut<'x, Value> {
 /// `TSTMap` keys iterator
 #[derive(Clone)]
 pub struct KeysIter<'x, Value: 'x> {
+    #[allow(clippy::type_complexity)]
     iter: Map<Iter<'x, Value>, fn((String, &'x Value)) -> String>,
 }
 
@@ -630,11 +1289,11 @@ impl<'x, Value:'x> Iterator for ValuesIter<'x, Value> {
 /// `TSTMap` wild-card iterator.
 #[derive(Clone)]
 pub struct WildCardIter<'x, Value: 'x> {
-    iter: WildCardTraverse<'x, Value>,
+    iter: WildCardTraverse<&'x Node<Value>>,
 }
 
 impl<'x, Value> WildCardIter<'x, Value> {
-    fn new(node: NodeRef<'x, Value>, pat: &str, max: usize) -> Self {
+    fn new(node: &'x Node<Value>, pat: &str, max: usize) -> Self {
         WildCardIter {
             iter: WildCardTraverse::new(node, pat, max),
         }
@@ -648,22 +1307,21 @@ impl<'x, Value> Iterator for WildCardIter<'x, Value> {
 }
 
 /// `TSTMap` wild-card mutable iterator.
-#[derive(Clone)]
 pub struct WildCardIterMut<'x, Value: 'x> {
-    iter: WildCardTraverse<'x, Value>,
+    iter: WildCardTraverse<&'x mut Node<Value>>,
 }
 
 impl<'x, Value> WildCardIterMut<'x, Value> {
-    fn new(node: NodeRefMut<'x, Value>, pat: &str, max: usize) -> Self {
+    fn new(node: &'x mut Node<Value>, pat: &str, max: usize) -> Self {
         WildCardIterMut {
-            iter: WildCardTraverse::new(node.into_immut(), pat, max),
+            iter: WildCardTraverse::new(node, pat, max),
         }
     }
 }
 
 impl<'x, Value> Iterator for WildCardIterMut<'x, Value> {
     type Item = (String, &'x mut Value);
-    fn next(&mut self) -> Option<(String, &'x mut Value)> { unsafe { mem::transmute(self.iter.next()) } }
+    fn next(&mut self) -> Option<(String, &'x mut Value)> { self.iter.next() }
     fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
 }
 
@@ -675,7 +1333,7 @@ pub struct IntoIter<Value> {
 impl<Value> IntoIter<Value> {
     fn new(mut tst: TSTMap<Value>) -> Self {
         let size = tst.len();
-        let root = tst.root.take();
+        let root = mem::take(&mut tst.root);
         IntoIter {
             iter: IntoTraverse::new(root, size),
         }
@@ -701,13 +1359,13 @@ impl<Value> ExactSizeIterator for IntoIter<Value> {
 
 /// A view into a single occupied location in a `TSTMap`.
 pub struct OccupiedEntry<'x, Value: 'x> {
-    node: &'x mut Node<Value>,
+    value: &'x mut Option<Value>,
     cont_size: &'x mut usize,
 }
 
 /// A view into a single empty location in a `TSTMap`.
 pub struct VacantEntry<'x, Value: 'x> {
-    node: &'x mut Node<Value>,
+    value: &'x mut Option<Value>,
     cont_size: &'x mut usize,
 }
 
@@ -720,10 +1378,10 @@ pub enum Entry<'x, Value: 'x> {
 }
 
 impl<'x, Value> Entry<'x, Value> {
-    fn new(node: &'x mut Node<Value>, size: &'x mut usize) -> Self {
-        match node.value {
-            None => Vacant(VacantEntry::new(node, size)),
-            Some(_) => Occupied(OccupiedEntry::new(node, size)),
+    pub(crate) fn new(value: &'x mut Option<Value>, size: &'x mut usize) -> Self {
+        match *value {
+            None => Vacant(VacantEntry::new(value, size)),
+            Some(_) => Occupied(OccupiedEntry::new(value, size)),
         }
     }
     /// Gets a mut reference to the value in the entry or Err in case for Vacant.
@@ -752,49 +1410,48 @@ impl<'x, Value> Entry<'x, Value> {
 }
 
 impl<'x, Value> OccupiedEntry<'x, Value> {
-    fn new(node: &'x mut Node<Value>, size: &'x mut usize) -> Self {
+    fn new(value: &'x mut Option<Value>, size: &'x mut usize) -> Self {
         OccupiedEntry {
-            node,
+            value,
             cont_size: size,
         }
     }
     /// Gets a reference to the value in the entry.
     pub fn get(&self) -> &Value {
-        self.node.value.as_ref().unwrap()
+        self.value.as_ref().unwrap()
     }
     /// Gets a mutable reference to the value in the entry.
     pub fn get_mut(&mut self) -> &mut Value {
-        self.node.value.as_mut().unwrap()
+        self.value.as_mut().unwrap()
     }
     /// Converts the OccupiedEntry into a mutable reference to the value in the entry
     /// with a lifetime bound to the TSTMap itself
     pub fn into_mut(self) -> &'x mut Value {
-        self.node.value.as_mut().unwrap()
+        self.value.as_mut().unwrap()
     }
     /// Sets the `value` of the entry, and returns the entry's old value
     pub fn insert(&mut self, value: Value) -> Value {
-        self.node.replace(Some(value)).unwrap()
+        self.value.replace(value).unwrap()
     }
     /// Takes the value out of the entry, and returns it
     pub fn remove(self) -> Value {
         *self.cont_size -= 1;
-        self.node.replace(None).unwrap()
+        self.value.take().unwrap()
     }
 }
 
 impl<'x, Value> VacantEntry<'x, Value> {
-    fn new(node: &'x mut Node<Value>, size: &'x mut usize) -> Self {
+    fn new(value: &'x mut Option<Value>, size: &'x mut usize) -> Self {
         VacantEntry {
-            node,
+            value,
             cont_size: size,
         }
     }
     /// Sets the `value` of the entry with the VacantEntry's key,
     /// and returns a mutable reference to it
     pub fn insert(self, value: Value) -> &'x mut Value {
-        self.node.value = Some(value);
         *self.cont_size += 1;
-        self.node.value.as_mut().unwrap()
+        self.value.insert(value)
     }
 }
 
@@ -811,6 +1468,85 @@ mod test {
         m.remove("BY");
         m.remove("BYE");
         m.remove("BYGONE");
-        assert_eq!(None, m.root.ptr);
+        assert_eq!(None, m.root.eq.ptr);
+    }
+
+    use super::Node;
+    use crate::traverse;
+
+    // height of the lt/gt tree of a level
+    fn level_height<Value>(top: &Node<Value>) -> usize {
+        let mut level = vec![(top, 1)];
+        let mut height = 0;
+        while let Some((node, depth)) = level.pop() {
+            height = height.max(depth);
+            level.extend(node.lt.ptr.as_deref().map(|n| (n, depth + 1)));
+            level.extend(node.gt.ptr.as_deref().map(|n| (n, depth + 1)));
+        }
+        height
+    }
+
+    #[test]
+    fn rebalance_makes_levels_balanced() {
+        let mut m = super::TSTMap::new();
+        for c in 'a'..='z' {
+            m.insert(&c.to_string(), c);
+        }
+        for c in 'a'..='z' {
+            m.insert(&format!("x{}", c), c);
+        }
+        let x_level = |m: &super::TSTMap<char>| {
+            let x = traverse::search(&m.root, "x".chars()).unwrap();
+            level_height(x.eq.ptr.as_deref().unwrap())
+        };
+        assert_eq!(26, level_height(m.root.eq.ptr.as_deref().unwrap()));
+        assert_eq!(26, x_level(&m));
+        let first: *const Node<char> = m.root.eq.ptr.as_deref().unwrap();
+
+        m.rebalance();
+        assert_eq!(5, level_height(m.root.eq.ptr.as_deref().unwrap()));
+        assert_eq!(5, x_level(&m));
+        assert_eq!(52, m.len());
+        // nodes are relinked, not reallocated
+        assert!(std::ptr::eq(first, traverse::search(&m.root, "a".chars()).unwrap()));
+    }
+
+    #[test]
+    fn insert_balanced_keeps_levels_balanced() {
+        let mut m = super::TSTMap::new();
+        let keys: Vec<(String, usize)> = (b'a'..=b'z')
+            .map(|c| ((c as char).to_string(), c as usize))
+            .collect();
+        m.insert_balanced(keys);
+
+        // 26 keys on one level fit into a tree of height 5
+        assert_eq!(5, level_height(m.root.eq.ptr.as_deref().unwrap()));
+        assert_eq!(26, m.len());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn deep_nodes_clone_compare_and_format() {
+        let key: String = "ACGT".repeat(250_000);
+        let mut m = super::TSTMap::new();
+        m.insert(&key, 1);
+        m.insert("ACGA", 2);
+
+        let copy = m.root.clone();
+        assert!(copy == m.root);
+        let text = format!("{:?}", copy);
+        assert!(text.starts_with("{c = '\\0', val = None, lt = None, eq = {c = 'A', val = None"));
+        assert_eq!(key.len() + 2, text.matches("{c = ").count());
+
+        // the same entries in another shape are different nodes
+        let mut other = super::TSTMap::new();
+        other.insert("ACGA", 2);
+        other.insert(&key, 1);
+        assert!(other.root != m.root);
+        assert!(other == m);
+
+        // a bare node is dropped recursively, maps drain theirs
+        let mut iter = traverse::DropTraverse::new(Some(Box::new(copy)));
+        while iter.next().is_some() { }
     }
 }
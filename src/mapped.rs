@@ -0,0 +1,624 @@
+use std::io::{self, Write, BufWriter};
+use std::fmt::{self, Debug};
+use std::cmp::Ordering;
+use super::node::Node;
+use super::snapshot::{ValueCodec, SnapshotError};
+
+pub(crate) const MAGIC: &[u8; 4] = b"TSTD";
+pub(crate) const VERSION: u16 = 1;
+pub(crate) const HEADER_LEN: usize = 8;
+pub(crate) const FOOTER_LEN: usize = 16;
+// set in the char field of a record, when a value follows the links
+pub(crate) const HAS_VALUE: u32 = 1 << 31;
+
+///
+/// Read-only symbol table with string keys, which works right on top of bytes
+/// made by [`TSTMap::write_mapped`](crate::TSTMap::write_mapped) or
+/// [`TSTBuilder`](crate::TSTBuilder), without loading them into memory.
+///
+/// Bytes can be any `AsRef<[u8]>`: a slice, a `Vec<u8>` or a memory-mapped file,
+/// so pages of a big dictionary are shared by all processes, which map it.
+/// Values are returned as raw bytes made by `ValueCodec::encode`.
+///
+/// Format, all numbers are little-endian and the whole file is 4-byte aligned:
+///
+/// - header: magic `b"TSTD"`, format version `u16` (now `1`) and 2 reserved bytes;
+/// - node records in post-order, so links always point back. A record is the char `u32`
+///   (bit 31 is set, if the node has a value), lt, eq and gt links `u32` as offsets
+///   in 4-byte words from the start (`0` - no link) and, if the node has a value,
+///   its length `u32` and bytes padded to 4 bytes;
+/// - footer: offset of the root record `u32`, 4 reserved bytes and number of values `u64`.
+///   The root record keeps value of the empty key and the trie in its eq link.
+///
+/// Only header and footer are checked on open. Records are checked on access, so broken
+/// bytes can give wrong answers, but never a panic or an endless loop.
+/// # Examples
+///
+/// ```rust
+/// use tst::{TSTMap, MappedTSTMap, ValueCodec};
+///
+/// let mut m = TSTMap::new();
+/// m.insert("first", 1u32);
+/// m.insert("second", 2);
+/// m.insert("firstthird", 3);
+///
+/// let mut bytes = Vec::new();
+/// m.write_mapped(&mut bytes).unwrap();
+///
+/// let m = MappedTSTMap::new(&bytes[..]).unwrap();
+/// assert_eq!(Some(1), m.get("first").and_then(u32::decode));
+/// assert_eq!(3, m.len());
+/// assert_eq!("first", m.longest_prefix("firstsecond"));
+/// assert_eq!(2, m.prefix_iter("fir").count());
+/// ```
+pub struct MappedTSTMap<Bytes> {
+    bytes: Bytes,
+    root: u32,
+    len: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Record<'x> {
+    c: char,
+    lt: Option<u32>,
+    eq: Option<u32>,
+    gt: Option<u32>,
+    value: Option<&'x [u8]>,
+}
+
+// record at `offset`, links of a record point only to records before it
+fn record(data: &[u8], offset: u32) -> Option<Record<'_>> {
+    let start = offset as usize * 4;
+    let word = |i: usize| -> Option<u32> {
+        let bytes = data.get(start + i * 4..start + i * 4 + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let link = |i: usize| -> Option<Option<u32>> {
+        let link = word(i)?;
+        Some(if link != 0 && link < offset { Some(link) } else { None })
+    };
+    let c = word(0)?;
+    let value = if c & HAS_VALUE != 0 {
+        let len = word(4)? as usize;
+        Some(data.get(start + 20..(start + 20).checked_add(len)?)?)
+    } else {
+        None
+    };
+    Some(Record {
+        c: char::from_u32(c & !HAS_VALUE)?,
+        lt: link(1)?,
+        eq: link(2)?,
+        gt: link(3)?,
+        value,
+    })
+}
+
+impl<Bytes: AsRef<[u8]>> MappedTSTMap<Bytes> {
+    /// Opens a `MappedTSTMap` on top of `bytes`, checks header and footer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap, SnapshotError};
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1u8);
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// assert!(MappedTSTMap::new(&bytes[..]).is_ok());
+    /// assert!(matches!(MappedTSTMap::new(&bytes[1..]), Err(SnapshotError::BadMagic)));
+    /// ```
+    pub fn new(bytes: Bytes) -> Result<Self, SnapshotError> {
+        let data = bytes.as_ref();
+        if data.len() < HEADER_LEN + FOOTER_LEN {
+            return Err(if data.len() >= 4 && &data[..4] != MAGIC {
+                SnapshotError::BadMagic
+            } else {
+                SnapshotError::Truncated
+            });
+        }
+        if &data[..4] != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        if data.len() % 4 != 0 {
+            return Err(SnapshotError::Corrupted("length is not aligned"));
+        }
+        let footer = &data[data.len() - FOOTER_LEN..];
+        let root = u32::from_le_bytes(footer[..4].try_into().unwrap());
+        let len = u64::from_le_bytes(footer[8..].try_into().unwrap());
+        let footer_offset = (data.len() - FOOTER_LEN) / 4;
+        if (root as usize) < HEADER_LEN / 4 || root as usize >= footer_offset {
+            return Err(SnapshotError::Corrupted("root offset is out of range"));
+        }
+        let len = usize::try_from(len).map_err(|_| SnapshotError::Corrupted("too many values"))?;
+        let map = MappedTSTMap { bytes, root, len };
+        if map.record(root).is_none() {
+            return Err(SnapshotError::Corrupted("root record is broken"));
+        }
+        Ok(map)
+    }
+
+    /// Returns the number of elements in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 2u8);
+    /// m.insert("x", 1);
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(2, MappedTSTMap::new(bytes).unwrap().len());
+    /// ```
+    pub fn len(&self) -> usize { self.len }
+
+    /// Returns true if the `MappedTSTMap` contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    ///
+    /// let m: TSTMap<u8> = TSTMap::new();
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// assert!(MappedTSTMap::new(bytes).unwrap().is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Returns bytes of the value corresponding to the `key` or None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("first", "1st".to_string());
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// let m = MappedTSTMap::new(bytes).unwrap();
+    /// assert_eq!(Some(&b"1st"[..]), m.get("first"));
+    /// assert_eq!(None, m.get("second"));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.search(key)?.value
+    }
+
+    /// Returns true if the `MappedTSTMap` contains a value for the specified `key`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", ());
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// let m = MappedTSTMap::new(bytes).unwrap();
+    /// assert!(!m.contains_key("ab"));
+    /// assert!(m.contains_key("abc"))
+    /// ```
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Method returns longest prefix `pref` in the `MappedTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1u8);
+    /// m.insert("abcd", 1);
+    /// m.insert("abcdef", 1);
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// assert_eq!("abcd", MappedTSTMap::new(bytes).unwrap().longest_prefix("abcde"));
+    /// ```
+    pub fn longest_prefix<'x>(&self, pref: &'x str) -> &'x str {
+        let mut length = 0;
+        let mut i = 0;
+        let mut cur = self.record(self.root).and_then(|root| root.eq);
+
+        for ch in pref.chars() {
+            let mut found = false;
+            while let Some(node) = cur.and_then(|offset| self.record(offset)) {
+                match ch.cmp(&node.c) {
+                    Ordering::Less => cur = node.lt,
+                    Ordering::Greater => cur = node.gt,
+                    Ordering::Equal => {
+                        i += ch.len_utf8();
+                        if node.value.is_some() { length = i; }
+                        cur = node.eq;
+                        found = true;
+                        break;
+                    }
+                }
+            }
+            if !found {
+                break;
+            }
+        }
+        &pref[..length]
+    }
+
+    /// An iterator returning all nodes matching wildcard pattern `pat`.
+    /// Iterator element type is (String, &[u8])
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1u8);
+    /// m.insert("b", 2);
+    /// m.insert("bc", 3);
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// let m = MappedTSTMap::new(bytes).unwrap();
+    /// assert_eq!(vec!["a", "b"], m.wildcard_iter(".").map(|(k, _)| k).collect::<Vec<_>>());
+    /// ```
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<'_> {
+        let data = self.bytes.as_ref();
+        let mut iter = WildCardIter {
+            data,
+            stack: vec![],
+            pat: pat.chars().collect(),
+        };
+        if let Some(root) = self.record(self.root) {
+            if iter.pat.is_empty() {
+                if let Some(value) = root.value {
+                    iter.stack.push(WildCardEntry::Value(String::new(), value));
+                }
+            } else if let Some(eq) = root.eq {
+                iter.stack.push(WildCardEntry::Node(String::new(), eq, 0));
+            }
+        }
+        iter
+    }
+
+    /// Method returns iterator over all values with common prefix `pref` in the `MappedTSTMap`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1u8);
+    /// m.insert("abcd", 1);
+    /// m.insert("add", 1);
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// let m = MappedTSTMap::new(bytes).unwrap();
+    /// let keys: Vec<String> = m.prefix_iter("ab").map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["abc", "abcd"], keys);
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_> {
+        let mut iter = Iter { data: self.bytes.as_ref(), stack: vec![] };
+        if let Some(node) = self.search(pref) {
+            if let Some(eq) = node.eq {
+                iter.stack.push(TraverseEntry::Node(pref.to_string(), eq));
+            }
+            if let Some(value) = node.value {
+                iter.stack.push(TraverseEntry::Value(pref.to_string(), value));
+            }
+        }
+        iter
+    }
+
+    /// Gets an iterator over the entries of the `MappedTSTMap` in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1u8);
+    /// m.insert("bbc", 2);
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// let m = MappedTSTMap::new(bytes).unwrap();
+    /// let (first_key, first_value) = m.iter().next().unwrap();
+    /// assert_eq!((first_key, first_value), ("abc".to_string(), &[1u8][..]));
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        self.prefix_iter("")
+    }
+
+    /// An iterator visiting all keys in alphabetical order.
+    /// Iterator element type is String
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{TSTMap, MappedTSTMap};
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", ());
+    /// m.insert("a", ());
+    /// let mut bytes = Vec::new();
+    /// m.write_mapped(&mut bytes).unwrap();
+    ///
+    /// let m = MappedTSTMap::new(bytes).unwrap();
+    /// assert_eq!(vec!["a", "b"], m.keys().collect::<Vec<String>>());
+    /// ```
+    pub fn keys(&self) -> KeysIter<'_> {
+        KeysIter { iter: self.iter() }
+    }
+
+    fn record(&self, offset: u32) -> Option<Record<'_>> {
+        record(self.bytes.as_ref(), offset)
+    }
+
+    // record with the last char of `key`, root record for empty key
+    fn search(&self, key: &str) -> Option<Record<'_>> {
+        let mut last = self.record(self.root)?;
+
+        for ch in key.chars() {
+            let mut cur = last.eq;
+            loop {
+                let node = self.record(cur?)?;
+                match ch.cmp(&node.c) {
+                    Ordering::Less => cur = node.lt,
+                    Ordering::Greater => cur = node.gt,
+                    Ordering::Equal => {
+                        last = node;
+                        break;
+                    }
+                }
+            }
+        }
+        Some(last)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl MappedTSTMap<memmap2::Mmap> {
+    /// Maps file at `path` into memory and opens a `MappedTSTMap` on top of it.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it's mapped,
+    /// see `memmap2::Mmap::map`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tst::MappedTSTMap;
+    ///
+    /// let m = unsafe { MappedTSTMap::open("words.tstd") }.unwrap();
+    /// println!("{}", m.len());
+    /// ```
+    pub unsafe fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, SnapshotError> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the caller guarantees nobody changes the file while it's mapped
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        MappedTSTMap::new(mmap)
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> Debug for MappedTSTMap<Bytes> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'x, Bytes: AsRef<[u8]>> IntoIterator for &'x MappedTSTMap<Bytes> {
+    type Item = (String, &'x [u8]);
+    type IntoIter = Iter<'x>;
+
+    fn into_iter(self) -> Iter<'x> {
+        self.iter()
+    }
+}
+
+// writes records of the `root` trie in post-order starting at `offset`,
+// returns offset of the root record and offset right after the written records
+pub(crate) fn write_records<Value: ValueCodec, W: Write>(root: &Node<Value>, mut offset: u32, out: &mut W) -> io::Result<(u32, u32)> {
+    // node, offsets of its lt, eq and gt records and the next link to visit
+    let mut stack = vec![(root, [0u32; 3], 0)];
+    let mut buf = Vec::new();
+
+    while let Some(top) = stack.last_mut() {
+        let (node, _, next) = *top;
+        if next < 3 {
+            top.2 += 1;
+            let link = [&node.lt, &node.eq, &node.gt][next];
+            if let Some(ref child) = link.ptr {
+                stack.push((child, [0; 3], 0));
+            }
+            continue;
+        }
+        let (node, links, _) = stack.pop().unwrap();
+        let value = match node.value {
+            Some(ref value) => {
+                encode_value(value, &mut buf)?;
+                Some(&buf[..])
+            }
+            None => None,
+        };
+        let written = offset;
+        offset = write_record(out, offset, node.c, links, value)?;
+        match stack.last_mut() {
+            Some(parent) => parent.1[parent.2 - 1] = written,
+            None => return Ok((written, offset)),
+        }
+    }
+    unreachable!()
+}
+
+// encodes `value` into `buf` as its length and bytes padded to 4 bytes
+pub(crate) fn encode_value<Value: ValueCodec>(value: &Value, buf: &mut Vec<u8>) -> io::Result<()> {
+    buf.clear();
+    buf.resize(4, 0);
+    value.encode(buf);
+    let len = u32::try_from(buf.len() - 4)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "encoded value is too large"))?;
+    buf[..4].copy_from_slice(&len.to_le_bytes());
+    buf.resize(buf.len().div_ceil(4) * 4, 0);
+    Ok(())
+}
+
+// writes a record at `offset`, `value` is made by `encode_value`,
+// returns offset right after the record
+pub(crate) fn write_record<W: Write>(out: &mut W, offset: u32, c: char, links: [u32; 3], value: Option<&[u8]>) -> io::Result<u32> {
+    let c = c as u32 | if value.is_some() { HAS_VALUE } else { 0 };
+    out.write_all(&c.to_le_bytes())?;
+    for link in links {
+        out.write_all(&link.to_le_bytes())?;
+    }
+    if let Some(value) = value {
+        out.write_all(value)?;
+    }
+    let words = 4 + value.map_or(0, |value| value.len() / 4);
+    offset.checked_add(words as u32)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "dictionary is too large"))
+}
+
+pub(crate) fn write_header<W: Write>(out: &mut W) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+    out.write_all(&[0, 0])
+}
+
+pub(crate) fn write_footer<W: Write>(out: &mut W, root: u32, len: u64) -> io::Result<()> {
+    out.write_all(&root.to_le_bytes())?;
+    out.write_all(&[0; 4])?;
+    out.write_all(&len.to_le_bytes())
+}
+
+pub(crate) fn write<Value: ValueCodec, W: Write>(root: &Node<Value>, size: usize, writer: W) -> io::Result<()> {
+    let mut out = BufWriter::new(writer);
+    write_header(&mut out)?;
+    let (root, _) = write_records(root, (HEADER_LEN / 4) as u32, &mut out)?;
+    write_footer(&mut out, root, size as u64)?;
+    out.flush()
+}
+
+//
+// iterators section
+//
+
+enum TraverseEntry<'x> {
+    Node(String, u32),
+    Value(String, &'x [u8]),
+}
+
+/// `MappedTSTMap` iterator.
+pub struct Iter<'x> {
+    data: &'x [u8],
+    stack: Vec<TraverseEntry<'x>>,
+}
+
+impl<'x> Iterator for Iter<'x> {
+    type Item = (String, &'x [u8]);
+    fn next(&mut self) -> Option<(String, &'x [u8])> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(key, value) => return Some((key, value)),
+                TraverseEntry::Node(prefix, offset) => {
+                    let cur = match record(self.data, offset) {
+                        Some(cur) => cur,
+                        None => continue,
+                    };
+                    if let Some(gt) = cur.gt {
+                        self.stack.push(TraverseEntry::Node(prefix.clone(), gt));
+                    }
+                    if cur.eq.is_some() || cur.value.is_some() {
+                        let mut key = String::with_capacity(prefix.len() + cur.c.len_utf8());
+                        key.push_str(&prefix);
+                        key.push(cur.c);
+                        if let Some(eq) = cur.eq {
+                            self.stack.push(TraverseEntry::Node(key.clone(), eq));
+                        }
+                        if let Some(value) = cur.value {
+                            self.stack.push(TraverseEntry::Value(key, value));
+                        }
+                    }
+                    if let Some(lt) = cur.lt {
+                        self.stack.push(TraverseEntry::Node(prefix, lt));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// `MappedTSTMap` keys iterator
+pub struct KeysIter<'x> {
+    iter: Iter<'x>,
+}
+
+impl<'x> Iterator for KeysIter<'x> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> { self.iter.next().map(|(k, _)| k) }
+}
+
+enum WildCardEntry<'x> {
+    Node(String, u32, usize),
+    Value(String, &'x [u8]),
+}
+
+/// `MappedTSTMap` wild-card iterator.
+pub struct WildCardIter<'x> {
+    data: &'x [u8],
+    stack: Vec<WildCardEntry<'x>>,
+    pat: Vec<char>,
+}
+
+impl<'x> Iterator for WildCardIter<'x> {
+    type Item = (String, &'x [u8]);
+    fn next(&mut self) -> Option<(String, &'x [u8])> {
+        while let Some(entry) = self.stack.pop() {
+            let (prefix, offset, pos) = match entry {
+                WildCardEntry::Value(key, value) => return Some((key, value)),
+                WildCardEntry::Node(prefix, offset, pos) => (prefix, offset, pos),
+            };
+            let cur = match record(self.data, offset) {
+                Some(cur) => cur,
+                None => continue,
+            };
+            let ch = self.pat[pos];
+            if (ch == '.' || ch > cur.c) && let Some(gt) = cur.gt {
+                self.stack.push(WildCardEntry::Node(prefix.clone(), gt, pos));
+            }
+            if ch == '.' || ch == cur.c {
+                let mut key = String::with_capacity(prefix.len() + cur.c.len_utf8());
+                key.push_str(&prefix);
+                key.push(cur.c);
+                if pos + 1 < self.pat.len() {
+                    if let Some(eq) = cur.eq {
+                        self.stack.push(WildCardEntry::Node(key, eq, pos + 1));
+                    }
+                } else if let Some(value) = cur.value {
+                    self.stack.push(WildCardEntry::Value(key, value));
+                }
+            }
+            if (ch == '.' || ch < cur.c) && let Some(lt) = cur.lt {
+                self.stack.push(WildCardEntry::Node(prefix, lt, pos));
+            }
+        }
+        None
+    }
+}
@@ -1,11 +1,8 @@
-use std::mem;
 use std::fmt::{self, Debug};
 use std::default::Default;
-use std::ops::Deref;
-use core::marker::PhantomData;
-use core::ptr;
 
-#[derive(Clone, PartialEq, Eq)]
+// Clone, PartialEq and Debug go through an explicit stack instead of the derived recursion,
+// a key of a million chars is a chain of a million nodes
 pub struct Node<Value> {
     pub lt: BoxedNode<Value>,
     pub eq: BoxedNode<Value>,
@@ -14,26 +11,10 @@ pub struct Node<Value> {
     pub c: char,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BoxedNode<Value> {
     pub ptr: Option<Box<Node<Value>>>,
 }
 
-pub struct NodeRef<'x, Value: 'x> {
-    node: *const Node<Value>,
-    _marker: PhantomData<&'x Value>,
-}
-
-pub struct NodeRefMut<'x, Value: 'x> {
-    node: *mut Node<Value>,
-    _marker: PhantomData<&'x Value>,
-}
-
-pub struct BoxedNodeRefMut<'x, Value: 'x> {
-    node: *mut BoxedNode<Value>,
-    _marker: PhantomData<&'x Value>,
-}
-
 impl<Value> Default for BoxedNode<Value> {
     fn default() -> BoxedNode<Value> {
         BoxedNode {
@@ -49,56 +30,6 @@ impl<Value> BoxedNode<Value> {
         }
     }
 
-    fn as_ptr(&self) -> *const Node<Value> {
-        match self.ptr {
-            Some(ref ptr) => {
-                &**ptr as *const Node<Value>
-            },
-            None => {
-                ptr::null()
-            }
-        }
-    }
-
-    fn as_ptr_mut(&mut self) -> *mut Node<Value> {
-        match self.ptr {
-            Some(ref mut ptr) => {
-                &mut **ptr as *mut Node<Value>
-            },
-            None => {
-                ptr::null_mut()
-            }
-        }
-    }
-
-    fn as_node_ref_mut(&mut self) -> &mut Node<Value> {
-        match self.ptr {
-            None => unreachable!(),
-            Some(ref mut ptr) => ptr,
-        }
-    }
-
-    pub fn as_ref<'x>(&self) -> NodeRef<'x, Value> {
-        NodeRef {
-            node: self.as_ptr(),
-            _marker: PhantomData,
-        }
-    }
-
-    pub fn as_ref_mut<'x>(&mut self) -> NodeRefMut<'x, Value> {
-        NodeRefMut {
-            node: self.as_ptr_mut(),
-            _marker: PhantomData,
-        }
-    }
-
-    pub fn as_mut(&mut self) -> BoxedNodeRefMut<Value> {
-        BoxedNodeRefMut {
-            node: self as *mut BoxedNode<Value>,
-            _marker: PhantomData,
-        }
-    }
-
     pub fn is_some(&self) -> bool {
         self.ptr.is_some()
     }
@@ -108,147 +39,124 @@ impl<Value> BoxedNode<Value> {
     }
 }
 
-impl<'a, Value> NodeRef<'a, Value> {
-    // we have to be shure about valid ptr, before calling
-    pub fn is_value(&self) -> bool {
-        unsafe {
-            let r = &*self.node;
-            r.value.is_some()
-        }
+impl<Value> Default for Node<Value> {
+    fn default() -> Node<Value> {
+        Node::new(Default::default())
     }
 }
 
-impl<'x, Value> NodeRef<'x, Value> {
-    pub fn as_option(&self) -> Option<&'x Node<Value>> {
-        if self.node.is_null() {
-            None
-        } else {
-            unsafe {
-                Some(&*self.node)
-            }
+impl<Value> Node<Value> {
+    fn new(c: char) -> Node<Value> {
+        Node {
+            lt: Default::default(),
+            eq: Default::default(),
+            gt: Default::default(),
+            value: None,
+            c,
         }
     }
 }
 
-impl<'x, Value> Deref for NodeRef<'x, Value> {
-    type Target = Node<Value>;
-
-    fn deref(&self) -> &Node<Value> {
-        unsafe {
-            &*self.node
+impl<Value: Clone> Node<Value> {
+    // copy of the node without its links
+    fn clone_alone(&self) -> Node<Value> {
+        Node {
+            value: self.value.clone(),
+            c: self.c,
+            ..Default::default()
         }
     }
 }
 
-impl<'x, Value> Default for NodeRef<'x, Value> {
-    fn default() -> Self {
-        NodeRef {
-            node: ptr::null(),
-            _marker: PhantomData,
+impl<Value: Clone> Clone for Node<Value> {
+    fn clone(&self) -> Node<Value> {
+        let mut root = self.clone_alone();
+        let mut stack = vec![(self, &mut root)];
+        while let Some((from, to)) = stack.pop() {
+            let Node { lt, eq, gt, .. } = to;
+            for (link, copy) in [(&from.lt, lt), (&from.eq, eq), (&from.gt, gt)] {
+                if let Some(node) = link.ptr.as_deref() {
+                    stack.push((node, copy.ptr.insert(Box::new(node.clone_alone()))));
+                }
+            }
         }
+        root
     }
 }
 
-impl<'x, Value> Clone for NodeRef<'x, Value> {
-    fn clone(&self) -> Self {
-        NodeRef {
-            node: self.node,
-            _marker: PhantomData,
+impl<Value: Clone> Clone for BoxedNode<Value> {
+    fn clone(&self) -> BoxedNode<Value> {
+        BoxedNode {
+            ptr: self.ptr.as_deref().map(|node| Box::new(node.clone())),
         }
     }
 }
 
-impl<'x, Value> NodeRefMut<'x, Value> {
-    pub fn into_immut(self) -> NodeRef<'x, Value> {
-        NodeRef {
-            node: self.node,
-            _marker: PhantomData,
+impl<Value: PartialEq> PartialEq for Node<Value> {
+    fn eq(&self, other: &Node<Value>) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((a, b)) = stack.pop() {
+            if a.c != b.c || a.value != b.value {
+                return false;
+            }
+            for (x, y) in [(&a.lt, &b.lt), (&a.eq, &b.eq), (&a.gt, &b.gt)] {
+                match (x.ptr.as_deref(), y.ptr.as_deref()) {
+                    (Some(x), Some(y)) => stack.push((x, y)),
+                    (None, None) => {},
+                    _ => return false,
+                }
+            }
         }
+        true
     }
 }
 
-impl<'a, Value> Default for NodeRefMut<'a, Value> {
-    fn default() -> Self {
-        NodeRefMut {
-            node: ptr::null_mut(),
-            _marker: PhantomData,
-        }
-    }
-}
+impl<Value: Eq> Eq for Node<Value> {}
 
-impl<'x, Value> Clone for NodeRefMut<'x, Value> {
-    fn clone(&self) -> Self {
-        NodeRefMut {
-            node: self.node,
-            _marker: PhantomData,
-        }
+impl<Value: PartialEq> PartialEq for BoxedNode<Value> {
+    fn eq(&self, other: &BoxedNode<Value>) -> bool {
+        self.ptr.as_deref() == other.ptr.as_deref()
     }
 }
 
-impl<'x, Value> BoxedNodeRefMut<'x, Value> {
-    pub fn as_node_ref(&self) -> &'x mut Node<Value> {
-        unsafe {
-            let r: &mut BoxedNode<Value> = &mut *self.node;
-            r.as_node_ref_mut()
-        }
-    }
+impl<Value: Eq> Eq for BoxedNode<Value> {}
 
-    pub fn as_mut(&self) -> &'x mut BoxedNode<Value> {
-        unsafe {
-            &mut *self.node
-        }
-    }
-
-    pub fn assign(&mut self, node: BoxedNode<Value>) {
-        unsafe {
-            *self.node = node;
-        }
-    }
-}
-
-impl<'x, Value> Default for BoxedNodeRefMut<'x, Value> {
-    fn default() -> Self {
-        BoxedNodeRefMut {
-            node: ptr::null_mut(),
-            _marker: PhantomData,
-        }
-    }
+enum Piece<'x, Value: 'x> {
+    Text(&'static str),
+    Link(&'x BoxedNode<Value>),
+    Node(&'x Node<Value>),
 }
 
-impl<'x, Value> Clone for BoxedNodeRefMut<'x, Value> {
-    fn clone(&self) -> Self {
-        BoxedNodeRefMut {
-            node: self.node,
-            _marker: PhantomData,
+// writes the pieces in order, a node is written as its char, value and links
+fn write_pieces<Value: Debug>(f: &mut fmt::Formatter, first: Piece<Value>) -> fmt::Result {
+    let mut stack = vec![first];
+    while let Some(piece) = stack.pop() {
+        match piece {
+            Piece::Text(text) => f.write_str(text)?,
+            Piece::Link(link) => match link.ptr.as_deref() {
+                Some(node) => stack.push(Piece::Node(node)),
+                None => f.write_str("None")?,
+            },
+            Piece::Node(node) => {
+                write!(f, "{{c = {:?}, val = {:?}, lt = ", node.c, node.value)?;
+                stack.extend([
+                    Piece::Text("}"), Piece::Link(&node.gt), Piece::Text(", gt = "),
+                    Piece::Link(&node.eq), Piece::Text(", eq = "), Piece::Link(&node.lt),
+                ]);
+            },
         }
     }
+    Ok(())
 }
 
-impl<Value> Node<Value> {
-    fn new(c: char) -> Node<Value> {
-        Node {
-            lt: Default::default(),
-            eq: Default::default(),
-            gt: Default::default(),
-            value: None,
-            c,
-        }
-    }
-
-    pub fn is_leaf(&self) -> bool {
-        self.lt.ptr.is_none() && self.gt.ptr.is_none() && self.eq.ptr.is_none() && self.value.is_none()
-    }
-
-    pub fn replace(&mut self, value: Option<Value>) -> Option<Value> {
-        mem::replace(&mut self.value, value)
+impl<Value: Debug> Debug for Node<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_pieces(f, Piece::Node(self))
     }
 }
 
-impl<Value: Debug> Debug for Node<Value> {
+impl<Value: Debug> Debug for BoxedNode<Value> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "{{")?;
-        write!(f, "lt = {:?}, eq = {:?}, gt = {:?}, val = {:?}, c = {:?}",
-            self.lt, self.eq, self.gt, self.value, self.c)?;
-        writeln!(f, "}}")
+        write_pieces(f, Piece::Link(self))
     }
 }
@@ -0,0 +1,187 @@
+//! Parallel iterators and `ParallelExtend` for `TSTMap`, enabled by the `rayon` feature.
+//!
+//! Work is split along `lt`/`eq`/`gt` links: a part of an iterator is a stack of subtrees,
+//! each with the key of its parent level, and splitting hands half of the stack to
+//! another thread. A part that holds one subtree opens its top node first.
+
+use std::mem;
+use rayon::iter::{ParallelIterator, plumbing::{bridge_unindexed, UnindexedConsumer, UnindexedProducer, Folder}};
+use rayon::slice::ParallelSliceMut;
+use rayon::iter::IntoParallelIterator;
+use super::node::{Node, BoxedNode};
+use super::traverse::{self, NodeRef, Parts, join};
+
+enum Work<N, V> {
+    // subtree and the key of its level
+    Node(String, N),
+    Value(String, V),
+}
+
+// pushes the parts of the subtree, so the first one in order of keys goes last
+fn open<N: NodeRef>(node: N, prefix: String, stack: &mut Vec<Work<N, N::Value>>) {
+    let Parts { lt, eq, gt, value, c } = node.split();
+    if let Some(gt) = gt {
+        stack.push(Work::Node(prefix.clone(), gt));
+    }
+    let key = join(&prefix, c);
+    if let Some(eq) = eq {
+        stack.push(Work::Node(key.clone(), eq));
+    }
+    if let Some(value) = value {
+        stack.push(Work::Value(key, value));
+    }
+    if let Some(lt) = lt {
+        stack.push(Work::Node(prefix, lt));
+    }
+}
+
+struct Producer<N: NodeRef> {
+    stack: Vec<Work<N, N::Value>>,
+}
+
+impl<N: NodeRef> Producer<N> {
+    // parts under the node of `prefix`: its value and its eq subtree
+    fn new(prefix: &str, eq: Option<N>, value: Option<N::Value>) -> Self {
+        let mut stack = Vec::new();
+        if let Some(eq) = eq {
+            stack.push(Work::Node(prefix.to_string(), eq));
+        }
+        if let Some(value) = value {
+            stack.push(Work::Value(prefix.to_string(), value));
+        }
+        Producer { stack }
+    }
+}
+
+impl<N: NodeRef + Send> UnindexedProducer for Producer<N> where N::Value: Send {
+    type Item = (String, N::Value);
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        if self.stack.len() == 1 && matches!(self.stack[0], Work::Node(..))
+            && let Some(Work::Node(prefix, node)) = self.stack.pop() {
+            open(node, prefix, &mut self.stack);
+        }
+        if self.stack.len() < 2 {
+            return (self, None);
+        }
+        // bottom of the stack goes last in order of keys
+        let right = self.stack.drain(..self.stack.len() / 2).collect();
+        (self, Some(Producer { stack: right }))
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(mut self, mut folder: F) -> F {
+        while let Some(work) = self.stack.pop() {
+            if folder.full() {
+                break;
+            }
+            match work {
+                Work::Value(key, value) => folder = folder.consume((key, value)),
+                Work::Node(prefix, node) => open(node, prefix, &mut self.stack),
+            }
+        }
+        folder
+    }
+}
+
+/// `TSTMap` parallel iterator, made by `par_iter` and `par_prefix_iter`.
+pub struct ParIter<'x, Value: Sync> {
+    producer: Producer<&'x Node<Value>>,
+}
+
+impl<'x, Value: Sync> ParIter<'x, Value> {
+    // entries under `node`, which is the node of `prefix`
+    pub(crate) fn new(node: Option<&'x Node<Value>>, prefix: &str) -> Self {
+        let (eq, value) = match node {
+            Some(node) => (node.eq.ptr.as_deref(), node.value.as_ref()),
+            None => (None, None),
+        };
+        ParIter { producer: Producer::new(prefix, eq, value) }
+    }
+}
+
+impl<'x, Value: Sync> ParallelIterator for ParIter<'x, Value> {
+    type Item = (String, &'x Value);
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge_unindexed(self.producer, consumer)
+    }
+}
+
+/// `TSTMap` parallel mutable iterator, made by `par_iter_mut`.
+pub struct ParIterMut<'x, Value: Send> {
+    producer: Producer<&'x mut Node<Value>>,
+}
+
+impl<'x, Value: Send> ParIterMut<'x, Value> {
+    // all entries of the trie under `root`
+    pub(crate) fn new(root: &'x mut Node<Value>) -> Self {
+        ParIterMut { producer: Producer::new("", root.eq.ptr.as_deref_mut(), root.value.as_mut()) }
+    }
+}
+
+impl<'x, Value: Send> ParallelIterator for ParIterMut<'x, Value> {
+    type Item = (String, &'x mut Value);
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge_unindexed(self.producer, consumer)
+    }
+}
+
+// values and eq subtrees of the level under `link` in order of their chars
+fn level_mut<Value>(link: &mut BoxedNode<Value>) -> Vec<(char, &mut Option<Value>, &mut BoxedNode<Value>)> {
+    let mut level = Vec::new();
+    let mut stack = vec![link.ptr.as_deref_mut()];
+    while let Some(node) = stack.pop() {
+        if let Some(Node { lt, eq, gt, value, c }) = node {
+            level.push((*c, value, eq));
+            stack.push(lt.ptr.as_deref_mut());
+            stack.push(gt.ptr.as_deref_mut());
+        }
+    }
+    level.sort_by_key(|&(c, _, _)| c);
+    level
+}
+
+// inserts `entries` under `root`, later entries of a key win, returns the number of new keys;
+// keys are grouped by their first char and every group goes to its own subtree in parallel
+pub(crate) fn extend<Value: Send>(root: &mut Node<Value>, mut entries: Vec<(&str, Value)>) -> usize {
+    // stable sort keeps entries of one key in their order
+    entries.par_sort_by_key(|&(key, _)| key.chars().next());
+
+    let mut added = 0;
+    let mut groups: Vec<(char, Vec<(&str, Value)>)> = Vec::new();
+    for (key, value) in entries {
+        let c = match key.chars().next() {
+            Some(c) => c,
+            None => {
+                added += root.value.replace(value).is_none() as usize;
+                continue;
+            },
+        };
+        let (head, tail) = key.split_at(c.len_utf8());
+        if groups.last().is_none_or(|&(last, _)| last != c) {
+            traverse::insert(root, head);
+            groups.push((c, Vec::new()));
+        }
+        if let Some((_, group)) = groups.last_mut() {
+            group.push((tail, value));
+        }
+    }
+
+    let mut level = level_mut(&mut root.eq).into_iter();
+    let work: Vec<_> = groups.into_iter().filter_map(|(c, group)| {
+        level.find(|&(ch, _, _)| ch == c).map(|(_, value, eq)| (value, eq, group))
+    }).collect();
+
+    added + work.into_par_iter().map(|(value, eq, group)| {
+        // subtree of one first char under a root of its own
+        let mut sub = Node { value: value.take(), eq: mem::take(eq), ..Default::default() };
+        let mut added = 0;
+        for (tail, value) in group {
+            added += traverse::insert(&mut sub, tail).value.replace(value).is_none() as usize;
+        }
+        *value = sub.value.take();
+        *eq = mem::take(&mut sub.eq);
+        added
+    }).sum::<usize>()
+}
@@ -0,0 +1,711 @@
+use std::ops;
+use std::fmt::{self, Debug};
+use std::cmp::Ordering;
+use std::iter::{Map, FromIterator};
+use std::sync::Arc;
+
+type Link<Value> = Option<Arc<PersistentNode<Value>>>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Dir {
+    Lt,
+    Eq,
+    Gt,
+}
+
+// Node is never changed after it's shared, a change copies the nodes on the way
+// to the changed one and links the copies to the same untouched subtrees.
+struct PersistentNode<Value> {
+    lt: Link<Value>,
+    eq: Link<Value>,
+    gt: Link<Value>,
+    value: Option<Arc<Value>>,
+    c: char,
+}
+
+impl<Value> PersistentNode<Value> {
+    fn new(c: char) -> Self {
+        PersistentNode {
+            lt: None,
+            eq: None,
+            gt: None,
+            value: None,
+            c,
+        }
+    }
+
+    // copy of the node itself, which shares all its children and value
+    fn copy(&self) -> Self {
+        PersistentNode {
+            lt: self.lt.clone(),
+            eq: self.eq.clone(),
+            gt: self.gt.clone(),
+            value: self.value.clone(),
+            c: self.c,
+        }
+    }
+
+    fn link(&self, dir: Dir) -> &Link<Value> {
+        match dir {
+            Dir::Lt => &self.lt,
+            Dir::Eq => &self.eq,
+            Dir::Gt => &self.gt,
+        }
+    }
+
+    fn link_mut(&mut self, dir: Dir) -> &mut Link<Value> {
+        match dir {
+            Dir::Lt => &mut self.lt,
+            Dir::Eq => &mut self.eq,
+            Dir::Gt => &mut self.gt,
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.lt.is_none() && self.eq.is_none() && self.gt.is_none() && self.value.is_none()
+    }
+}
+
+impl<Value> Drop for PersistentNode<Value> {
+    // nodes, which aren't shared with other versions, are freed without recursion
+    fn drop(&mut self) {
+        let mut stack: Vec<Arc<PersistentNode<Value>>> = Vec::new();
+        stack.extend(self.lt.take());
+        stack.extend(self.eq.take());
+        stack.extend(self.gt.take());
+        while let Some(node) = stack.pop() {
+            if let Some(mut node) = Arc::into_inner(node) {
+                stack.extend(node.lt.take());
+                stack.extend(node.eq.take());
+                stack.extend(node.gt.take());
+            }
+        }
+    }
+}
+
+fn search<'x, Value>(top: &'x Link<Value>, key: &str) -> Option<&'x PersistentNode<Value>> {
+    let mut chars = key.chars();
+    let mut ch = chars.next()?;
+    let mut cur = top.as_deref();
+    while let Some(node) = cur {
+        cur = match ch.cmp(&node.c) {
+            Ordering::Less => node.lt.as_deref(),
+            Ordering::Greater => node.gt.as_deref(),
+            Ordering::Equal => match chars.next() {
+                Some(next) => {
+                    ch = next;
+                    node.eq.as_deref()
+                },
+                None => return Some(node),
+            },
+        };
+    }
+    None
+}
+
+// copies of the nodes on the way to `key` with the links taken from them, the last one is
+// the node of `key`; the way ends early if the key isn't there and `create` is false
+fn copy_path<Value>(top: &Link<Value>, key: &str, create: bool) -> Option<Vec<(PersistentNode<Value>, Dir)>> {
+    let mut path = Vec::new();
+    let mut chars = key.chars().peekable();
+    let mut cur = top.as_deref();
+    while let Some(&ch) = chars.peek() {
+        let node = match cur {
+            Some(node) => node.copy(),
+            None if create => PersistentNode::new(ch),
+            None => return None,
+        };
+        let dir = match ch.cmp(&node.c) {
+            Ordering::Less => Dir::Lt,
+            Ordering::Greater => Dir::Gt,
+            Ordering::Equal => {
+                chars.next();
+                Dir::Eq
+            },
+        };
+        cur = cur.and_then(|node| node.link(dir).as_deref());
+        path.push((node, dir));
+    }
+    Some(path)
+}
+
+// links the copied nodes back together bottom-up, dropping the ones left empty,
+// returns the new top
+fn link_path<Value>(path: Vec<(PersistentNode<Value>, Dir)>, mut child: Link<Value>) -> Link<Value> {
+    for (mut node, dir) in path.into_iter().rev() {
+        *node.link_mut(dir) = child;
+        child = if node.is_leaf() { None } else { Some(Arc::new(node)) };
+    }
+    child
+}
+
+///
+/// Persistent version of `TSTMap`.
+///
+/// Nodes are shared through `Arc` and never changed in place, `insert` and `remove`
+/// return a new version of the map, which copies only the nodes on the way to the key
+/// and shares the rest with the old one. So `clone` only bumps a reference count,
+/// old versions stay valid snapshots, and versions can be sent to other threads.
+/// # Examples
+///
+/// ```rust
+/// use tst::PersistentTSTMap;
+///
+/// let v1 = PersistentTSTMap::new().insert("port", 80).insert("host", 1);
+/// let v2 = v1.insert("port", 8080);
+/// let v3 = v2.remove("host");
+///
+/// assert_eq!(Some(&80), v1.get("port"));
+/// assert_eq!(Some(&8080), v2.get("port"));
+/// assert_eq!(None, v3.get("host"));
+/// assert_eq!(2, v2.len());
+/// ```
+pub struct PersistentTSTMap<Value> {
+    top: Link<Value>,
+    // value of the empty key
+    root_value: Option<Arc<Value>>,
+    size: usize,
+}
+
+impl<Value> PersistentTSTMap<Value> {
+    /// Constructs a new, empty `PersistentTSTMap<Value>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    /// let m: PersistentTSTMap<i64> = PersistentTSTMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of elements in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("ab", 2).insert("x", 1);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn len(&self) -> usize { self.size }
+
+    /// Returns true if the `PersistentTSTMap` contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new();
+    /// assert!(m.is_empty());
+    /// assert!(!m.insert("ab", 2).is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool { self.size == 0 }
+
+    /// Returns a new version of the map with `value` at `key`, `self` stays as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let old = PersistentTSTMap::new().insert("a", 1);
+    /// let new = old.insert("a", 2).insert("b", 3);
+    ///
+    /// assert_eq!(Some(&1), old.get("a"));
+    /// assert_eq!(Some(&2), new.get("a"));
+    /// assert_eq!(2, new.len());
+    /// ```
+    pub fn insert(&self, key: &str, value: Value) -> Self {
+        let value = Some(Arc::new(value));
+        if key.is_empty() {
+            let size = self.size + self.root_value.is_none() as usize;
+            return PersistentTSTMap { top: self.top.clone(), root_value: value, size };
+        }
+        let mut path = copy_path(&self.top, key, true).unwrap();
+        let node = &mut path.last_mut().unwrap().0;
+        let size = self.size + node.value.is_none() as usize;
+        node.value = value;
+        let child = node.eq.take();
+        PersistentTSTMap {
+            top: link_path(path, child),
+            root_value: self.root_value.clone(),
+            size,
+        }
+    }
+
+    /// Returns a new version of the map without `key`, `self` stays as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let old = PersistentTSTMap::new().insert("a", 1).insert("ab", 2);
+    /// let new = old.remove("a");
+    ///
+    /// assert_eq!(Some(&1), old.get("a"));
+    /// assert_eq!(None, new.get("a"));
+    /// assert_eq!(1, new.len());
+    /// ```
+    pub fn remove(&self, key: &str) -> Self {
+        if key.is_empty() {
+            let size = self.size - self.root_value.is_some() as usize;
+            return PersistentTSTMap { top: self.top.clone(), root_value: None, size };
+        }
+        let mut path = match copy_path(&self.top, key, false) {
+            Some(path) if path.last().is_some_and(|(node, _)| node.value.is_some()) => path,
+            _ => return self.clone(),
+        };
+        let node = &mut path.last_mut().unwrap().0;
+        node.value = None;
+        let child = node.eq.take();
+        PersistentTSTMap {
+            top: link_path(path, child),
+            root_value: self.root_value.clone(),
+            size: self.size - 1,
+        }
+    }
+
+    /// Returns a reference to the value corresponding to the `key` or None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("first", 13);
+    /// assert_eq!(Some(&13), m.get("first"));
+    /// assert_eq!(None, m.get("second"));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.find(key).map(|value| &**value)
+    }
+
+    // shared value of `key`, which lives as long as any version keeps it
+    pub(crate) fn find(&self, key: &str) -> Option<&Arc<Value>> {
+        if key.is_empty() {
+            return self.root_value.as_ref();
+        }
+        search(&self.top, key).and_then(|node| node.value.as_ref())
+    }
+
+    /// Returns true if the `PersistentTSTMap` contains a value for the specified `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("abc", 1);
+    /// assert!(m.contains_key("abc"));
+    /// assert!(!m.contains_key("ab"));
+    /// ```
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the longest prefix of `pref`, which is a key in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("abc", 1).insert("abcd", 1).insert("abce", 1);
+    /// assert_eq!("abcd", m.longest_prefix("abcde"));
+    /// ```
+    pub fn longest_prefix<'x>(&self, pref: &'x str) -> &'x str {
+        let mut length = 0;
+        let mut cur = self.top.as_deref();
+        let mut chars = pref.char_indices();
+        let mut next = chars.next();
+        while let (Some(node), Some((i, ch))) = (cur, next) {
+            cur = match ch.cmp(&node.c) {
+                Ordering::Less => node.lt.as_deref(),
+                Ordering::Greater => node.gt.as_deref(),
+                Ordering::Equal => {
+                    if node.value.is_some() {
+                        length = i + ch.len_utf8();
+                    }
+                    next = chars.next();
+                    node.eq.as_deref()
+                },
+            };
+        }
+        &pref[..length]
+    }
+
+    /// Creates an iterator over the keys matching the wildcard `pat`, `.` matches any char.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("a", 1).insert("b", 2).insert("bc", 3);
+    /// assert_eq!(3, m.wildcard_iter(".").fold(0, |sum, (_, val)| sum + val));
+    /// ```
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<'_, Value> {
+        let mut iter = WildCardIter { stack: Vec::new(), pat: pat.chars().collect() };
+        if let Some(top) = self.top.as_deref().filter(|_| !pat.is_empty()) {
+            iter.stack.push(TraverseEntry::Node((String::new(), 0), top));
+        } else if let Some(value) = self.root_value.as_deref().filter(|_| pat.is_empty()) {
+            iter.stack.push(TraverseEntry::Value(String::new(), value));
+        }
+        iter
+    }
+
+    /// Creates an iterator over the keys starting with `pref` in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("abc", 1).insert("abd", 2).insert("b", 3);
+    /// let keys: Vec<String> = m.prefix_iter("ab").map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["abc", "abd"], keys);
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_, Value> {
+        let mut iter = Iter { stack: Vec::new() };
+        let (eq, value) = if pref.is_empty() {
+            (self.top.as_deref(), self.root_value.as_deref())
+        } else {
+            match search(&self.top, pref) {
+                Some(node) => (node.eq.as_deref(), node.value.as_deref()),
+                None => (None, None),
+            }
+        };
+        if let Some(eq) = eq {
+            iter.stack.push(TraverseEntry::Node(pref.to_string(), eq));
+        }
+        if let Some(value) = value {
+            iter.stack.push(TraverseEntry::Value(pref.to_string(), value));
+        }
+        iter
+    }
+
+    /// Gets an iterator over the entries in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("b", 2).insert("a", 1);
+    /// let keys: Vec<String> = m.iter().map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["a", "b"], keys);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Value> {
+        self.prefix_iter("")
+    }
+
+    /// An iterator visiting all keys in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("b", 2).insert("a", 1);
+    /// let keys: Vec<String> = m.keys().collect();
+    /// assert_eq!(vec!["a", "b"], keys);
+    /// ```
+    pub fn keys(&self) -> KeysIter<'_, Value> {
+        fn first<A, B>((k, _): (A, B)) -> A { k }
+        KeysIter { iter: self.iter().map(first) }
+    }
+
+    /// An iterator visiting all values in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("b", 2).insert("a", 1);
+    /// let values: Vec<i32> = m.values().cloned().collect();
+    /// assert_eq!(vec![1, 2], values);
+    /// ```
+    pub fn values(&self) -> ValuesIter<'_, Value> {
+        fn second<A, B>((_, v): (A, B)) -> B { v }
+        ValuesIter { iter: self.iter().map(second) }
+    }
+}
+
+impl<Value> PersistentTSTMap<Value> {
+    /// Turns this version into an iterator over the keys starting with `pref` in order of keys.
+    /// The iterator owns the nodes it's going to visit, so it doesn't borrow the map
+    /// and yields values as shared `Arc`s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("abc", 1).insert("abd", 2).insert("b", 3);
+    /// let iter = m.clone().into_prefix_iter("ab");
+    /// let m = m.remove("abc");
+    ///
+    /// let entries: Vec<(String, i32)> = iter.map(|(k, v)| (k, *v)).collect();
+    /// assert_eq!(vec![("abc".to_string(), 1), ("abd".to_string(), 2)], entries);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn into_prefix_iter(self, pref: &str) -> IntoIter<Value> {
+        let mut iter = IntoIter { stack: Vec::new() };
+        let (eq, value) = if pref.is_empty() {
+            (self.top, self.root_value)
+        } else {
+            match search(&self.top, pref) {
+                Some(node) => (node.eq.clone(), node.value.clone()),
+                None => (None, None),
+            }
+        };
+        if let Some(eq) = eq {
+            iter.stack.push(TraverseEntry::Node(pref.to_string(), eq));
+        }
+        if let Some(value) = value {
+            iter.stack.push(TraverseEntry::Value(pref.to_string(), value));
+        }
+        iter
+    }
+}
+
+impl<Value> IntoIterator for PersistentTSTMap<Value> {
+    type Item = (String, Arc<Value>);
+    type IntoIter = IntoIter<Value>;
+
+    fn into_iter(self) -> IntoIter<Value> {
+        self.into_prefix_iter("")
+    }
+}
+
+impl<Value> Clone for PersistentTSTMap<Value> {
+    /// Makes another handle to the same version, it takes constant time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::PersistentTSTMap;
+    ///
+    /// let m = PersistentTSTMap::new().insert("a", 1);
+    /// let snapshot = m.clone();
+    /// let m = m.insert("a", 2);
+    /// assert_eq!(Some(&1), snapshot.get("a"));
+    /// assert_eq!(Some(&2), m.get("a"));
+    /// ```
+    fn clone(&self) -> Self {
+        PersistentTSTMap {
+            top: self.top.clone(),
+            root_value: self.root_value.clone(),
+            size: self.size,
+        }
+    }
+}
+
+impl<'x, Value> FromIterator<(&'x str, Value)> for PersistentTSTMap<Value> {
+    fn from_iter<I: IntoIterator<Item = (&'x str, Value)>>(iter: I) -> PersistentTSTMap<Value> {
+        iter.into_iter().fold(PersistentTSTMap::new(), |m, (k, v)| m.insert(k, v))
+    }
+}
+
+impl<Value> ops::Index<&str> for PersistentTSTMap<Value> {
+    type Output = Value;
+    #[inline]
+    fn index(&self, idx: &str) -> &Value {
+        self.get(idx).expect("no entry found for key")
+    }
+}
+
+impl<Value: Debug> Debug for PersistentTSTMap<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<Value> Default for PersistentTSTMap<Value> {
+    fn default() -> Self {
+        PersistentTSTMap {
+            top: None,
+            root_value: None,
+            size: 0,
+        }
+    }
+}
+
+//
+// iterators section
+//
+
+#[derive(Clone)]
+enum TraverseEntry<NodeRef, Prefix, ValueRef> {
+    Node(Prefix, NodeRef),
+    Value(String, ValueRef),
+}
+
+fn join(prefix: &str, c: char) -> String {
+    let mut key = String::with_capacity(prefix.len() + c.len_utf8());
+    key.push_str(prefix);
+    key.push(c);
+    key
+}
+
+/// `PersistentTSTMap` iterator.
+#[derive(Clone)]
+pub struct Iter<'x, Value: 'x> {
+    stack: Vec<TraverseEntry<&'x PersistentNode<Value>, String, &'x Value>>,
+}
+
+impl<'x, Value> Iterator for Iter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(key, value) => return Some((key, value)),
+                TraverseEntry::Node(prefix, cur) => {
+                    if let Some(gt) = cur.gt.as_deref() {
+                        self.stack.push(TraverseEntry::Node(prefix.clone(), gt));
+                    }
+                    let key = join(&prefix, cur.c);
+                    if let Some(eq) = cur.eq.as_deref() {
+                        self.stack.push(TraverseEntry::Node(key.clone(), eq));
+                    }
+                    if let Some(value) = cur.value.as_deref() {
+                        self.stack.push(TraverseEntry::Value(key, value));
+                    }
+                    if let Some(lt) = cur.lt.as_deref() {
+                        self.stack.push(TraverseEntry::Node(prefix, lt));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// `PersistentTSTMap` owning iterator, which keeps the version it walks alive.
+#[derive(Clone)]
+pub struct IntoIter<Value> {
+    stack: Vec<TraverseEntry<Arc<PersistentNode<Value>>, String, Arc<Value>>>,
+}
+
+impl<Value> Iterator for IntoIter<Value> {
+    type Item = (String, Arc<Value>);
+    fn next(&mut self) -> Option<(String, Arc<Value>)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(key, value) => return Some((key, value)),
+                TraverseEntry::Node(prefix, cur) => {
+                    if let Some(ref gt) = cur.gt {
+                        self.stack.push(TraverseEntry::Node(prefix.clone(), gt.clone()));
+                    }
+                    let key = join(&prefix, cur.c);
+                    if let Some(ref eq) = cur.eq {
+                        self.stack.push(TraverseEntry::Node(key.clone(), eq.clone()));
+                    }
+                    if let Some(ref value) = cur.value {
+                        self.stack.push(TraverseEntry::Value(key, value.clone()));
+                    }
+                    if let Some(ref lt) = cur.lt {
+                        self.stack.push(TraverseEntry::Node(prefix, lt.clone()));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// `PersistentTSTMap` wild-card iterator.
+#[derive(Clone)]
+pub struct WildCardIter<'x, Value: 'x> {
+    // prefix goes with the index of the pattern char to match
+    stack: Vec<TraverseEntry<&'x PersistentNode<Value>, (String, usize), &'x Value>>,
+    pat: Vec<char>,
+}
+
+impl<'x, Value> Iterator for WildCardIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(key, value) => return Some((key, value)),
+                TraverseEntry::Node((prefix, idx), cur) => {
+                    let ch = self.pat[idx];
+                    if let Some(gt) = cur.gt.as_deref().filter(|_| ch == '.' || ch > cur.c) {
+                        self.stack.push(TraverseEntry::Node((prefix.clone(), idx), gt));
+                    }
+                    if ch == '.' || ch == cur.c {
+                        let key = join(&prefix, cur.c);
+                        if let Some(eq) = cur.eq.as_deref().filter(|_| idx + 1 < self.pat.len()) {
+                            self.stack.push(TraverseEntry::Node((key.clone(), idx + 1), eq));
+                        }
+                        if let Some(value) = cur.value.as_deref().filter(|_| idx + 1 == self.pat.len()) {
+                            self.stack.push(TraverseEntry::Value(key, value));
+                        }
+                    }
+                    if let Some(lt) = cur.lt.as_deref().filter(|_| ch == '.' || ch < cur.c) {
+                        self.stack.push(TraverseEntry::Node((prefix, idx), lt));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// `PersistentTSTMap` keys iterator
+#[derive(Clone)]
+pub struct KeysIter<'x, Value: 'x> {
+    #[allow(clippy::type_complexity)]
+    iter: Map<Iter<'x, Value>, fn((String, &'x Value)) -> String>,
+}
+
+impl<'x, Value: 'x> Iterator for KeysIter<'x, Value> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> { self.iter.next() }
+}
+
+/// `PersistentTSTMap` values iterator
+#[derive(Clone)]
+pub struct ValuesIter<'x, Value: 'x> {
+    #[allow(clippy::type_complexity)]
+    iter: Map<Iter<'x, Value>, fn((String, &'x Value)) -> &'x Value>,
+}
+
+impl<'x, Value: 'x> Iterator for ValuesIter<'x, Value> {
+    type Item = &'x Value;
+    fn next(&mut self) -> Option<&'x Value> { self.iter.next() }
+}
+
+// internal tests
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use super::PersistentTSTMap;
+
+    #[test]
+    fn versions_share_untouched_nodes() {
+        let v1: PersistentTSTMap<i32> = vec![("abc", 1), ("abd", 2), ("x", 3)].into_iter().collect();
+        let v2 = v1.insert("xy", 4);
+
+        let (a1, a2) = (v1.top.as_ref().unwrap(), v2.top.as_ref().unwrap());
+        // "a" is on the way to "xy", so it's copied, but its eq subtree is shared
+        assert!(!Arc::ptr_eq(a1, a2));
+        assert!(Arc::ptr_eq(a1.eq.as_ref().unwrap(), a2.eq.as_ref().unwrap()));
+        // "x" is copied too, as its value stays where it was
+        let (x1, x2) = (a1.gt.as_ref().unwrap(), a2.gt.as_ref().unwrap());
+        assert!(!Arc::ptr_eq(x1, x2));
+        assert!(Arc::ptr_eq(x1.value.as_ref().unwrap(), x2.value.as_ref().unwrap()));
+
+        // removing everything in a subtree unlinks its nodes
+        let v3 = v2.remove("abc").remove("abd");
+        assert!(v3.top.as_ref().unwrap().eq.is_none());
+        assert_eq!(2, v3.len());
+        assert_eq!(3, v1.len());
+    }
+}
@@ -0,0 +1,1012 @@
+use std::ops;
+use std::fmt::{self, Debug};
+use std::default::Default;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use crate::map::Entry::{self, *};
+
+type Link<Value> = Option<Box<RadixNode<Value>>>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Dir {
+    Lt,
+    Eq,
+    Gt,
+}
+
+// Node keeps a run of chars, only the first one takes part in lt/gt ordering,
+// eq link continues the key after the last char of the run and value belongs to
+// the key, which ends on the last char.
+#[derive(Clone)]
+struct RadixNode<Value> {
+    lt: Link<Value>,
+    eq: Link<Value>,
+    gt: Link<Value>,
+    value: Option<Value>,
+    label: Box<str>,
+}
+
+impl<Value> RadixNode<Value> {
+    fn new(label: &str) -> Self {
+        RadixNode {
+            lt: None,
+            eq: None,
+            gt: None,
+            value: None,
+            label: label.into(),
+        }
+    }
+
+    fn first(&self) -> char {
+        self.label.chars().next().unwrap_or_default()
+    }
+
+    fn link(&self, dir: Dir) -> &Link<Value> {
+        match dir {
+            Dir::Lt => &self.lt,
+            Dir::Eq => &self.eq,
+            Dir::Gt => &self.gt,
+        }
+    }
+
+    fn link_mut(&mut self, dir: Dir) -> &mut Link<Value> {
+        match dir {
+            Dir::Lt => &mut self.lt,
+            Dir::Eq => &mut self.eq,
+            Dir::Gt => &mut self.gt,
+        }
+    }
+
+    // cut the run at `at` bytes, the tail goes down by eq link with value and eq subtree
+    fn split(&mut self, at: usize) {
+        let tail = RadixNode {
+            lt: None,
+            eq: self.eq.take(),
+            gt: None,
+            value: self.value.take(),
+            label: self.label[at..].into(),
+        };
+        self.label = self.label[..at].into();
+        self.eq = Some(Box::new(tail));
+    }
+
+    // glue single eq child without siblings back into the run
+    fn try_merge(&mut self) {
+        if self.value.is_some() {
+            return;
+        }
+        let single = match self.eq {
+            Some(ref child) => child.lt.is_none() && child.gt.is_none(),
+            None => false,
+        };
+        if single {
+            let child = *self.eq.take().unwrap();
+            let mut label = String::with_capacity(self.label.len() + child.label.len());
+            label.push_str(&self.label);
+            label.push_str(&child.label);
+            self.label = label.into();
+            self.eq = child.eq;
+            self.value = child.value;
+        }
+    }
+}
+
+fn common_prefix(label: &str, key: &str) -> usize {
+    label.char_indices()
+        .zip(key.chars())
+        .find(|&((_, a), b)| a != b)
+        .map_or(label.len().min(key.len()), |((i, _), _)| i)
+}
+
+fn search<'x, Value>(root: &'x RadixNode<Value>, key: &str) -> Option<&'x RadixNode<Value>> {
+    match search_prefix(root, key) {
+        Some((node, offset)) if offset == node.label.len() => Some(node),
+        _ => None,
+    }
+}
+
+// returns the node where `key` ends and offset in its run right after the key
+fn search_prefix<'x, Value>(root: &'x RadixNode<Value>, key: &str) -> Option<(&'x RadixNode<Value>, usize)> {
+    let mut rest = key;
+    let mut last = root;
+    let mut cur = root.eq.as_deref();
+
+    while let Some(ch) = rest.chars().next() {
+        let node = cur?;
+        match ch.cmp(&node.first()) {
+            Ordering::Less => cur = node.lt.as_deref(),
+            Ordering::Greater => cur = node.gt.as_deref(),
+            Ordering::Equal => {
+                let common = common_prefix(&node.label, rest);
+                rest = &rest[common..];
+                if common < node.label.len() {
+                    return if rest.is_empty() { Some((node, common)) } else { None };
+                }
+                last = node;
+                cur = node.eq.as_deref();
+            }
+        }
+    }
+    Some((last, last.label.len()))
+}
+
+fn search_mut<'x, Value>(root: &'x mut RadixNode<Value>, key: &str) -> Option<&'x mut RadixNode<Value>> {
+    let mut rest = key;
+    let mut last = root;
+
+    while let Some(ch) = rest.chars().next() {
+        let mut cur = last.eq.as_deref_mut();
+        loop {
+            let node = cur?;
+            match ch.cmp(&node.first()) {
+                Ordering::Less => cur = node.lt.as_deref_mut(),
+                Ordering::Greater => cur = node.gt.as_deref_mut(),
+                Ordering::Equal => {
+                    if !rest.starts_with(&*node.label) {
+                        return None;
+                    }
+                    rest = &rest[node.label.len()..];
+                    last = node;
+                    break;
+                }
+            }
+        }
+    }
+    Some(last)
+}
+
+fn insert<'x, Value>(root: &'x mut RadixNode<Value>, key: &str) -> &'x mut RadixNode<Value> {
+    let mut rest = key;
+    let mut last = root;
+
+    while let Some(ch) = rest.chars().next() {
+        let mut slot = &mut last.eq;
+        loop {
+            let node = slot.get_or_insert_with(|| Box::new(RadixNode::new(rest)));
+            match ch.cmp(&node.first()) {
+                Ordering::Less => slot = &mut node.lt,
+                Ordering::Greater => slot = &mut node.gt,
+                Ordering::Equal => {
+                    let common = common_prefix(&node.label, rest);
+                    if common < node.label.len() {
+                        node.split(common);
+                    }
+                    rest = &rest[common..];
+                    last = node;
+                    break;
+                }
+            }
+        }
+    }
+    last
+}
+
+fn follow_mut<'x, Value>(root: &'x mut RadixNode<Value>, path: &[Dir]) -> &'x mut RadixNode<Value> {
+    let mut node = root;
+    for &dir in path {
+        node = node.link_mut(dir).as_deref_mut().unwrap();
+    }
+    node
+}
+
+// detach the smallest node of the lt/gt tree, returns it and the rest of the tree
+fn take_min<Value>(mut tree: Box<RadixNode<Value>>) -> (Box<RadixNode<Value>>, Link<Value>) {
+    if tree.lt.is_none() {
+        let rest = tree.gt.take();
+        return (tree, rest);
+    }
+    let mut parent = &mut tree;
+    while parent.lt.as_ref().unwrap().lt.is_some() {
+        parent = parent.lt.as_mut().unwrap();
+    }
+    let mut min = parent.lt.take().unwrap();
+    parent.lt = min.gt.take();
+    (min, Some(tree))
+}
+
+// remove node without value and eq link from its lt/gt tree
+fn unlink<Value>(slot: &mut Link<Value>) {
+    let mut node = slot.take().unwrap();
+    *slot = match (node.lt.take(), node.gt.take()) {
+        (None, None) => None,
+        (Some(lt), None) => Some(lt),
+        (None, Some(gt)) => Some(gt),
+        (Some(lt), Some(gt)) => {
+            let (mut min, rest) = take_min(gt);
+            min.lt = Some(lt);
+            min.gt = rest;
+            Some(min)
+        }
+    }
+}
+
+fn remove<Value>(root: &mut RadixNode<Value>, key: &str) -> Option<Value> {
+    let mut path = Vec::new();
+    let mut rest = key;
+    let mut last: &RadixNode<Value> = root;
+
+    while let Some(ch) = rest.chars().next() {
+        let mut dir = Dir::Eq;
+        let mut parent = last;
+        loop {
+            let node = parent.link(dir).as_deref()?;
+            path.push(dir);
+            match ch.cmp(&node.first()) {
+                Ordering::Less => dir = Dir::Lt,
+                Ordering::Greater => dir = Dir::Gt,
+                Ordering::Equal => {
+                    if !rest.starts_with(&*node.label) {
+                        return None;
+                    }
+                    rest = &rest[node.label.len()..];
+                    last = node;
+                    break;
+                }
+            }
+            parent = node;
+        }
+    }
+
+    let ret = follow_mut(root, &path).value.take()?;
+    // go up while nodes become useless: unlink them or merge their runs
+    let mut depth = path.len();
+    while depth > 0 {
+        let parent = follow_mut(root, &path[..depth - 1]);
+        let slot = parent.link_mut(path[depth - 1]);
+        let node = slot.as_mut().unwrap();
+        if node.value.is_some() || node.eq.is_some() {
+            node.try_merge();
+            break;
+        }
+        unlink(slot);
+        // continue with the upper level node, whose eq tree just lost a node
+        depth = path[..depth].iter().rposition(|&d| d == Dir::Eq).unwrap_or(0);
+    }
+    Some(ret)
+}
+
+///
+/// Symbol table with string keys, implemented using a path-compressed
+/// (radix) ternary search trie (`RadixTSTMap`).
+///
+/// Each node keeps a run of chars instead of one char, so long unique tails,
+/// like URLs, take one node instead of one node per char. Runs are split,
+/// when a new key diverges in the middle of them, and merged back, when a key
+/// is removed. Order of keys and semantics of all queries are the same as for `TSTMap`.
+/// # Examples
+///
+/// ```rust
+/// use tst::RadixTSTMap;
+///
+/// let mut m = RadixTSTMap::new();
+///
+/// m.insert("first", 1);
+/// m.insert("second", 2);
+/// m.insert("firstthird", 3);
+/// m.insert("firstsecond", 12);
+/// m.insert("xirst", -13);
+///
+/// assert_eq!(Some(&1), m.get("first"));
+/// assert_eq!(5, m.len());
+/// assert_eq!("firstsecond", m.longest_prefix("firstsecondthird"));
+/// assert_eq!(3, m.prefix_iter("fir").count());
+/// assert_eq!(-12, m.wildcard_iter(".irst").fold(0, |sum, (_, val)| sum + val));
+/// ```
+#[derive(Clone)]
+pub struct RadixTSTMap<Value> {
+    // root has empty run, it keeps value of the empty key
+    root: RadixNode<Value>,
+    size: usize,
+}
+
+impl<Value> RadixTSTMap<Value> {
+    /// Constructs a new, empty `RadixTSTMap<Value>`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    /// let mut t: RadixTSTMap<i64> = RadixTSTMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of elements in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// assert_eq!(0, m.len());
+    /// m.insert("ab", 2);
+    /// m.insert("x", 1);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn len(&self) -> usize { self.size }
+
+    /// Returns true if the `RadixTSTMap` contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// assert!(m.is_empty());
+    ///
+    /// m.insert("abc", 1);
+    /// assert!(!m.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.size == 0 }
+
+    /// Clears the `RadixTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.clear();
+    ///
+    /// assert!(m.is_empty());
+    /// assert_eq!(None, m.get("abc"));
+    /// ```
+    pub fn clear(&mut self) { *self = RadixTSTMap::new(); }
+
+    /// Inserts an element at key `key` with value `val`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("https://example.com/first", 2);
+    /// m.insert("https://example.com/second", 4);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn insert(&mut self, key: &str, value: Value) -> Option<Value> {
+        match self.entry(key) {
+            Occupied(mut entry) => Some(entry.insert(value)),
+            Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Gets the given `key`'s corresponding entry in the `RadixTSTMap` for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut count: RadixTSTMap<usize> = RadixTSTMap::new();
+    ///
+    /// for x in vec!["abc","bad","abd","cdddd","abc","bade"] {
+    ///     *count.entry(x).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(2, count["abc"]);
+    /// assert_eq!(1, count["abd"]);
+    /// ```
+    pub fn entry(&mut self, key: &str) -> Entry<'_, Value> {
+        let cur = insert(&mut self.root, key);
+        Entry::new(&mut cur.value, &mut self.size)
+    }
+
+    /// Removes a `key` from the `RadixTSTMap`, returning the value at the key if the key
+    /// was previously in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("abc", 100);
+    /// assert_eq!(Some(100), m.remove("abc"));
+    /// assert_eq!(None, m.remove("abc"));
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let ret = remove(&mut self.root, key);
+        if ret.is_some() {
+            self.size -= 1;
+        }
+        ret
+    }
+
+    /// Returns a reference to the value corresponding to the `key` or None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("first", 13);
+    /// assert_eq!(Some(&13), m.get("first"));
+    /// assert_eq!(None, m.get("fir"));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        search(&self.root, key).and_then(|node| node.value.as_ref())
+    }
+
+    /// Returns a mutable reference to the value corresponding to the `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("first", 13);
+    /// if let Some(x) = m.get_mut("first") {
+    ///     *x = -13;
+    /// }
+    /// assert_eq!(-13, m["first"]);
+    /// ```
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        search_mut(&mut self.root, key).and_then(|node| node.value.as_mut())
+    }
+
+    /// Returns true if the `RadixTSTMap` contains a value for the specified `key`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert!(!m.contains_key("ab"));
+    /// assert!(m.contains_key("abc"))
+    /// ```
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Method returns longest prefix `pref` in the `RadixTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// m.insert("abcdef", 1);
+    ///
+    /// assert_eq!("abcd", m.longest_prefix("abcde"));
+    /// ```
+    pub fn longest_prefix<'x>(&self, pref: &'x str) -> &'x str {
+        let mut length = 0;
+        let mut i = 0;
+        let mut cur = self.root.eq.as_deref();
+
+        while let Some(ch) = pref[i..].chars().next() {
+            let node = match cur {
+                None => break,
+                Some(node) => node,
+            };
+            match ch.cmp(&node.first()) {
+                Ordering::Less => cur = node.lt.as_deref(),
+                Ordering::Greater => cur = node.gt.as_deref(),
+                Ordering::Equal => {
+                    if !pref[i..].starts_with(&*node.label) {
+                        break;
+                    }
+                    i += node.label.len();
+                    if node.value.is_some() { length = i; }
+                    cur = node.eq.as_deref();
+                }
+            }
+        }
+        &pref[..length]
+    }
+
+    /// An iterator returning all nodes matching wildcard pattern `pat`.
+    /// Iterator element type is (String, V)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.insert("xbc", 3);
+    ///
+    /// assert_eq!(4, m.wildcard_iter(".bc").map(|(_, v)| v).sum::<i32>());
+    /// ```
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<'_, Value> {
+        WildCardIter::new(&self.root, pat, self.len())
+    }
+
+    /// Method returns iterator over all values with common prefix `pref` in the `RadixTSTMap`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("abcd", 1);
+    /// m.insert("abce", 1);
+    /// m.insert("add", 1);
+    ///
+    /// let keys: Vec<String> = m.prefix_iter("ab").map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["abcd", "abce"], keys);
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_, Value> {
+        match search_prefix(&self.root, pref) {
+            None => Iter { stack: vec![], min_size: 0, max_size: 0 },
+            Some((node, offset)) => {
+                let mut prefix = String::with_capacity(pref.len() + node.label.len() - offset);
+                prefix.push_str(pref);
+                prefix.push_str(&node.label[offset..]);
+                Iter::with_prefix(node, prefix, 0, self.len())
+            }
+        }
+    }
+
+    /// Gets an iterator over the entries of the `RadixTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("bbc", 2);
+    /// m.insert("cccda", 3);
+    ///
+    /// let (first_key, first_value) = m.iter().next().unwrap();
+    /// assert_eq!((first_key, *first_value), ("abc".to_string(), 1));
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Value> {
+        Iter::with_prefix(&self.root, String::new(), self.len(), self.len())
+    }
+
+    /// Gets a mutable iterator over the entries of the `RadixTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    ///
+    /// for (key, value) in m.iter_mut() {
+    ///     if key != "a" {
+    ///         *value += 10;
+    ///     }
+    /// }
+    /// assert_eq!(1, m["a"]);
+    /// assert_eq!(12, m["b"]);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, Value> {
+        let len = self.len();
+        let RadixNode { ref mut eq, ref mut value, .. } = self.root;
+        let mut stack = vec![];
+        if let Some(node) = eq.as_deref_mut() {
+            stack.push(TraverseEntry::Node(String::new(), node));
+        }
+        if let Some(value) = value.as_mut() {
+            stack.push(TraverseEntry::Value(String::new(), value));
+        }
+        IterMut { stack, size: len }
+    }
+
+    /// An iterator visiting all keys in alphabetical order.
+    /// Iterator element type is String
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// assert_eq!(vec!["a", "b"], m.keys().collect::<Vec<String>>());
+    /// ```
+    pub fn keys(&self) -> KeysIter<'_, Value> {
+        KeysIter { iter: self.iter() }
+    }
+
+    /// An iterator visiting all values in order of their keys.
+    /// Iterator element type is &V
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// assert_eq!(vec![&1, &2], m.values().collect::<Vec<&i32>>());
+    /// ```
+    pub fn values(&self) -> ValuesIter<'_, Value> {
+        ValuesIter { iter: self.iter() }
+    }
+}
+
+impl<Value> IntoIterator for RadixTSTMap<Value> {
+    type Item = (String, Value);
+    type IntoIter = IntoIter<Value>;
+
+    /// Creates a consuming iterator, that is, one that moves each key-value
+    /// pair out of the `RadixTSTMap` in order of keys. The `RadixTSTMap` cannot be used after
+    /// calling this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    ///
+    /// let mut m = RadixTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    ///
+    /// let vec: Vec<(String, isize)> = m.into_iter().collect();
+    /// assert_eq!(vec![("a".to_string(), 1), ("b".to_string(), 2)], vec);
+    /// ```
+    fn into_iter(mut self) -> IntoIter<Value> {
+        let mut stack = vec![];
+        if let Some(node) = self.root.eq.take() {
+            stack.push(TraverseEntry::Node(String::new(), node));
+        }
+        if let Some(value) = self.root.value.take() {
+            stack.push(TraverseEntry::Value(String::new(), value));
+        }
+        IntoIter { stack, size: self.size }
+    }
+}
+
+impl<'x, Value> FromIterator<(&'x str, Value)> for RadixTSTMap<Value> {
+    fn from_iter<I: IntoIterator<Item = (&'x str, Value)>>(iter: I) -> RadixTSTMap<Value> {
+        let mut m = RadixTSTMap::new();
+        for item in iter {
+            m.insert(item.0, item.1);
+        }
+        m
+    }
+}
+
+impl<'x, Value> Extend<(&'x str, Value)> for RadixTSTMap<Value> {
+    #[inline]
+    fn extend<I: IntoIterator<Item=(&'x str, Value)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<Value> ops::Index<&str> for RadixTSTMap<Value> {
+    type Output = Value;
+    #[inline]
+    fn index(&self, idx: &str) -> &Value {
+        self.get(idx).expect("no entry found for key")
+    }
+}
+
+impl<Value> ops::IndexMut<&str> for RadixTSTMap<Value> {
+    #[inline]
+    fn index_mut(&mut self, idx: &str) -> &mut Value {
+        self.get_mut(idx).expect("no entry found for key")
+    }
+}
+
+impl<Value> Drop for RadixTSTMap<Value> {
+    fn drop(&mut self) {
+        // drop nodes one by one, recursive drop could overflow the stack
+        let mut stack: Vec<Box<RadixNode<Value>>> = self.root.eq.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.lt.take());
+            stack.extend(node.eq.take());
+            stack.extend(node.gt.take());
+        }
+    }
+}
+
+impl<Value: Debug> Debug for RadixTSTMap<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<Value> Default for RadixTSTMap<Value> {
+    /// Constructs a new, empty `RadixTSTMap<Value>`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::RadixTSTMap;
+    /// let mut t: RadixTSTMap<i64> = Default::default();
+    /// ```
+    fn default() -> Self {
+        RadixTSTMap {
+            root: RadixNode::new(""),
+            size: 0,
+        }
+    }
+}
+
+//
+// iterators section
+//
+
+#[derive(Clone)]
+enum TraverseEntry<NodeRef, ValueRef> {
+    Node(String, NodeRef),
+    Value(String, ValueRef),
+}
+
+/// `RadixTSTMap` iterator.
+#[derive(Clone)]
+pub struct Iter<'x, Value: 'x> {
+    stack: Vec<TraverseEntry<&'x RadixNode<Value>, &'x Value>>,
+    min_size: usize,
+    max_size: usize,
+}
+
+impl<'x, Value> Iter<'x, Value> {
+    // `prefix` is the key of `node` itself
+    fn with_prefix(node: &'x RadixNode<Value>, prefix: String, min: usize, max: usize) -> Self {
+        let mut iter = Iter { stack: vec![], min_size: min, max_size: max };
+        if let Some(eq) = node.eq.as_deref() {
+            iter.stack.push(TraverseEntry::Node(prefix.clone(), eq));
+        }
+        if let Some(value) = node.value.as_ref() {
+            iter.min_size = iter.min_size.max(1);
+            iter.stack.push(TraverseEntry::Value(prefix, value));
+        }
+        iter
+    }
+}
+
+fn join(prefix: &str, label: &str) -> String {
+    let mut key = String::with_capacity(prefix.len() + label.len());
+    key.push_str(prefix);
+    key.push_str(label);
+    key
+}
+
+impl<'x, Value> Iterator for Iter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(key, value) => {
+                    self.min_size = self.min_size.saturating_sub(1);
+                    self.max_size -= 1;
+                    return Some((key, value));
+                }
+                TraverseEntry::Node(prefix, cur) => {
+                    if let Some(gt) = cur.gt.as_deref() {
+                        self.stack.push(TraverseEntry::Node(prefix.clone(), gt));
+                    }
+                    let key = join(&prefix, &cur.label);
+                    if let Some(eq) = cur.eq.as_deref() {
+                        self.stack.push(TraverseEntry::Node(key.clone(), eq));
+                    }
+                    if let Some(value) = cur.value.as_ref() {
+                        self.stack.push(TraverseEntry::Value(key, value));
+                    }
+                    if let Some(lt) = cur.lt.as_deref() {
+                        self.stack.push(TraverseEntry::Node(prefix, lt));
+                    }
+                }
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.min_size, Some(self.max_size))
+    }
+}
+
+/// `RadixTSTMap` mutable iterator.
+pub struct IterMut<'x, Value: 'x> {
+    stack: Vec<TraverseEntry<&'x mut RadixNode<Value>, &'x mut Value>>,
+    size: usize,
+}
+
+impl<'x, Value> Iterator for IterMut<'x, Value> {
+    type Item = (String, &'x mut Value);
+    fn next(&mut self) -> Option<(String, &'x mut Value)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(key, value) => {
+                    self.size -= 1;
+                    return Some((key, value));
+                }
+                TraverseEntry::Node(prefix, cur) => {
+                    let RadixNode { lt, eq, gt, value, label } = cur;
+                    if let Some(gt) = gt.as_deref_mut() {
+                        self.stack.push(TraverseEntry::Node(prefix.clone(), gt));
+                    }
+                    let key = join(&prefix, label);
+                    if let Some(eq) = eq.as_deref_mut() {
+                        self.stack.push(TraverseEntry::Node(key.clone(), eq));
+                    }
+                    if let Some(value) = value.as_mut() {
+                        self.stack.push(TraverseEntry::Value(key, value));
+                    }
+                    if let Some(lt) = lt.as_deref_mut() {
+                        self.stack.push(TraverseEntry::Node(prefix, lt));
+                    }
+                }
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.size, Some(self.size)) }
+}
+
+/// `RadixTSTMap` keys iterator
+#[derive(Clone)]
+pub struct KeysIter<'x, Value: 'x> {
+    iter: Iter<'x, Value>,
+}
+
+impl<'x, Value: 'x> Iterator for KeysIter<'x, Value> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> { self.iter.next().map(|(k, _)| k) }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+/// `RadixTSTMap` values iterator
+#[derive(Clone)]
+pub struct ValuesIter<'x, Value: 'x> {
+    iter: Iter<'x, Value>,
+}
+
+impl<'x, Value: 'x> Iterator for ValuesIter<'x, Value> {
+    type Item = &'x Value;
+    fn next(&mut self) -> Option<&'x Value> { self.iter.next().map(|(_, v)| v) }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+/// `RadixTSTMap` wild-card iterator.
+#[derive(Clone)]
+pub struct WildCardIter<'x, Value: 'x> {
+    stack: Vec<TraverseEntry<(&'x RadixNode<Value>, usize), &'x Value>>,
+    max_size: usize,
+    pat: Vec<char>,
+}
+
+impl<'x, Value> WildCardIter<'x, Value> {
+    fn new(root: &'x RadixNode<Value>, pat: &str, max: usize) -> Self {
+        let mut iter = WildCardIter {
+            stack: vec![],
+            max_size: max,
+            pat: pat.chars().collect(),
+        };
+        if !iter.pat.is_empty() {
+            if let Some(node) = root.eq.as_deref() {
+                iter.stack.push(TraverseEntry::Node(String::new(), (node, 0)));
+            }
+        } else if let Some(value) = root.value.as_ref() {
+            iter.stack.push(TraverseEntry::Value(String::new(), value));
+        }
+        iter
+    }
+}
+
+impl<'x, Value> Iterator for WildCardIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(key, value) => {
+                    self.max_size -= 1;
+                    return Some((key, value));
+                }
+                TraverseEntry::Node(prefix, (cur, idx)) => {
+                    let ch = self.pat[idx];
+                    let first = cur.first();
+                    if (ch == '.' || ch > first) && cur.gt.is_some() {
+                        self.stack.push(TraverseEntry::Node(prefix.clone(), (cur.gt.as_deref().unwrap(), idx)));
+                    }
+                    if ch == '.' || ch == first {
+                        // the rest of the run has to match the pattern too
+                        let len = cur.label.chars().count();
+                        let end = idx + len;
+                        let matched = end <= self.pat.len() &&
+                            cur.label.chars().zip(&self.pat[idx..end]).all(|(c, &p)| p == '.' || p == c);
+                        if matched {
+                            let key = join(&prefix, &cur.label);
+                            if end < self.pat.len() {
+                                if let Some(eq) = cur.eq.as_deref() {
+                                    self.stack.push(TraverseEntry::Node(key, (eq, end)));
+                                }
+                            } else if let Some(value) = cur.value.as_ref() {
+                                self.stack.push(TraverseEntry::Value(key, value));
+                            }
+                        }
+                    }
+                    if (ch == '.' || ch < first) && cur.lt.is_some() {
+                        self.stack.push(TraverseEntry::Node(prefix, (cur.lt.as_deref().unwrap(), idx)));
+                    }
+                }
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (0, Some(self.max_size)) }
+}
+
+/// `RadixTSTMap` consuming iterator
+pub struct IntoIter<Value> {
+    stack: Vec<TraverseEntry<Box<RadixNode<Value>>, Value>>,
+    size: usize,
+}
+
+impl<Value> Iterator for IntoIter<Value> {
+    type Item = (String, Value);
+
+    fn next(&mut self) -> Option<(String, Value)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(key, value) => {
+                    self.size -= 1;
+                    return Some((key, value));
+                }
+                TraverseEntry::Node(prefix, mut cur) => {
+                    if let Some(gt) = cur.gt.take() {
+                        self.stack.push(TraverseEntry::Node(prefix.clone(), gt));
+                    }
+                    let key = join(&prefix, &cur.label);
+                    if let Some(eq) = cur.eq.take() {
+                        self.stack.push(TraverseEntry::Node(key.clone(), eq));
+                    }
+                    if let Some(value) = cur.value.take() {
+                        self.stack.push(TraverseEntry::Value(key, value));
+                    }
+                    if let Some(lt) = cur.lt.take() {
+                        self.stack.push(TraverseEntry::Node(prefix, lt));
+                    }
+                }
+            }
+        }
+        None
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.size, Some(self.size)) }
+}
+
+impl<Value> ExactSizeIterator for IntoIter<Value> {
+    fn len(&self) -> usize { self.size }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RadixTSTMap;
+
+    #[test]
+    fn remove_merges_runs() {
+        let mut m = RadixTSTMap::new();
+        m.insert("abcdef", 1);
+        m.insert("abcxyz", 2);
+        assert_eq!("abc", &*m.root.eq.as_ref().unwrap().label);
+
+        m.remove("abcxyz");
+        let node = m.root.eq.as_ref().unwrap();
+        assert_eq!("abcdef", &*node.label);
+        assert!(node.eq.is_none());
+
+        m.remove("abcdef");
+        assert!(m.root.eq.is_none());
+    }
+}
@@ -0,0 +1,85 @@
+//! `Serialize` and `Deserialize` for `TSTMap` and `TSTSet`, enabled by the `serde` feature.
+//!
+//! A map is written as a map of key/value pairs and a set as a sequence of keys,
+//! both in order of keys. Input is built with `from_sorted_iter`, which sorts it first,
+//! so the trie stays shallow whatever order the input comes in.
+
+use std::fmt;
+use std::marker::PhantomData;
+use serde::ser::{Serialize, Serializer, SerializeMap, SerializeSeq};
+use serde::de::{Deserialize, Deserializer, Visitor, MapAccess, SeqAccess};
+use crate::{TSTMap, TSTSet};
+
+// don't trust size hints of the input too much
+const MAX_PREALLOC: usize = 4096;
+
+impl<Value: Serialize> Serialize for TSTMap<Value> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.iter() {
+            map.serialize_entry(&key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for TSTSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for key in self.iter() {
+            seq.serialize_element(&key)?;
+        }
+        seq.end()
+    }
+}
+
+struct MapVisitor<Value> {
+    marker: PhantomData<Value>,
+}
+
+impl<'de, Value: Deserialize<'de>> Visitor<'de> for MapVisitor<Value> {
+    type Value = TSTMap<Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map with string keys")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::with_capacity(access.size_hint().unwrap_or(0).min(MAX_PREALLOC));
+        while let Some(item) = access.next_entry::<String, Value>()? {
+            items.push(item);
+        }
+        // `from_sorted_iter` sorts stably, so the last of repeated keys wins like with inserts
+        Ok(TSTMap::from_sorted_iter(items))
+    }
+}
+
+impl<'de, Value: Deserialize<'de>> Deserialize<'de> for TSTMap<Value> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor { marker: PhantomData })
+    }
+}
+
+struct SetVisitor;
+
+impl<'de> Visitor<'de> for SetVisitor {
+    type Value = TSTSet;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of strings")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::with_capacity(access.size_hint().unwrap_or(0).min(MAX_PREALLOC));
+        while let Some(key) = access.next_element::<String>()? {
+            items.push(key);
+        }
+        Ok(TSTSet::from_sorted_iter(items))
+    }
+}
+
+impl<'de> Deserialize<'de> for TSTSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(SetVisitor)
+    }
+}
@@ -1,9 +1,12 @@
 use crate::tst_map::{self, TSTMap};
+use crate::tst_stats::Stats;
 use std::fmt::{self, Debug};
 use std::iter::{Map, FromIterator};
 
 /// A set based on a `TSTMap`.
-#[derive(Clone, PartialEq, Eq)]
+///
+/// Sets are compared and hashed by their keys in order, like `TSTMap`.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct TSTSet {
     map: TSTMap<()>,
 }
@@ -11,17 +14,20 @@ pub struct TSTSet {
 /// An iterator over a `TSTSet`'s items.
 #[derive(Clone)]
 pub struct Iter<'a> {
+    #[allow(clippy::type_complexity)]
     iter: Map<tst_map::Iter<'a, ()>, fn((String, &'a ())) -> String>
 }
 
 /// An owning iterator over a `TSTSet`'s items.
 pub struct IntoIter {
+    #[allow(clippy::type_complexity)]
     iter: Map<tst_map::IntoIter<()>, fn((String, ())) -> String>
 }
 
 /// `TSTSet` wild-card iterator.
 #[derive(Clone)]
 pub struct WildCardIter<'a> {
+    #[allow(clippy::type_complexity)]
     iter: Map<tst_map::WildCardIter<'a, ()>, fn( (String, &'a () )) -> String>,
 }
 
@@ -37,6 +43,23 @@ impl TSTSet {
     /// ```
     pub fn new() -> Self { Default::default() }
 
+    /// Makes a `TSTSet` from keys in sorted order, inserting them median-first,
+    /// so the trie comes out balanced. Unsorted keys are sorted first.
+    /// See `TSTMap::from_sorted_iter`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTSet;
+    ///
+    /// let s = TSTSet::from_sorted_iter(vec!["a", "b", "c", "c"]);
+    /// assert_eq!(3, s.len());
+    /// assert!(s.contains("b"));
+    /// ```
+    pub fn from_sorted_iter<K: AsRef<str>, I: IntoIterator<Item = K>>(iter: I) -> Self {
+        TSTSet { map: TSTMap::from_sorted_iter(iter.into_iter().map(|key| (key, ()))) }
+    }
+
     /// Returns the number of elements in the set.
     ///
     /// # Examples
@@ -100,6 +123,22 @@ impl TSTSet {
         self.map.contains_key(key)
     }
 
+    /// Returns `true` if the set contains a key given as a sequence of chars.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTSet;
+    ///
+    /// let mut s: TSTSet = TSTSet::new();
+    /// s.insert("abc");
+    /// assert!(!s.contains_chars(['a', 'b']));
+    /// assert!(s.contains_chars("abc".chars()));
+    /// ```
+    pub fn contains_chars<I: IntoIterator<Item = char>>(&self, key: I) -> bool {
+        self.map.contains_chars(key)
+    }
+
     /// Adds a value to the set.
     ///
     /// If the set did not have a value present, `true` is returned.
@@ -152,7 +191,7 @@ impl TSTSet {
     ///     println!("{}", x);
     /// }
     /// ```
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         fn first<A, B>((a, _): (A, B)) -> A { a }
         Iter { iter: self.map.iter().map(first) }
 
@@ -176,7 +215,7 @@ impl TSTSet {
     ///     println!("{}", x);
     /// }
     /// ```
-    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter {
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<'_> {
         fn first<A, B>((a, _): (A, B)) -> A { a }
         WildCardIter { iter: self.map.wildcard_iter(pat).map(first) }
     }
@@ -223,10 +262,50 @@ impl TSTSet {
     /// let first_key = set.iter().next().unwrap();
     /// assert_eq!("abc".to_string(), first_key);
     /// ```
-    pub fn prefix_iter(&self, pref: &str) -> Iter {
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_> {
         fn first<A, B>((a, _): (A, B)) -> A { a }
         Iter { iter: self.map.prefix_iter(pref).map(first) }
     }
+
+    /// Method returns iterator over all values with common prefix in the TSTSet,
+    /// the prefix is given as a sequence of chars.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTSet;
+    /// let mut set = TSTSet::new();
+    /// set.insert("abc");
+    /// set.insert("abcd");
+    /// set.insert("add");
+    ///
+    /// let keys: Vec<String> = set.prefix_iter_chars(['a', 'b']).collect();
+    /// assert_eq!(vec!["abc", "abcd"], keys);
+    /// ```
+    pub fn prefix_iter_chars<I: IntoIterator<Item = char>>(&self, pref: I) -> Iter<'_> {
+        fn first<A, B>((a, _): (A, B)) -> A { a }
+        Iter { iter: self.map.prefix_iter_chars(pref).map(first) }
+    }
+
+    /// Returns statistics of the trie shape and its estimated memory use, see `TSTMap::stats`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTSet;
+    ///
+    /// let mut s = TSTSet::new();
+    /// s.insert("abc");
+    /// s.insert("abd");
+    ///
+    /// let stats = s.stats();
+    /// assert_eq!(4, stats.nodes);
+    /// assert_eq!(2, stats.values);
+    /// // "abd" is found after the "c" node
+    /// assert_eq!(4, stats.max_depth);
+    /// ```
+    pub fn stats(&self) -> Stats {
+        self.map.stats()
+    }
 }
 
 impl IntoIterator for TSTSet {
@@ -284,7 +363,6 @@ impl Default for TSTSet {
     ///
     /// let mut s: TSTSet = TSTSet::new();
     /// ```
-
     fn default() -> Self {
         TSTSet { map: Default::default() }
     }
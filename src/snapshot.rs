@@ -0,0 +1,411 @@
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::fmt;
+use std::error::Error;
+use super::node::{Node, BoxedNode};
+use super::traverse::DropTraverse;
+
+pub(crate) const MAGIC: &[u8; 4] = b"TSTM";
+pub(crate) const VERSION: u16 = 1;
+
+const HAS_LT: u8 = 1;
+const HAS_EQ: u8 = 2;
+const HAS_GT: u8 = 4;
+const HAS_VALUE: u8 = 8;
+const LINKS: u8 = HAS_LT | HAS_EQ | HAS_GT;
+
+/// Error of reading or writing a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Underlying reader or writer failed.
+    Io(io::Error),
+    /// Input ended before the snapshot was complete.
+    Truncated,
+    /// Input doesn't start with the snapshot magic header.
+    BadMagic,
+    /// Snapshot was written in a format version this crate can't read.
+    UnsupportedVersion(u16),
+    /// Checksum of the content doesn't match the stored one.
+    ChecksumMismatch {
+        /// Checksum stored in the snapshot.
+        expected: u32,
+        /// Checksum of the content actually read.
+        actual: u32,
+    },
+    /// Node structure of the snapshot is broken.
+    Corrupted(&'static str),
+    /// `ValueCodec::decode` rejected a stored value.
+    BadValue,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotError::Io(ref err) => write!(f, "snapshot i/o error: {}", err),
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::BadMagic => write!(f, "not a TSTMap snapshot"),
+            SnapshotError::UnsupportedVersion(version) =>
+                write!(f, "unsupported snapshot version {}, expected {}", version, VERSION),
+            SnapshotError::ChecksumMismatch { expected, actual } =>
+                write!(f, "snapshot checksum mismatch: expected {:08x}, got {:08x}", expected, actual),
+            SnapshotError::Corrupted(what) => write!(f, "snapshot is corrupted: {}", what),
+            SnapshotError::BadValue => write!(f, "snapshot contains a value, which can't be decoded"),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SnapshotError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            SnapshotError::Truncated
+        } else {
+            SnapshotError::Io(err)
+        }
+    }
+}
+
+/// Encoding of values in a snapshot.
+///
+/// Implemented for primitive types, `String` and `Vec<u8>`, implement it
+/// to store values of your own types.
+/// # Examples
+///
+/// ```
+/// use tst::{TSTMap, ValueCodec};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Point(i32, i32);
+///
+/// impl ValueCodec for Point {
+///     fn encode(&self, out: &mut Vec<u8>) {
+///         self.0.encode(out);
+///         self.1.encode(out);
+///     }
+///     fn decode(bytes: &[u8]) -> Option<Self> {
+///         if bytes.len() != 8 {
+///             return None;
+///         }
+///         Some(Point(i32::decode(&bytes[..4])?, i32::decode(&bytes[4..])?))
+///     }
+/// }
+///
+/// let mut m = TSTMap::new();
+/// m.insert("a", Point(1, 2));
+///
+/// let mut buf = Vec::new();
+/// m.write_snapshot(&mut buf).unwrap();
+/// let m = TSTMap::<Point>::read_snapshot(&buf[..]).unwrap();
+/// assert_eq!(Some(&Point(1, 2)), m.get("a"));
+/// ```
+pub trait ValueCodec: Sized {
+    /// Appends encoded value to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Decodes a value from exactly the bytes written by `encode`,
+    /// returns None if they are malformed.
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! number_codec {
+    ($($t:ty),*) => {$(
+        impl ValueCodec for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn decode(bytes: &[u8]) -> Option<Self> {
+                Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+            }
+        }
+    )*};
+}
+
+number_codec!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl ValueCodec for usize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u64).encode(out)
+    }
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        usize::try_from(u64::decode(bytes)?).ok()
+    }
+}
+
+impl ValueCodec for isize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as i64).encode(out)
+    }
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        isize::try_from(i64::decode(bytes)?).ok()
+    }
+}
+
+impl ValueCodec for () {
+    fn encode(&self, _: &mut Vec<u8>) {}
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() { Some(()) } else { None }
+    }
+}
+
+impl ValueCodec for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8)
+    }
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl ValueCodec for char {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u32).encode(out)
+    }
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        char::from_u32(u32::decode(bytes)?)
+    }
+}
+
+impl ValueCodec for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes())
+    }
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl ValueCodec for Vec<u8> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self)
+    }
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+// CRC-32 (IEEE 802.3), the one used by zlib and png
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+pub(crate) fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    let mut c = !crc;
+    for &b in bytes {
+        c = CRC_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8);
+    }
+    !c
+}
+
+struct CrcWriter<W: Write> {
+    inner: W,
+    crc: u32,
+}
+
+impl<W: Write> CrcWriter<W> {
+    fn put(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.crc = crc32(self.crc, bytes);
+        self.inner.write_all(bytes)
+    }
+}
+
+struct CrcReader<R: Read> {
+    inner: R,
+    crc: u32,
+}
+
+impl<R: Read> CrcReader<R> {
+    fn get<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.inner.read_exact(&mut buf)?;
+        self.crc = crc32(self.crc, &buf);
+        Ok(buf)
+    }
+
+    // doesn't preallocate `len` bytes, so a broken length can't eat all the memory
+    fn get_vec(&mut self, len: usize, buf: &mut Vec<u8>) -> Result<(), SnapshotError> {
+        buf.clear();
+        (&mut self.inner).take(len as u64).read_to_end(buf)?;
+        if buf.len() != len {
+            return Err(SnapshotError::Truncated);
+        }
+        self.crc = crc32(self.crc, buf);
+        Ok(())
+    }
+}
+
+fn flags<Value>(node: &Node<Value>) -> u8 {
+    let mut flags = 0;
+    if node.lt.is_some() { flags |= HAS_LT; }
+    if node.eq.is_some() { flags |= HAS_EQ; }
+    if node.gt.is_some() { flags |= HAS_GT; }
+    if node.value.is_some() { flags |= HAS_VALUE; }
+    flags
+}
+
+pub(crate) fn write<Value: ValueCodec, W: Write>(root: &Node<Value>, size: usize, writer: W) -> Result<(), SnapshotError> {
+    let mut out = CrcWriter { inner: BufWriter::new(writer), crc: 0 };
+    out.put(MAGIC)?;
+    out.put(&VERSION.to_le_bytes())?;
+    out.put(&(size as u64).to_le_bytes())?;
+
+    let mut buf = Vec::new();
+    // pre-order: node, then lt, eq and gt subtrees
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        out.put(&[flags(node)])?;
+        out.put(&(node.c as u32).to_le_bytes())?;
+        if let Some(ref value) = node.value {
+            buf.clear();
+            value.encode(&mut buf);
+            let len = u32::try_from(buf.len())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "encoded value is too large"))?;
+            out.put(&len.to_le_bytes())?;
+            out.put(&buf)?;
+        }
+        for link in [&node.gt, &node.eq, &node.lt] {
+            if let Some(ref child) = link.ptr {
+                stack.push(child);
+            }
+        }
+    }
+
+    let crc = out.crc;
+    out.put(&crc.to_le_bytes())?;
+    out.inner.flush()?;
+    Ok(())
+}
+
+// partly read trees are dropped node by node, recursive drop could overflow the stack
+fn drop_tree<Value>(node: Box<Node<Value>>) {
+    let mut iter = DropTraverse::new(Some(node));
+    while iter.next().is_some() { }
+}
+
+pub(crate) fn read<Value: ValueCodec, R: Read>(reader: R) -> Result<(Node<Value>, usize), SnapshotError> {
+    let mut input = CrcReader { inner: BufReader::new(reader), crc: 0 };
+    if &input.get::<4>()? != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = u16::from_le_bytes(input.get()?);
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let size = u64::from_le_bytes(input.get()?);
+
+    let mut stack = Vec::new();
+    let result = read_nodes(&mut input, &mut stack);
+    for (node, _) in stack {
+        drop_tree(node);
+    }
+    let (root, count) = result?;
+
+    match read_tail(&mut input, count, size) {
+        Ok(()) => Ok((*root, size as usize)),
+        Err(err) => {
+            drop_tree(root);
+            Err(err)
+        }
+    }
+}
+
+fn read_tail<R: Read>(input: &mut CrcReader<R>, count: u64, size: u64) -> Result<(), SnapshotError> {
+    let actual = input.crc;
+    let expected = u32::from_le_bytes(input.get()?);
+    if expected != actual {
+        return Err(SnapshotError::ChecksumMismatch { expected, actual });
+    }
+    if count != size {
+        return Err(SnapshotError::Corrupted("number of values doesn't match the header"));
+    }
+    if input.inner.read(&mut [0])? != 0 {
+        return Err(SnapshotError::Corrupted("trailing data after the checksum"));
+    }
+    Ok(())
+}
+
+// reads nodes in pre-order, `stack` keeps nodes, which still wait for their children,
+// with flags of the missing links
+fn read_nodes<Value: ValueCodec, R: Read>(input: &mut CrcReader<R>, stack: &mut Vec<(Box<Node<Value>>, u8)>)
+    -> Result<(Box<Node<Value>>, u64), SnapshotError> {
+    let mut count = 0;
+    let mut buf = Vec::new();
+    loop {
+        let [flags] = input.get()?;
+        if flags & !(LINKS | HAS_VALUE) != 0 {
+            return Err(SnapshotError::Corrupted("unknown node flags"));
+        }
+        let c = char::from_u32(u32::from_le_bytes(input.get()?))
+            .ok_or(SnapshotError::Corrupted("invalid char"))?;
+        if stack.is_empty() && flags & (HAS_LT | HAS_GT) != 0 {
+            return Err(SnapshotError::Corrupted("root node has siblings"));
+        }
+        let mut node = Box::new(Node::<Value>::default());
+        node.c = c;
+        if flags & HAS_VALUE != 0 {
+            let len = u32::from_le_bytes(input.get()?) as usize;
+            input.get_vec(len, &mut buf)?;
+            node.value = Some(Value::decode(&buf).ok_or(SnapshotError::BadValue)?);
+            count += 1;
+        }
+        if flags & LINKS != 0 {
+            stack.push((node, flags & LINKS));
+            continue;
+        }
+
+        // the node is complete, attach it and all completed parents
+        let mut done = Some(node);
+        while let Some((mut parent, missing)) = stack.pop() {
+            let link = missing & missing.wrapping_neg();
+            let slot = match link {
+                HAS_LT => &mut parent.lt,
+                HAS_EQ => &mut parent.eq,
+                _ => &mut parent.gt,
+            };
+            *slot = BoxedNode { ptr: done.take() };
+            let missing = missing & !link;
+            if missing != 0 {
+                stack.push((parent, missing));
+                break;
+            }
+            done = Some(parent);
+        }
+        // nothing left to attach to, so it's the root
+        if let Some(root) = done {
+            return Ok((root, count));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::crc32;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(0xCBF4_3926, crc32(0, b"123456789"));
+        assert_eq!(crc32(0, b"123456789"), crc32(crc32(0, b"1234"), b"56789"));
+    }
+}
@@ -0,0 +1,454 @@
+use std::fmt::{self, Debug};
+use std::iter::FromIterator;
+use crate::tst_map::{TSTMap, Entry, Iter, IterMut, KeysIter, ValuesIter, WildCardIter, WildCardIterMut, IntoIter};
+
+///
+/// Self-adjusting flavour of `TSTMap` for skewed workloads.
+///
+/// Every lookup, insert and remove splays the nodes of its key to the top of their
+/// character levels, like in a splay tree, so keys asked for often are found in a few
+/// comparisons, and any sequence of operations takes amortized logarithmic time per level.
+/// Because of that `get` takes `&mut self`. `as_map` gives read access without reordering,
+/// prefix, wildcard and full iteration don't reorder nodes either.
+/// # Examples
+///
+/// ```rust
+/// use tst::SplayTSTMap;
+///
+/// let mut m = SplayTSTMap::new();
+/// for c in 'a'..='z' {
+///     m.insert(&c.to_string(), c);
+/// }
+/// // "q" goes to the top of the first level and stays near it
+/// assert_eq!(Some(&'q'), m.get("q"));
+/// assert_eq!(Some(&'q'), m.get("q"));
+/// assert_eq!(Some('q'), m.remove("q"));
+/// assert_eq!(25, m.len());
+/// ```
+#[derive(Clone)]
+pub struct SplayTSTMap<Value> {
+    map: TSTMap<Value>,
+}
+
+impl<Value> SplayTSTMap<Value> {
+    /// Constructs a new, empty `SplayTSTMap<Value>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    /// let mut m: SplayTSTMap<i64> = SplayTSTMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of elements in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("ab", 2);
+    /// m.insert("x", 1);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn len(&self) -> usize { self.map.len() }
+
+    /// Returns true if the `SplayTSTMap` contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// assert!(m.is_empty());
+    /// m.insert("ab", 2);
+    /// assert!(!m.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool { self.map.is_empty() }
+
+    /// Clears the `SplayTSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.clear();
+    /// assert!(m.is_empty());
+    /// ```
+    pub fn clear(&mut self) { self.map.clear() }
+
+    /// Inserts an element at key `key` with value `val`, returns the old value if there was one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// assert_eq!(None, m.insert("SOmeWOrd", 2));
+    /// assert_eq!(Some(2), m.insert("SOmeWOrd", 4));
+    /// assert_eq!(1, m.len());
+    /// ```
+    pub fn insert(&mut self, key: &str, value: Value) -> Option<Value> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Gets the given `key`'s corresponding entry for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut count: SplayTSTMap<usize> = SplayTSTMap::new();
+    ///
+    /// for x in vec!["abc","bad","abd","cdddd","abc","bade"] {
+    ///     *count.entry(x).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(Some(&2), count.get("abc"));
+    /// assert_eq!(Some(&1), count.get("abd"));
+    /// ```
+    pub fn entry(&mut self, key: &str) -> Entry<'_, Value> {
+        self.map.splay_entry(key)
+    }
+
+    /// Removes a `key`, returning the value at the key if the key was in the map.
+    /// Nodes left without keys are unlinked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("abc", 100);
+    /// assert_eq!(Some(100), m.remove("abc"));
+    /// assert_eq!(None, m.remove("abc"));
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.map.splay_remove(key)
+    }
+
+    /// Returns a reference to the value corresponding to the `key` or None,
+    /// moves the nodes of `key` to the tops of their levels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("first", 13);
+    /// assert_eq!(Some(&13), m.get("first"));
+    /// assert_eq!(None, m.get("second"));
+    /// ```
+    pub fn get(&mut self, key: &str) -> Option<&Value> {
+        self.map.splay_get_mut(key).map(|value| &*value)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the `key`,
+    /// moves the nodes of `key` to the tops of their levels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("first", 13);
+    /// if let Some(x) = m.get_mut("first") {
+    ///     *x = -13;
+    /// }
+    /// assert_eq!(Some(&-13), m.get("first"));
+    /// ```
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.map.splay_get_mut(key)
+    }
+
+    /// Returns true if the `SplayTSTMap` contains a value for the specified `key`,
+    /// moves the nodes of `key` to the tops of their levels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert!(m.contains_key("abc"));
+    /// assert!(!m.contains_key("ab"));
+    /// ```
+    pub fn contains_key(&mut self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the longest prefix of `pref`, which is a key in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// m.insert("abce", 1);
+    ///
+    /// assert_eq!("abcd", m.longest_prefix("abcde"));
+    /// ```
+    pub fn longest_prefix<'x>(&self, pref: &'x str) -> &'x str where Value: 'x {
+        self.map.longest_prefix(pref)
+    }
+
+    /// Creates an iterator over the keys matching the wildcard `pat`, `.` matches any char.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("bc", 3);
+    ///
+    /// assert_eq!(3, m.wildcard_iter(".").fold(0, |sum, (_, val)| sum + val));
+    /// ```
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<'_, Value> {
+        self.map.wildcard_iter(pat)
+    }
+
+    /// Creates a mutable iterator over the keys matching the wildcard `pat`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    ///
+    /// for (_, v) in m.wildcard_iter_mut(".") {
+    ///     *v *= 10;
+    /// }
+    /// assert_eq!(Some(&20), m.get("b"));
+    /// ```
+    pub fn wildcard_iter_mut(&mut self, pat: &str) -> WildCardIterMut<'_, Value> {
+        self.map.wildcard_iter_mut(pat)
+    }
+
+    /// Creates an iterator over the keys starting with `pref` in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.insert("b", 3);
+    ///
+    /// let keys: Vec<String> = m.prefix_iter("ab").map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["abc", "abd"], keys);
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_, Value> {
+        self.map.prefix_iter(pref)
+    }
+
+    /// Creates a mutable iterator over the keys starting with `pref`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("b", 3);
+    ///
+    /// for (_, v) in m.prefix_iter_mut("a") {
+    ///     *v += 10;
+    /// }
+    /// assert_eq!(Some(&11), m.get("abc"));
+    /// assert_eq!(Some(&3), m.get("b"));
+    /// ```
+    pub fn prefix_iter_mut(&mut self, pref: &str) -> IterMut<'_, Value> {
+        self.map.prefix_iter_mut(pref)
+    }
+
+    /// Gets an iterator over the entries in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// let keys: Vec<String> = m.iter().map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["a", "b"], keys);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Value> {
+        self.map.iter()
+    }
+
+    /// Gets a mutable iterator over the entries in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    ///
+    /// for (_, v) in m.iter_mut() {
+    ///     *v *= 2;
+    /// }
+    /// assert_eq!(Some(&4), m.get("b"));
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, Value> {
+        self.map.iter_mut()
+    }
+
+    /// An iterator visiting all keys in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// let keys: Vec<String> = m.keys().collect();
+    /// assert_eq!(vec!["a", "b"], keys);
+    /// ```
+    pub fn keys(&self) -> KeysIter<'_, Value> {
+        self.map.keys()
+    }
+
+    /// An iterator visiting all values in order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// let values: Vec<i32> = m.values().cloned().collect();
+    /// assert_eq!(vec![1, 2], values);
+    /// ```
+    pub fn values(&self) -> ValuesIter<'_, Value> {
+        self.map.values()
+    }
+
+    /// Returns the underlying `TSTMap` for reading, its lookups don't reorder nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert_eq!(Some(&1), m.as_map().get("abc"));
+    /// ```
+    pub fn as_map(&self) -> &TSTMap<Value> {
+        &self.map
+    }
+
+    /// Converts into the underlying `TSTMap`, hot keys stay near the tops of their levels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("abc", 1);
+    /// let frozen = m.into_map().freeze();
+    /// assert_eq!(Some(&1), frozen.get("abc"));
+    /// ```
+    pub fn into_map(self) -> TSTMap<Value> {
+        self.map
+    }
+}
+
+impl<Value> IntoIterator for SplayTSTMap<Value> {
+    type Item = (String, Value);
+    type IntoIter = IntoIter<Value>;
+
+    /// Creates a consuming iterator, that is, one that moves each key-value
+    /// pair out of the `SplayTSTMap`. The `SplayTSTMap` cannot be used after
+    /// calling this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::SplayTSTMap;
+    ///
+    /// let mut m = SplayTSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    ///
+    /// let vec: Vec<(String, isize)> = m.into_iter().collect();
+    /// assert_eq!(2, vec.len());
+    /// ```
+    fn into_iter(self) -> IntoIter<Value> {
+        self.map.into_iter()
+    }
+}
+
+impl<'x, Value> FromIterator<(&'x str, Value)> for SplayTSTMap<Value> {
+    fn from_iter<I: IntoIterator<Item = (&'x str, Value)>>(iter: I) -> SplayTSTMap<Value> {
+        let mut m = SplayTSTMap::new();
+        m.extend(iter);
+        m
+    }
+}
+
+impl<'x, Value> Extend<(&'x str, Value)> for SplayTSTMap<Value> {
+    #[inline]
+    fn extend<I: IntoIterator<Item=(&'x str, Value)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<Value: Debug> Debug for SplayTSTMap<Value> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<Value> Default for SplayTSTMap<Value> {
+    fn default() -> SplayTSTMap<Value> {
+        SplayTSTMap { map: TSTMap::new() }
+    }
+}
@@ -0,0 +1,130 @@
+use std::mem;
+use super::node::Node;
+
+/// Heap memory owned by a value, used by `TSTMap::stats` to estimate memory use.
+///
+/// `heap_size` counts only memory behind pointers, the value itself is a part
+/// of its node and is counted with it.
+///
+/// # Examples
+///
+/// ```
+/// use tst::HeapSize;
+///
+/// struct Record {
+///     name: String,
+///     id: u32,
+/// }
+///
+/// impl HeapSize for Record {
+///     fn heap_size(&self) -> usize {
+///         self.name.heap_size()
+///     }
+/// }
+///
+/// let r = Record { name: String::with_capacity(16), id: 1 };
+/// assert_eq!(16, r.heap_size());
+/// ```
+pub trait HeapSize {
+    /// Returns the number of heap bytes owned by the value.
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! no_heap {
+    ($($t:ty),*) => {
+        $(
+            impl HeapSize for $t {
+                fn heap_size(&self) -> usize { 0 }
+            }
+        )*
+    };
+}
+
+no_heap!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char, ());
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        mem::size_of::<T>() + (**self).heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+/// Shape and memory use of a trie, made by `TSTMap::stats`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// Number of allocated nodes.
+    pub nodes: usize,
+    /// Number of values, the same as the number of keys.
+    pub values: usize,
+    /// The largest number of nodes visited to find a key, counting `lt`/`gt` steps too.
+    pub max_depth: usize,
+    /// Average number of nodes visited to find a key.
+    pub avg_depth: f64,
+    /// `chain_lengths[n]` is the number of nodes with `n` `lt`/`gt` links between them
+    /// and the top of their character level; a long tail means degenerate levels.
+    pub chain_lengths: Vec<usize>,
+    /// `key_lengths[n]` is the number of keys of `n` chars.
+    pub key_lengths: Vec<usize>,
+    /// Estimated heap bytes: nodes and `HeapSize` of the values.
+    pub heap_bytes: usize,
+}
+
+// counts one more item in `histogram[idx]`
+fn count(histogram: &mut Vec<usize>, idx: usize) {
+    if histogram.len() <= idx {
+        histogram.resize(idx + 1, 0);
+    }
+    histogram[idx] += 1;
+}
+
+pub(crate) fn collect<Value: HeapSize>(root: &Node<Value>) -> Stats {
+    let mut stats = Stats::default();
+    let mut total_depth = 0;
+    if let Some(ref value) = root.value {
+        stats.values += 1;
+        stats.heap_bytes += value.heap_size();
+        count(&mut stats.key_lengths, 0);
+    }
+    // node, its depth, its chain length and the length of its key
+    let mut stack = vec![(root.eq.ptr.as_deref(), 1, 0, 1)];
+    while let Some((node, depth, chain, len)) = stack.pop() {
+        let node = match node {
+            Some(node) => node,
+            None => continue,
+        };
+        stats.nodes += 1;
+        stats.heap_bytes += mem::size_of::<Node<Value>>();
+        count(&mut stats.chain_lengths, chain);
+        if let Some(ref value) = node.value {
+            stats.values += 1;
+            stats.heap_bytes += value.heap_size();
+            stats.max_depth = stats.max_depth.max(depth);
+            total_depth += depth;
+            count(&mut stats.key_lengths, len);
+        }
+        stack.push((node.lt.ptr.as_deref(), depth + 1, chain + 1, len));
+        stack.push((node.eq.ptr.as_deref(), depth + 1, 0, len + 1));
+        stack.push((node.gt.ptr.as_deref(), depth + 1, chain + 1, len));
+    }
+    if stats.values > 0 {
+        stats.avg_depth = total_depth as f64 / stats.values as f64;
+    }
+    stats
+}
@@ -1,13 +1,7 @@
 use std::cmp::Ordering;
-use std::mem;
+use std::ops::ControlFlow;
 
-use super::node::{Node, NodeRef, NodeRefMut, BoxedNodeRefMut, BoxedNode};
-
-enum CompareResult<Handle> {
-    GoLeftOrRight(Handle),
-    GoDown(Handle),
-    NotFound,
-}
+use super::node::{Node, BoxedNode};
 
 #[derive(Clone)]
 struct Trace<Ref> {
@@ -42,78 +36,140 @@ enum TraverseEntry<NodeRef, ValueRef> {
     Value(ValueRef)
 }
 
+// links, value and char of a node, as split by `NodeRef::split`
+pub struct Parts<N, V> {
+    pub lt: Option<N>,
+    pub eq: Option<N>,
+    pub gt: Option<N>,
+    pub value: Option<V>,
+    pub c: char,
+}
+
+// shared or unique reference to a node; splitting a unique one hands out unique references
+// to its parts, so traversals are written once and need no pointer casts for `&mut`
+pub trait NodeRef: Sized {
+    type Value;
+
+    fn split(self) -> Parts<Self, Self::Value>;
+}
+
+impl<'x, Value> NodeRef for &'x Node<Value> {
+    type Value = &'x Value;
+
+    fn split(self) -> Parts<Self, &'x Value> {
+        Parts {
+            lt: self.lt.ptr.as_deref(),
+            eq: self.eq.ptr.as_deref(),
+            gt: self.gt.ptr.as_deref(),
+            value: self.value.as_ref(),
+            c: self.c,
+        }
+    }
+}
+
+impl<'x, Value> NodeRef for &'x mut Node<Value> {
+    type Value = &'x mut Value;
+
+    fn split(self) -> Parts<Self, &'x mut Value> {
+        let Node { lt, eq, gt, value, c } = self;
+        Parts {
+            lt: lt.ptr.as_deref_mut(),
+            eq: eq.ptr.as_deref_mut(),
+            gt: gt.ptr.as_deref_mut(),
+            value: value.as_mut(),
+            c: *c,
+        }
+    }
+}
+
+pub fn join(prefix: &str, c: char) -> String {
+    let mut key = String::with_capacity(prefix.len() + c.len_utf8());
+    key.push_str(prefix);
+    key.push(c);
+    key
+}
+
 #[derive(Clone)]
-pub struct Traverse<'x, Value: 'x> {
-    stack: Trace<TraverseEntry<(String, NodeRef<'x, Value>), (String, &'x Value)>>,
+enum KeyEntry<N, V> {
+    // subtree of a level, whose prefix is the first `usize` bytes of the key buffer
+    Node(N, usize),
+    // char of a node, which goes to the key buffer at `usize`, then its value and eq link
+    Char(char, Option<V>, Option<N>, usize),
+    Value(V, usize),
+}
+
+// keys are made in one buffer, which is cut back to the prefix of the next entry,
+// so a long key costs its length once, not once per its node
+pub struct Traverse<N: NodeRef> {
+    key: String,
+    stack: Trace<KeyEntry<N, N::Value>>,
     min_size: usize,
     max_size: usize,
 }
 
-impl<'x, Value> Traverse<'x, Value> {
-    pub fn new(node: NodeRef<'x, Value>, min: usize, max: usize) -> Self {
-        Traverse {
-            stack: Trace {
-                stack: vec![TraverseEntry::Node(("".to_string(), node))],
-            },
-            min_size: min,
-            max_size: max,
-        }
+impl<N: NodeRef> Traverse<N> {
+    pub fn new(root: N, min: usize, max: usize) -> Self {
+        let mut iter = Traverse::with_prefix(Some(root), "", max);
+        iter.min_size = min;
+        iter
     }
 
-    pub fn with_prefix(node: Option<&'x Node<Value>>, prefix: &str, max: usize) -> Self {
-        let mut iter: Traverse<Value> = Default::default();
+    pub fn with_prefix(node: Option<N>, prefix: &str, max: usize) -> Self {
+        let mut iter: Traverse<N> = Default::default();
         match node {
             None => (),
-            Some(ptr) => {
+            Some(node) => {
+                iter.key.push_str(prefix);
                 iter.max_size = max;
-                if ptr.value.is_some() {
-                    iter.min_size += 1;
-                    iter.stack.push(TraverseEntry::Value((prefix.to_string(), ptr.value.as_ref().unwrap())));
+                let parts = node.split();
+                if let Some(eq) = parts.eq {
+                    iter.stack.push(KeyEntry::Node(eq, prefix.len()));
                 }
-                if ptr.eq.ptr.is_some() {
-                    iter.stack.push(TraverseEntry::Node((prefix.to_string(), ptr.eq.as_ref())));
+                // the prefix itself goes before all its continuations
+                if let Some(value) = parts.value {
+                    iter.min_size += 1;
+                    iter.stack.push(KeyEntry::Value(value, prefix.len()));
                 }
             }
         }
         iter
     }
 
-    pub fn next(&mut self) -> Option<(String, &'x Value)> {
+    pub fn next(&mut self) -> Option<(String, N::Value)> {
         while let Some(entry) = self.stack.pop() {
-            match entry {
-                TraverseEntry::Value((prefix, value)) => {
-                    if self.min_size == self.max_size {
-                        self.min_size -= 1;
+            let value = match entry {
+                KeyEntry::Node(node, len) => {
+                    let cur = node.split();
+                    if let Some(gt) = cur.gt {
+                        self.stack.push(KeyEntry::Node(gt, len));
                     }
-                    self.max_size -= 1;
-                    return Some((prefix, value));
+                    self.stack.push(KeyEntry::Char(cur.c, cur.value, cur.eq, len));
+                    if let Some(lt) = cur.lt {
+                        self.stack.push(KeyEntry::Node(lt, len));
+                    }
+                    continue;
                 }
-                TraverseEntry::Node((prefix, node)) => {
-                    match node.as_option() {
-                        None => {}
-                        Some(cur) => {
-                            if cur.gt.is_some() {
-                                self.stack.push(TraverseEntry::Node((prefix.clone(), cur.gt.as_ref())));
-                            }
-                            if cur.eq.is_some() {
-                                let mut new_prefix = String::with_capacity(prefix.len() + 1);
-                                new_prefix.push_str(&prefix);
-                                new_prefix.push(cur.c);
-                                self.stack.push(TraverseEntry::Node((new_prefix, cur.eq.as_ref())));
-                            }
-                            if cur.value.is_some() {
-                                let mut new_prefix = String::with_capacity(prefix.len() + 1);
-                                new_prefix.push_str(&prefix);
-                                new_prefix.push(cur.c);
-                                self.stack.push(TraverseEntry::Value((new_prefix, cur.value.as_ref().unwrap())));
-                            }
-                            if cur.lt.is_some() {
-                                self.stack.push(TraverseEntry::Node((prefix, cur.lt.as_ref())));
-                            }
-                        }
+                KeyEntry::Char(c, value, eq, len) => {
+                    self.key.truncate(len);
+                    self.key.push(c);
+                    if let Some(eq) = eq {
+                        self.stack.push(KeyEntry::Node(eq, self.key.len()));
+                    }
+                    match value {
+                        Some(value) => value,
+                        None => continue,
                     }
                 }
+                KeyEntry::Value(value, len) => {
+                    self.key.truncate(len);
+                    value
+                }
+            };
+            if self.min_size == self.max_size {
+                self.min_size -= 1;
             }
+            self.max_size -= 1;
+            return Some((self.key.clone(), value));
         }
         None
     }
@@ -123,9 +179,22 @@ impl<'x, Value> Traverse<'x, Value> {
     }
 }
 
-impl<'x, Value> Default for Traverse<'x, Value> {
+// only shared traversals can be cloned, a unique one would hand out the same `&mut` twice
+impl<N: NodeRef + Copy> Clone for Traverse<N> where N::Value: Copy {
+    fn clone(&self) -> Self {
+        Traverse {
+            key: self.key.clone(),
+            stack: self.stack.clone(),
+            min_size: self.min_size,
+            max_size: self.max_size,
+        }
+    }
+}
+
+impl<N: NodeRef> Default for Traverse<N> {
     fn default() -> Self {
         Traverse {
+            key: String::new(),
             stack: Default::default(),
             min_size: 0,
             max_size: 0,
@@ -134,16 +203,20 @@ impl<'x, Value> Default for Traverse<'x, Value> {
 }
 
 pub struct IntoTraverse<Value> {
+    #[allow(clippy::type_complexity)]
     stack: Trace<TraverseEntry<(String, Option<Box<Node<Value>>>), (String, Value)>>,
     pub size: usize,
 }
 
 impl<Value> IntoTraverse<Value> {
-    pub fn new(node: Option<Box<Node<Value>>>, size: usize) -> Self {
+    pub fn new(mut root: Node<Value>, size: usize) -> Self {
+        let mut stack = Trace::new(2);
+        stack.push(TraverseEntry::Node(("".to_string(), root.eq.take())));
+        if let Some(value) = root.value.take() {
+            stack.push(TraverseEntry::Value(("".to_string(), value)));
+        }
         IntoTraverse {
-            stack: Trace {
-                stack: vec![TraverseEntry::Node(("".to_string(), node))],
-            },
+            stack,
             size,
         }
     }
@@ -232,17 +305,22 @@ impl<Value> DropTraverse<Value> {
 
 #[derive(Clone)]
 pub struct ValuesTraverse<'x, Value: 'x> {
-    stack: Trace<TraverseEntry<NodeRef<'x, Value>, &'x Value>>,
+    stack: Trace<TraverseEntry<&'x Node<Value>, &'x Value>>,
     min_size: usize,
     max_size: usize,
 }
 
 impl<'x, Value> ValuesTraverse<'x, Value> {
-    pub fn new(node: NodeRef<'x, Value>, min: usize, max: usize) -> Self {
+    pub fn new(root: &'x Node<Value>, min: usize, max: usize) -> Self {
+        let mut stack = Trace::new(2);
+        if let Some(eq) = root.eq.ptr.as_deref() {
+            stack.push(TraverseEntry::Node(eq));
+        }
+        if let Some(ref value) = root.value {
+            stack.push(TraverseEntry::Value(value));
+        }
         ValuesTraverse {
-            stack: Trace {
-                stack: vec![TraverseEntry::Node(node)],
-            },
+            stack,
             min_size: min,
             max_size: max,
         }
@@ -258,23 +336,18 @@ impl<'x, Value> ValuesTraverse<'x, Value> {
                     self.max_size -= 1;
                     return Some(value);
                 }
-                TraverseEntry::Node(node) => {
-                    match node.as_option() {
-                        None => {}
-                        Some(cur) => {
-                            if cur.gt.is_some() {
-                                self.stack.push(TraverseEntry::Node(cur.gt.as_ref()));
-                            }
-                            if cur.eq.is_some() {
-                                self.stack.push(TraverseEntry::Node(cur.eq.as_ref()));
-                            }
-                            if cur.value.is_some() {
-                                self.stack.push(TraverseEntry::Value(cur.value.as_ref().unwrap()));
-                            }
-                            if cur.lt.is_some() {
-                                self.stack.push(TraverseEntry::Node(cur.lt.as_ref()));
-                            }
-                        }
+                TraverseEntry::Node(cur) => {
+                    if let Some(gt) = cur.gt.ptr.as_deref() {
+                        self.stack.push(TraverseEntry::Node(gt));
+                    }
+                    if let Some(eq) = cur.eq.ptr.as_deref() {
+                        self.stack.push(TraverseEntry::Node(eq));
+                    }
+                    if let Some(ref value) = cur.value {
+                        self.stack.push(TraverseEntry::Value(value));
+                    }
+                    if let Some(lt) = cur.lt.ptr.as_deref() {
+                        self.stack.push(TraverseEntry::Node(lt));
                     }
                 }
             }
@@ -287,25 +360,38 @@ impl<'x, Value> ValuesTraverse<'x, Value> {
     }
 }
 
-#[derive(Clone)]
-pub struct WildCardTraverse<'x, Value: 'x> {
-    stack: Trace<TraverseEntry<(String, NodeRef<'x, Value>, usize), (String, &'x Value)>>,
+pub struct WildCardTraverse<N: NodeRef> {
+    #[allow(clippy::type_complexity)]
+    stack: Trace<TraverseEntry<(String, N, usize), (String, N::Value)>>,
     max_size: usize,
     pat: Vec<char>,
 }
 
-impl<'x, Value> WildCardTraverse<'x, Value> {
-    pub fn new(node: NodeRef<'x, Value>, pat: &str, max: usize) -> Self {
+impl<N: NodeRef> WildCardTraverse<N> {
+    pub fn new(root: N, pat: &str, max: usize) -> Self {
+        WildCardTraverse::with_prefix(root, "", pat, max)
+    }
+
+    // matches keys under `root` against `pat` and puts `prefix` before them
+    pub fn with_prefix(root: N, prefix: &str, pat: &str, max: usize) -> Self {
+        let mut stack = Trace::new(1);
+        let root = root.split();
+        if !pat.is_empty() {
+            if let Some(eq) = root.eq {
+                stack.push(TraverseEntry::Node((prefix.to_string(), eq, 0)));
+            }
+        } else if let Some(value) = root.value {
+            // only the empty key matches the empty pattern
+            stack.push(TraverseEntry::Value((prefix.to_string(), value)));
+        }
         WildCardTraverse {
-            stack: Trace{
-                stack: vec![TraverseEntry::Node(("".to_string(), node, 0))],
-            },
+            stack,
             max_size: max,
             pat: pat.chars().collect(),
         }
     }
 
-    pub fn next(&mut self) -> Option<(String, &'x Value)> {
+    pub fn next(&mut self) -> Option<(String, N::Value)> {
         while let Some(entry) = self.stack.pop() {
             match entry {
                 TraverseEntry::Value((prefix, value)) => {
@@ -313,33 +399,22 @@ impl<'x, Value> WildCardTraverse<'x, Value> {
                     return Some((prefix, value));
                 }
                 TraverseEntry::Node((prefix, node, idx)) => {
-                    match node.as_option() {
-                        None => {}
-                        Some(cur) => {
-                            let ch = self.pat[idx];
-                            if (ch == '.' || ch > cur.c) && cur.gt.is_some() {
-                                self.stack.push(TraverseEntry::Node((prefix.clone(), cur.gt.as_ref(), idx)));
-                            }
-                            if ch == '.' || ch == cur.c {
-                                if idx+1 < self.pat.len() && cur.eq.is_some() {
-                                    let mut new_prefix = String::with_capacity(prefix.len() + 1);
-                                    new_prefix.push_str(&prefix);
-                                    new_prefix.push(cur.c);
-                                    self.stack.push(TraverseEntry::Node((new_prefix, cur.eq.as_ref(), idx+1)));
-                                }
-
-                                if idx+1 == self.pat.len() && cur.value.is_some() {
-                                    let mut new_prefix = String::with_capacity(prefix.len() + 1);
-                                    new_prefix.push_str(&prefix);
-                                    new_prefix.push(cur.c);
-                                    self.stack.push(TraverseEntry::Value((new_prefix, cur.value.as_ref().unwrap())));
-                                }
-                            }
-                            if (ch == '.' || ch < cur.c) && cur.lt.is_some() {
-                                self.stack.push(TraverseEntry::Node((prefix, cur.lt.as_ref(), idx)));
-                            }
+                    let cur = node.split();
+                    let ch = self.pat[idx];
+                    if let Some(gt) = cur.gt && (ch == '.' || ch > cur.c) {
+                        self.stack.push(TraverseEntry::Node((prefix.clone(), gt, idx)));
+                    }
+                    if ch == '.' || ch == cur.c {
+                        if let Some(eq) = cur.eq && idx+1 < self.pat.len() {
+                            self.stack.push(TraverseEntry::Node((join(&prefix, cur.c), eq, idx+1)));
+                        }
+                        if let Some(value) = cur.value && idx+1 == self.pat.len() {
+                            self.stack.push(TraverseEntry::Value((join(&prefix, cur.c), value)));
                         }
                     }
+                    if let Some(lt) = cur.lt && (ch == '.' || ch < cur.c) {
+                        self.stack.push(TraverseEntry::Node((prefix, lt, idx)));
+                    }
                 }
             }
         }
@@ -350,143 +425,718 @@ impl<'x, Value> WildCardTraverse<'x, Value> {
     }
 }
 
-fn lookup_next<'x, Value>(node: &NodeRef<'x, Value>, ch: char) -> CompareResult<NodeRef<'x, Value>> {
-    match node.as_option() {
-        None => CompareResult::NotFound,
-        Some(cur) => {
-            match ch.cmp(&cur.c) {
-                Ordering::Less => CompareResult::GoLeftOrRight(cur.lt.as_ref()),
-                Ordering::Greater => CompareResult::GoLeftOrRight(cur.gt.as_ref()),
-                Ordering::Equal => CompareResult::GoDown(cur.eq.as_ref()),
-            }
+// like `Traverse`, only a shared one can be cloned
+impl<N: NodeRef + Copy> Clone for WildCardTraverse<N> where N::Value: Copy {
+    fn clone(&self) -> Self {
+        WildCardTraverse {
+            stack: self.stack.clone(),
+            max_size: self.max_size,
+            pat: self.pat.clone(),
         }
     }
 }
 
-fn lookup_next_mut<'x, Value>(node: &BoxedNodeRefMut<'x, Value>, ch: char) -> CompareResult<BoxedNodeRefMut<'x, Value>> {
-    match node.as_mut().ptr {
-        None => CompareResult::NotFound,
-        Some(ref mut cur) => {
-            match ch.cmp(&cur.c) {
-                Ordering::Less => CompareResult::GoLeftOrRight(cur.lt.as_mut()),
-                Ordering::Greater => CompareResult::GoLeftOrRight(cur.gt.as_mut()),
-                Ordering::Equal => CompareResult::GoDown(cur.eq.as_mut()),
-            }
-        }
-    }
+enum VisitEntry<'x, Value: 'x> {
+    // subtree of a level, whose prefix is the first `usize` bytes of the key buffer
+    Node(&'x Node<Value>, usize, usize),
+    // the node itself: its char goes to the key buffer, then its value and eq link
+    Inner(&'x Node<Value>, usize, usize),
 }
 
-pub fn search<'x, Value>(mut node: NodeRef<'x, Value>, key: &str) ->
-        Option<&'x Node<Value>>
+// calls `f` for the keys under `node` in order of keys, `node` is the node of `prefix`;
+// if `pat` is given, only keys of `prefix` followed by what matches `pat` are visited.
+// All keys are made in one buffer, so nothing is allocated per key.
+pub fn visit<'x, Value, B, F>(node: Option<&'x Node<Value>>, prefix: &str, pat: Option<&str>, f: F)
+    -> ControlFlow<B>
+    where F: FnMut(&str, &'x Value) -> ControlFlow<B>
 {
-    let mut last = Default::default();
+    visit_with(node, &mut prefix.to_string(), pat, f)
+}
 
-    for ch in key.chars() {
-        let mut go_next = false;
-        while !go_next {
-            node = match lookup_next(&node, ch) {
-                CompareResult::GoLeftOrRight(next) => next,
-                CompareResult::GoDown(next) => {
-                    go_next = true;
-                    last = node;
-                    next
-                },
-                CompareResult::NotFound => {
-                    return None;
-                },
-            }
+// `visit` with the prefix in the `key` buffer, which is left as it was,
+// so a caller visiting many nodes can share one buffer
+pub fn visit_with<'x, Value, B, F>(node: Option<&'x Node<Value>>, key: &mut String, pat: Option<&str>, f: F)
+    -> ControlFlow<B>
+    where F: FnMut(&str, &'x Value) -> ControlFlow<B>
+{
+    let len = key.len();
+    let ret = visit_below(node, key, pat, f);
+    key.truncate(len);
+    ret
+}
+
+fn visit_below<'x, Value, B, F>(node: Option<&'x Node<Value>>, key: &mut String, pat: Option<&str>, mut f: F)
+    -> ControlFlow<B>
+    where F: FnMut(&str, &'x Value) -> ControlFlow<B>
+{
+    let node = match node {
+        Some(node) => node,
+        None => return ControlFlow::Continue(()),
+    };
+    // only the empty key matches the empty pattern
+    if pat == Some("") {
+        return node.value.as_ref().map_or(ControlFlow::Continue(()), |value| f(key, value));
+    }
+    // the pattern position is a byte offset in `pat`
+    let wildcard = pat.is_some();
+    let pat = pat.unwrap_or("");
+    if let Some(ref value) = node.value && !wildcard {
+        f(key, value)?;
+    }
+    let mut stack = Vec::new();
+    if let Some(eq) = node.eq.ptr.as_deref() {
+        stack.push(VisitEntry::Node(eq, key.len(), 0));
+    }
+    while let Some(entry) = stack.pop() {
+        match entry {
+            VisitEntry::Node(cur, len, idx) => {
+                let ch = pat[idx..].chars().next();
+                let any = !wildcard || ch == Some('.');
+                if let Some(gt) = cur.gt.ptr.as_deref() && (any || ch > Some(cur.c)) {
+                    stack.push(VisitEntry::Node(gt, len, idx));
+                }
+                if any || ch == Some(cur.c) {
+                    stack.push(VisitEntry::Inner(cur, len, idx));
+                }
+                if let Some(lt) = cur.lt.ptr.as_deref() && (any || ch < Some(cur.c)) {
+                    stack.push(VisitEntry::Node(lt, len, idx));
+                }
+            },
+            VisitEntry::Inner(cur, len, idx) => {
+                key.truncate(len);
+                key.push(cur.c);
+                let next = if wildcard { idx + pat[idx..].chars().next().map_or(0, char::len_utf8) } else { idx };
+                let last = next == pat.len();
+                if let Some(ref value) = cur.value && last {
+                    f(key, value)?;
+                }
+                if let Some(eq) = cur.eq.ptr.as_deref() && (!wildcard || !last) {
+                    stack.push(VisitEntry::Node(eq, key.len(), next));
+                }
+            },
         }
     }
-    last.as_option()
+    ControlFlow::Continue(())
 }
 
-pub fn insert<'x, Value>(mut node: BoxedNodeRefMut<'x, Value>, key: &str) -> &'x mut Node<Value> {
-    let mut last = Default::default();
+pub fn search<Value, I>(root: &Node<Value>, key: I) ->
+        Option<&Node<Value>>
+    where I: IntoIterator<Item = char>
+{
+    let mut node = root;
+    for ch in key {
+        node = level_find(&node.eq, ch)?;
+    }
+    Some(node)
+}
 
-    for ch in key.chars() {
-        let mut go_next = false;
-        while !go_next {
-            node = match lookup_next_mut(&node, ch) {
-                CompareResult::GoLeftOrRight(next) => next,
-                CompareResult::GoDown(next) => {
-                    go_next = true;
-                    last = node;
-                    next
-                },
-                CompareResult::NotFound => {
-                    node.assign(BoxedNode::new(ch));
-                    node
+pub fn insert<'x, Value>(root: &'x mut Node<Value>, key: &str) -> &'x mut Node<Value> {
+    if key.is_empty() {
+        return root;
+    }
+    insert_level(&mut root.eq, key)
+}
+
+// inserts a non-empty `key` starting at the level of `slot`
+pub fn insert_level<'x, Value>(mut slot: &'x mut BoxedNode<Value>, key: &str) -> &'x mut Node<Value> {
+    let mut chars = key.chars();
+    let mut ch = chars.next().unwrap();
+    loop {
+        let cur = slot.ptr.get_or_insert_with(|| Box::new(Node { c: ch, ..Default::default() }));
+        slot = match ch.cmp(&cur.c) {
+            Ordering::Less => &mut cur.lt,
+            Ordering::Greater => &mut cur.gt,
+            Ordering::Equal => match chars.next() {
+                Some(next) => {
+                    ch = next;
+                    &mut cur.eq
                 },
-            }
-        }
+                None => return cur,
+            },
+        };
     }
-    last.as_node_ref()
 }
 
-pub fn search_mut<'x, Value>(node: NodeRefMut<'x, Value>, key: &str) ->
+pub fn search_mut<'x, Value>(root: &'x mut Node<Value>, key: &str) ->
         Option<&'x mut Node<Value>>
 {
-    unsafe { mem::transmute(search(node.into_immut(), key)) }
+    let mut node = root;
+    for ch in key.chars() {
+        node = level_find_mut(&mut node.eq, ch)?;
+    }
+    Some(node)
 }
 
-pub fn longest_prefix<'x, Value>(mut node: NodeRef<'x, Value>, pref: &'x str) -> &'x str {
+pub fn longest_prefix<'x, Value>(root: &Node<Value>, pref: &'x str) -> &'x str {
+    let mut node = root;
     let mut length: usize = 0;
-    let mut i: usize = 0;
-    for ch in pref.chars() {
-        let mut go_next = false;
-        while !go_next {
-            node = match lookup_next(&node, ch) {
-                CompareResult::GoLeftOrRight(next) => next,
-                CompareResult::GoDown(next) => {
-                    go_next = true;
-                    i += 1;
-                    if node.is_value() { length = i; }
-                    next
-                },
-                CompareResult::NotFound => {
-                    return &pref[..length];
-                },
-            }
+    for (i, ch) in pref.char_indices() {
+        node = match level_find(&node.eq, ch) {
+            Some(next) => next,
+            None => break,
+        };
+        if node.value.is_some() {
+            length = i + ch.len_utf8();
         }
     }
     &pref[..length]
 }
 
-pub fn remove<Value>(mut node: BoxedNodeRefMut<Value>, key: &str) -> Option<Value> {
-    let mut stack = Trace::<BoxedNodeRefMut<Value>>::new(key.len());
-    let mut ptr = None;
+pub fn remove<Value>(root: &mut Node<Value>, key: &str) -> Option<Value> {
+    let ret = search_mut(root, key)?.value.take();
+    if ret.is_none() || key.is_empty() {
+        return ret;
+    }
 
-    for ch in key.chars() {
-        let mut go_next = false;
-        while !go_next {
-            stack.push(node.clone());
-            node = match lookup_next_mut(&node, ch) {
-                CompareResult::GoLeftOrRight(next) => next,
-                CompareResult::GoDown(next) => {
-                    go_next = true;
-                    ptr = Some(node.as_node_ref());
-                    next
-                },
-                CompareResult::NotFound => {
-                    return None;
+    // steps from `root.eq` down to the node of `key`, and the first one of the trailing
+    // steps, whose nodes are left with nothing but the link to the next step
+    let mut steps = Vec::new();
+    let mut cut = None;
+    let mut node = root.eq.ptr.as_deref();
+    let mut chars = key.chars().peekable();
+    while let (Some(cur), Some(&ch)) = (node, chars.peek()) {
+        let step = ch.cmp(&cur.c);
+        if step == Ordering::Equal {
+            chars.next();
+        }
+        let (next, others) = match step {
+            Ordering::Less => (&cur.lt, [&cur.eq, &cur.gt]),
+            Ordering::Equal => (&cur.eq, [&cur.lt, &cur.gt]),
+            Ordering::Greater => (&cur.gt, [&cur.lt, &cur.eq]),
+        };
+        // the node of `key` keeps its eq link too
+        let last = chars.peek().is_none() && step == Ordering::Equal;
+        if cur.value.is_none() && others.iter().all(|link| link.ptr.is_none()) && !(last && next.is_some()) {
+            cut = cut.or(Some(steps.len()));
+        } else {
+            cut = None;
+        }
+        steps.push(step);
+        node = if last { None } else { next.ptr.as_deref() };
+    }
+
+    if let Some(cut) = cut {
+        let mut slot = &mut root.eq;
+        for step in &steps[..cut] {
+            let cur = slot.ptr.as_deref_mut().unwrap();
+            slot = match step {
+                Ordering::Less => &mut cur.lt,
+                Ordering::Equal => &mut cur.eq,
+                Ordering::Greater => &mut cur.gt,
+            };
+        }
+        // the cut tail may be as long as the key, so it's freed without recursion
+        let mut iter = DropTraverse::new(slot.take());
+        while iter.next().is_some() { }
+    }
+    ret
+}
+
+// rebuilds lt/gt tree of every level into a balanced one, moving the same boxes around;
+// levels go through an explicit stack, so long keys don't overflow the call stack
+pub fn rebalance<Value>(root: &mut Node<Value>) {
+    let mut levels = vec![&mut root.eq];
+
+    while let Some(slot) = levels.pop() {
+        let mut nodes = Vec::new();
+        let mut cur = slot.take();
+        let mut stack = Vec::new();
+        // in-order walk, detaches lt/gt links, so nodes come out sorted by char
+        loop {
+            while let Some(mut node) = cur {
+                cur = node.lt.take();
+                stack.push(node);
+            }
+            match stack.pop() {
+                Some(mut node) => {
+                    cur = node.gt.take();
+                    nodes.push(Some(node));
                 },
+                None => break,
+            }
+        }
+        slot.ptr = build_balanced(&mut nodes);
+
+        let mut level: Vec<&mut Node<Value>> = slot.ptr.as_deref_mut().into_iter().collect();
+        while let Some(node) = level.pop() {
+            let Node { lt, eq, gt, .. } = node;
+            level.extend(lt.ptr.as_deref_mut());
+            level.extend(gt.ptr.as_deref_mut());
+            if eq.is_some() {
+                levels.push(eq);
+            }
+        }
+    }
+}
+
+// links sorted nodes of one level median-first, recursion depth is log2 of their number
+fn build_balanced<Value>(nodes: &mut [Option<Box<Node<Value>>>]) -> Option<Box<Node<Value>>> {
+    if nodes.is_empty() {
+        return None;
+    }
+    let mid = nodes.len() / 2;
+    let (left, rest) = nodes.split_at_mut(mid);
+    let (node, right) = rest.split_first_mut().unwrap();
+    let mut node = node.take().unwrap();
+    node.lt.ptr = build_balanced(left);
+    node.gt.ptr = build_balanced(right);
+    Some(node)
+}
+
+// priority of a node in a treap level, a bijective mix of the char (murmur3 finalizer),
+// so different chars never tie and the shape doesn't depend on the order of inserts
+fn priority(c: char) -> u32 {
+    let mut h = c as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
+
+fn rotate_right<Value>(slot: &mut BoxedNode<Value>) {
+    let mut node = slot.take().unwrap();
+    let mut lt = node.lt.take().unwrap();
+    node.lt.ptr = lt.gt.take();
+    lt.gt.ptr = Some(node);
+    slot.ptr = Some(lt);
+}
+
+fn rotate_left<Value>(slot: &mut BoxedNode<Value>) {
+    let mut node = slot.take().unwrap();
+    let mut gt = node.gt.take().unwrap();
+    node.gt.ptr = gt.lt.take();
+    gt.lt.ptr = Some(node);
+    slot.ptr = Some(gt);
+}
+
+// adds `ch` to the level in `slot` if it's not there, restoring the heap order on the way back;
+// recursion depth is the height of the level, which is logarithmic
+fn level_insert<Value>(slot: &mut BoxedNode<Value>, ch: char) {
+    let node = match slot.ptr.as_deref_mut() {
+        None => {
+            *slot = BoxedNode::new(ch);
+            return;
+        },
+        Some(node) => node,
+    };
+    match ch.cmp(&node.c) {
+        Ordering::Less => {
+            level_insert(&mut node.lt, ch);
+            if priority(node.lt.ptr.as_ref().unwrap().c) > priority(node.c) {
+                rotate_right(slot);
             }
+        },
+        Ordering::Greater => {
+            level_insert(&mut node.gt, ch);
+            if priority(node.gt.ptr.as_ref().unwrap().c) > priority(node.c) {
+                rotate_left(slot);
+            }
+        },
+        Ordering::Equal => {},
+    }
+}
 
+pub fn level_find<Value>(slot: &BoxedNode<Value>, ch: char) -> Option<&Node<Value>> {
+    let mut cur = slot.ptr.as_deref();
+    while let Some(node) = cur {
+        cur = match ch.cmp(&node.c) {
+            Ordering::Less => node.lt.ptr.as_deref(),
+            Ordering::Greater => node.gt.ptr.as_deref(),
+            Ordering::Equal => return Some(node),
+        };
+    }
+    None
+}
+
+fn level_find_mut<Value>(slot: &mut BoxedNode<Value>, ch: char) -> Option<&mut Node<Value>> {
+    let mut cur = slot.ptr.as_deref_mut();
+    while let Some(node) = cur {
+        cur = match ch.cmp(&node.c) {
+            Ordering::Less => node.lt.ptr.as_deref_mut(),
+            Ordering::Greater => node.gt.ptr.as_deref_mut(),
+            Ordering::Equal => return Some(node),
+        };
+    }
+    None
+}
+
+// takes the node of `ch` out of the level, rotating it down until it has at most one child
+fn level_remove<Value>(slot: &mut BoxedNode<Value>, ch: char) -> Box<Node<Value>> {
+    let node = slot.ptr.as_deref_mut().unwrap();
+    match ch.cmp(&node.c) {
+        Ordering::Less => return level_remove(&mut node.lt, ch),
+        Ordering::Greater => return level_remove(&mut node.gt, ch),
+        Ordering::Equal => {},
+    }
+    match (node.lt.ptr.as_deref(), node.gt.ptr.as_deref()) {
+        (Some(lt), Some(gt)) => {
+            if priority(lt.c) > priority(gt.c) {
+                rotate_right(slot);
+                level_remove(&mut slot.ptr.as_deref_mut().unwrap().gt, ch)
+            } else {
+                rotate_left(slot);
+                level_remove(&mut slot.ptr.as_deref_mut().unwrap().lt, ch)
+            }
+        },
+        (Some(_), None) => {
+            let mut node = slot.take().unwrap();
+            slot.ptr = node.lt.take();
+            node
+        },
+        (None, _) => {
+            let mut node = slot.take().unwrap();
+            slot.ptr = node.gt.take();
+            node
+        },
+    }
+}
+
+// the depth of the first node of the trailing run of `key`'s path, which keeps nothing
+// but the rest of the run, so the run can be cut off; `key` must be in the trie
+fn trailing_run<Value>(root: &Node<Value>, key: &str) -> Option<usize> {
+    let last = key.chars().count() - 1;
+    let mut cut = None;
+    let mut node = root;
+    for (i, ch) in key.chars().enumerate() {
+        node = level_find(&node.eq, ch).unwrap();
+        let single = match node.eq.ptr.as_deref() {
+            None => true,
+            Some(next) => i < last && next.lt.ptr.is_none() && next.gt.ptr.is_none(),
+        };
+        if node.value.is_none() && single {
+            cut = cut.or(Some(i));
+        } else {
+            cut = None;
         }
     }
-    let ret = match ptr {
-        None => None,
-        Some(ptr) => ptr.value.take(),
+    cut
+}
+
+// like `insert`, but keeps every level a treap
+pub fn treap_insert<'x, Value>(root: &'x mut Node<Value>, key: &str) -> &'x mut Node<Value> {
+    let mut node = root;
+    for ch in key.chars() {
+        level_insert(&mut node.eq, ch);
+        node = level_find_mut(&mut node.eq, ch).unwrap();
+    }
+    node
+}
+
+// like `remove`, but unlinks nodes left without keys by rotating them down their levels
+pub fn treap_remove<Value>(root: &mut Node<Value>, key: &str) -> Option<Value> {
+    let mut node = &mut *root;
+    for ch in key.chars() {
+        node = level_find_mut(&mut node.eq, ch)?;
+    }
+    let ret = node.value.take();
+    if ret.is_none() || key.is_empty() {
+        return ret;
+    }
+
+    let cut = match trailing_run(root, key) {
+        Some(cut) => cut,
+        // longer keys go through the last node
+        None => return ret,
+    };
+
+    let mut node = &mut *root;
+    for ch in key.chars().take(cut) {
+        node = level_find_mut(&mut node.eq, ch).unwrap();
+    }
+    let ch = key.chars().nth(cut).unwrap();
+    let tail = level_remove(&mut node.eq, ch);
+    let mut iter = DropTraverse::new(Some(tail));
+    while iter.next().is_some() { }
+    ret
+}
+
+// top-down splay of the level in `slot`: brings the node of `ch` to the top or,
+// if there is no such char, the last node on the way to it; it's iterative,
+// because a splayed level may be as deep as the number of its chars
+fn splay<Value>(slot: &mut BoxedNode<Value>, ch: char) {
+    let mut top = match slot.take() {
+        Some(top) => top,
+        None => return,
     };
-    // cut the tail
-    if ret.is_some() {
-        while let Some(mut node_to_drop) = stack.pop() {
-            let ptr = node_to_drop.as_node_ref();
-            if !ptr.is_leaf() {
-                break;
+    // nodes less and greater than `ch`, each next one goes under the previous
+    let mut left: Vec<Box<Node<Value>>> = Vec::new();
+    let mut right: Vec<Box<Node<Value>>> = Vec::new();
+    loop {
+        match ch.cmp(&top.c) {
+            Ordering::Less => {
+                let mut lt = match top.lt.take() {
+                    Some(lt) => lt,
+                    None => break,
+                };
+                if ch < lt.c {
+                    // zig-zig, rotate right first
+                    top.lt.ptr = lt.gt.take();
+                    lt.gt.ptr = Some(top);
+                    top = lt;
+                    lt = match top.lt.take() {
+                        Some(lt) => lt,
+                        None => break,
+                    };
+                }
+                right.push(top);
+                top = lt;
+            },
+            Ordering::Greater => {
+                let mut gt = match top.gt.take() {
+                    Some(gt) => gt,
+                    None => break,
+                };
+                if ch > gt.c {
+                    // zig-zig, rotate left first
+                    top.gt.ptr = gt.lt.take();
+                    gt.lt.ptr = Some(top);
+                    top = gt;
+                    gt = match top.gt.take() {
+                        Some(gt) => gt,
+                        None => break,
+                    };
+                }
+                left.push(top);
+                top = gt;
+            },
+            Ordering::Equal => break,
+        }
+    }
+    let mut lt = top.lt.take();
+    for mut node in left.into_iter().rev() {
+        node.gt.ptr = lt;
+        lt = Some(node);
+    }
+    let mut gt = top.gt.take();
+    for mut node in right.into_iter().rev() {
+        node.lt.ptr = gt;
+        gt = Some(node);
+    }
+    top.lt.ptr = lt;
+    top.gt.ptr = gt;
+    slot.ptr = Some(top);
+}
+
+// like `search_mut`, but splays every node of `key` to the top of its level
+pub fn splay_search<'x, Value>(root: &'x mut Node<Value>, key: &str) -> Option<&'x mut Node<Value>> {
+    let mut node = root;
+    for ch in key.chars() {
+        splay(&mut node.eq, ch);
+        node = match node.eq.ptr.as_deref_mut() {
+            Some(next) if next.c == ch => next,
+            _ => return None,
+        };
+    }
+    Some(node)
+}
+
+// like `insert`, but splays every node of `key` to the top of its level,
+// a new node goes to the top, splitting the level around it
+pub fn splay_insert<'x, Value>(root: &'x mut Node<Value>, key: &str) -> &'x mut Node<Value> {
+    let mut node = root;
+    for ch in key.chars() {
+        splay(&mut node.eq, ch);
+        if node.eq.ptr.as_ref().is_none_or(|top| top.c != ch) {
+            let mut new = BoxedNode::new(ch);
+            let next = new.ptr.as_mut().unwrap();
+            if let Some(mut top) = node.eq.take() {
+                if ch < top.c {
+                    next.lt.ptr = top.lt.take();
+                    next.gt.ptr = Some(top);
+                } else {
+                    next.gt.ptr = top.gt.take();
+                    next.lt.ptr = Some(top);
+                }
             }
-            node_to_drop.assign(Default::default());
+            node.eq = new;
         }
+        node = node.eq.ptr.as_deref_mut().unwrap();
+    }
+    node
+}
+
+// like `remove`, but splays the nodes of `key` and unlinks the ones left without keys
+pub fn splay_remove<Value>(root: &mut Node<Value>, key: &str) -> Option<Value> {
+    let ret = splay_search(root, key)?.value.take();
+    if ret.is_none() || key.is_empty() {
+        return ret;
+    }
+    let cut = match trailing_run(root, key) {
+        Some(cut) => cut,
+        // longer keys go through the last node
+        None => return ret,
+    };
+
+    // the nodes of `key` are on top of their levels now
+    let mut node = &mut *root;
+    for _ in 0..cut {
+        node = node.eq.ptr.as_deref_mut().unwrap();
     }
+    let mut tail = node.eq.take().unwrap();
+    // join the rest of the level: the greatest of the lesser chars goes to the top
+    node.eq.ptr = tail.lt.take();
+    if node.eq.is_some() {
+        splay(&mut node.eq, char::MAX);
+        node.eq.ptr.as_mut().unwrap().gt.ptr = tail.gt.take();
+    } else {
+        node.eq.ptr = tail.gt.take();
+    }
+    let mut iter = DropTraverse::new(Some(tail));
+    while iter.next().is_some() { }
     ret
 }
+
+// internal tests
+#[cfg(test)]
+mod test {
+    use super::{Node, priority, insert, remove, treap_insert, treap_remove, splay_insert, splay_search, splay_remove};
+
+    // checks order of every level and heap priorities for a treap, returns the max level height and node count
+    fn check_levels<Value>(root: &Node<Value>, treap: bool) -> (usize, usize) {
+        let mut height = 0;
+        let mut count = 0;
+        let mut stack = vec![(root.eq.ptr.as_deref(), 1, None::<char>, None::<char>)];
+        while let Some((node, depth, min, max)) = stack.pop() {
+            let node = match node {
+                Some(node) => node,
+                None => continue,
+            };
+            count += 1;
+            height = height.max(depth);
+            assert!(min.is_none_or(|min| min < node.c));
+            assert!(max.is_none_or(|max| node.c < max));
+            for child in [&node.lt, &node.gt].into_iter().filter_map(|link| link.ptr.as_deref()) {
+                assert!(!treap || priority(child.c) < priority(node.c));
+            }
+            // nodes without keys under them are unlinked
+            assert!(node.value.is_some() || node.eq.is_some());
+            stack.push((node.lt.ptr.as_deref(), depth + 1, min, Some(node.c)));
+            stack.push((node.gt.ptr.as_deref(), depth + 1, Some(node.c), max));
+            stack.push((node.eq.ptr.as_deref(), 1, None, None));
+        }
+        (height, count)
+    }
+
+    #[test]
+    fn remove_cuts_nodes_without_keys() {
+        let count = |root: &Node<usize>| {
+            let mut count = 0;
+            let mut stack = vec![root.eq.ptr.as_deref()];
+            while let Some(node) = stack.pop() {
+                if let Some(node) = node {
+                    count += 1;
+                    stack.extend([&node.lt, &node.eq, &node.gt].map(|link| link.ptr.as_deref()));
+                }
+            }
+            count
+        };
+        let mut root = Node::default();
+        for (i, key) in ["b", "a", "c", "cab", "cabd"].into_iter().enumerate() {
+            insert(&mut root, key).value = Some(i);
+        }
+        assert_eq!(6, count(&root));
+
+        assert_eq!(None, remove(&mut root, "ca"));
+        assert_eq!(None, remove(&mut root, "x"));
+        assert_eq!(6, count(&root));
+        assert_eq!(Some(4), remove(&mut root, "cabd"));
+        assert_eq!(5, count(&root));
+        assert_eq!(Some(1), remove(&mut root, "a"));
+        assert_eq!(4, count(&root));
+        // "c" keeps its value, so only its eq chain goes
+        assert_eq!(Some(3), remove(&mut root, "cab"));
+        assert_eq!(2, count(&root));
+        assert_eq!(Some(2), remove(&mut root, "c"));
+        assert_eq!(Some(0), remove(&mut root, "b"));
+        assert!(root.eq.ptr.is_none());
+    }
+
+    #[test]
+    fn treap_levels_stay_balanced() {
+        let mut root = Node::default();
+        let chars: Vec<char> = (0x400..0x400 + 2000).filter_map(char::from_u32).collect();
+        for (i, c) in chars.iter().enumerate() {
+            treap_insert(&mut root, &c.to_string()).value = Some(i);
+            treap_insert(&mut root, &format!("{}{}", c, c)).value = Some(i);
+        }
+        let (height, count) = check_levels(&root, true);
+        // a plain insert in this order makes a chain of 2000
+        assert!(height <= 30, "height {}", height);
+        assert_eq!(4000, count);
+
+        for (i, c) in chars.iter().enumerate().filter(|(i, _)| i % 3 != 0) {
+            assert_eq!(Some(i), treap_remove(&mut root, &c.to_string()));
+            assert_eq!(None, treap_remove(&mut root, &c.to_string()));
+            if i % 2 == 0 {
+                assert_eq!(Some(i), treap_remove(&mut root, &format!("{}{}", c, c)));
+            }
+        }
+        let (height, count) = check_levels(&root, true);
+        assert!(height <= 30, "height {}", height);
+        // a node for each char with any key left and one more for "cc"
+        let expected: usize = (0..chars.len())
+            .map(|i| if i % 3 == 0 || i % 2 == 1 { 2 } else { 0 })
+            .sum();
+        assert_eq!(expected, count);
+    }
+
+    #[test]
+    fn treap_remove_keeps_other_keys() {
+        let mut root = Node::default();
+        for key in ["abc", "ab", "abcd", "abx", "b"] {
+            treap_insert(&mut root, key).value = Some(key.len());
+        }
+        assert_eq!(Some(2), treap_remove(&mut root, "ab"));
+        assert_eq!(Some(4), treap_remove(&mut root, "abcd"));
+        assert_eq!(None, treap_remove(&mut root, "a"));
+        assert_eq!(None, treap_remove(&mut root, "abcde"));
+        // a, b, c, x and b at the top
+        assert_eq!(5, check_levels(&root, true).1);
+        assert_eq!(Some(3), treap_remove(&mut root, "abc"));
+        assert_eq!(Some(3), treap_remove(&mut root, "abx"));
+        assert_eq!(Some(1), treap_remove(&mut root, "b"));
+        assert!(root.eq.ptr.is_none());
+    }
+
+    #[test]
+    fn splay_moves_hot_keys_up() {
+        let mut root = Node::default();
+        let chars: Vec<char> = (0x400..0x400 + 1000).filter_map(char::from_u32).collect();
+        for (i, c) in chars.iter().enumerate() {
+            splay_insert(&mut root, &c.to_string()).value = Some(i);
+        }
+        // sorted inserts leave the last char on top and the rest in a chain
+        assert_eq!((1000, 1000), check_levels(&root, false));
+
+        let hot = chars[10].to_string();
+        assert_eq!(Some(10), splay_search(&mut root, &hot).unwrap().value);
+        assert_eq!(chars[10], root.eq.ptr.as_ref().unwrap().c);
+        // splaying roughly halves the depth of the path
+        assert!(check_levels(&root, false).0 < 600);
+        assert!(splay_search(&mut root, "x").is_none());
+        assert_eq!(1000, check_levels(&root, false).1);
+    }
+
+    #[test]
+    fn splay_remove_keeps_other_keys() {
+        let mut root = Node::default();
+        for key in ["abc", "ab", "abcd", "abx", "b", "aa", "ac"] {
+            splay_insert(&mut root, key).value = Some(key.len());
+        }
+        assert_eq!(Some(2), splay_remove(&mut root, "ab"));
+        assert_eq!(Some(4), splay_remove(&mut root, "abcd"));
+        assert_eq!(None, splay_remove(&mut root, "a"));
+        assert_eq!(None, splay_remove(&mut root, "abcde"));
+        // a, a, b, c, c, x and b at the top
+        assert_eq!(7, check_levels(&root, false).1);
+        assert_eq!(Some(3), splay_remove(&mut root, "abc"));
+        assert_eq!(Some(3), splay_remove(&mut root, "abx"));
+        assert_eq!(Some(2), splay_remove(&mut root, "ac"));
+        assert_eq!(3, check_levels(&root, false).1);
+        assert_eq!(Some(2), splay_remove(&mut root, "aa"));
+        assert_eq!(Some(1), splay_remove(&mut root, "b"));
+        assert!(root.eq.ptr.is_none());
+    }
+}
@@ -0,0 +1,247 @@
+extern crate tst;
+
+#[macro_use]
+mod common;
+
+use self::tst::{ArenaTSTMap, TSTMap};
+use self::tst::tst_map::Entry::*;
+use std::iter::FromIterator;
+
+fn prepare_data() -> ArenaTSTMap<i32> {
+    ArenaTSTMap::from_iter(vec![
+        ("BY", 1),
+        ("BYGONE", 3),
+        ("BYE", 2),
+        ("BYLAW", 4),
+        ("BYLINE", 5),
+        ("BYPASS", 6),
+        ("BYPATH", 7),
+        ("BYPRODUCT", 8),
+        ("BYROAD", 9),
+        ("BYSTANDER", 10),
+        ("BYTE", 11),
+        ("BYWAY", 12),
+        ("BYWORD", 13),
+    ])
+}
+
+#[test]
+fn create_root() {
+    let m = ArenaTSTMap::<i32>::new();
+    assert_eq!(0, m.len());
+    assert!(m.is_empty());
+}
+
+#[test]
+fn insert_get() {
+    let mut m = ArenaTSTMap::new();
+
+    assert_eq!(None, m.insert("abc", 13));
+    assert_eq!(Some(13), m.insert("abc", 14));
+    assert_eq!(None, m.insert("abd", 15));
+    assert_eq!(2, m.len());
+    assert_eq!(Some(&14), m.get("abc"));
+    assert_eq!(Some(&15), m.get("abd"));
+    assert_eq!(None, m.get("ab"));
+    assert_eq!(None, m.get("abcd"));
+    assert_eq!(None, m.get(""));
+}
+
+#[test]
+fn entry() {
+    let mut m = ArenaTSTMap::new();
+
+    match m.entry("abcdg") {
+        Vacant(entry) => {
+            assert_eq!(100, *entry.insert(100));
+        },
+        Occupied(_) => unreachable!(),
+    }
+    match m.entry("abcdg") {
+        Vacant(_) => unreachable!(),
+        Occupied(entry) => {
+            assert_eq!(100, entry.remove());
+        }
+    }
+    assert_eq!(None, m.get("abcdg"));
+    assert_eq!(0, m.len());
+}
+
+#[test]
+fn remove_rich() {
+    let mut m = prepare_data();
+
+    assert_eq!(Some(1), m.remove("BY"));
+    assert_eq!(Some(12), m.remove("BYWAY"));
+    assert_eq!(Some(10), m.remove("BYSTANDER"));
+    assert_eq!(Some(8), m.remove("BYPRODUCT"));
+    assert_eq!(None, m.remove("BYPRODUCT"));
+    assert_eq!(Some(2), m.remove("BYE"));
+    assert_eq!(8, m.len());
+    assert_eq!(Some(&13), m.get("BYWORD"));
+    assert_eq!(Some(&7), m.get("BYPATH"));
+}
+
+#[test]
+fn removed_slots_are_reused() {
+    let mut m = ArenaTSTMap::new();
+    m.insert("abcdefgh", 1);
+    m.insert("abcdxyz", 2);
+    let capacity = m.capacity();
+
+    for i in 0..1000 {
+        assert_eq!(Some(2), m.remove("abcdxyz"));
+        assert_eq!(None, m.insert("abcdxyz", i));
+        m.insert("abcdxyz", 2);
+    }
+    assert_eq!(capacity, m.capacity());
+    assert_eq!(2, m.len());
+}
+
+#[test]
+fn empty_key() {
+    let mut m = prepare_data();
+
+    assert_eq!(None, m.insert("", 0));
+    assert_eq!(Some(&0), m.get(""));
+    assert_eq!(Some(("".to_string(), &0)), m.iter().next());
+    assert_eq!(Some(0), m.remove(""));
+    assert_eq!(13, m.len());
+}
+
+#[test]
+fn longest_prefix() {
+    let m = prepare_data();
+
+    assert_eq!("BYLAW", m.longest_prefix("BYLAWS"));
+    assert_eq!("BY", m.longest_prefix("BYL"));
+    assert_eq!("", m.longest_prefix("B"));
+}
+
+#[test]
+fn iterators() {
+    let mut m = prepare_data();
+
+    let keys: Vec<String> = m.keys().collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(sorted, keys);
+    assert_eq!((13, Some(13)), m.iter().size_hint());
+
+    let pref: Vec<String> = m.prefix_iter("BYP").map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYPASS", "BYPATH", "BYPRODUCT"], pref);
+
+    let wild: Vec<String> = m.wildcard_iter("BY.A..").map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYPASS", "BYPATH"], wild);
+
+    for (_, v) in m.prefix_iter_mut("BYW") {
+        *v *= 100;
+    }
+    for (_, v) in m.iter_mut() {
+        *v += 1;
+    }
+    assert_eq!(1201, m["BYWAY"]);
+    assert_eq!(1301, m["BYWORD"]);
+    assert_eq!(2, m["BY"]);
+
+    let sum: i32 = m.values().sum();
+    let all: Vec<(String, i32)> = m.into_iter().collect();
+    assert_eq!(13, all.len());
+    assert_eq!(sum, all.iter().map(|&(_, v)| v).sum());
+}
+
+#[test]
+fn same_as_tstmap() {
+    let words = common::keys(42, 2000);
+    let mut arena = ArenaTSTMap::new();
+    let mut map = TSTMap::new();
+    for (i, w) in words.iter().enumerate() {
+        assert_eq!(map.insert(w, i), arena.insert(w, i));
+    }
+    for w in words.iter().step_by(3) {
+        assert_eq!(map.remove(w), arena.remove(w));
+    }
+    assert_same_as_tstmap!(map, arena, &words);
+}
+
+#[test]
+fn clear() {
+    let mut m = prepare_data();
+    m.clear();
+    assert!(m.is_empty());
+    assert_eq!(None, m.get("BY"));
+    assert_eq!(0, m.iter().count());
+    m.insert("BY", 1);
+    assert_eq!(1, m["BY"]);
+}
+
+#[test]
+fn format() {
+    let m = ArenaTSTMap::from_iter(vec![("abc", 2), ("abd", 1)]);
+
+    assert_eq!("{\"abc\": 2, \"abd\": 1}", format!("{:?}", m));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let mut m = ArenaTSTMap::new();
+    let mut key = String::new();
+
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    m.insert(&key, 666);
+    let cpy = m.clone();
+
+    assert_eq!(Some(&666), cpy.get(&key));
+    assert_eq!(Some(666), m.remove(&key));
+    assert_eq!(None, m.get(&key));
+}
+
+#[test]
+fn iter_mut_after_removes() {
+    let words = common::keys(3, 500);
+    let mut arena = ArenaTSTMap::new();
+    let mut map = TSTMap::new();
+    for (i, w) in words.iter().enumerate() {
+        arena.insert(w, i);
+        map.insert(w, i);
+    }
+    for w in words.iter().step_by(2) {
+        arena.remove(w);
+        map.remove(w);
+    }
+    // new keys take the slots of the removed ones
+    arena.insert("ab€", 0);
+    map.insert("ab€", 0);
+
+    arena.iter_mut().for_each(|(_, v)| *v += 1);
+    map.iter_mut().for_each(|(_, v)| *v += 1);
+    arena.prefix_iter_mut("a").for_each(|(_, v)| *v *= 2);
+    map.prefix_iter_mut("a").for_each(|(_, v)| *v *= 2);
+    assert!(map.iter().eq(arena.iter()));
+}
+
+#[test]
+fn prefix_iter_mut_on_large_map() {
+    let words = common::keys(7, 20000);
+    let mut m = ArenaTSTMap::new();
+    for (i, w) in words.iter().enumerate() {
+        m.insert(w, i);
+    }
+    let before = m.clone();
+    let expected: Vec<String> = m.prefix_iter("ab").map(|(k, _)| k).collect();
+    assert!(!expected.is_empty() && expected.len() * 20 < m.len());
+
+    let mut touched = vec![];
+    for (k, v) in m.prefix_iter_mut("ab") {
+        *v += 1;
+        touched.push(k);
+    }
+    assert_eq!(expected, touched);
+    for (k, v) in m.iter() {
+        let old = before[&k];
+        assert_eq!(if k.starts_with("ab") { old + 1 } else { old }, *v, "{:?}", k);
+    }
+}
@@ -0,0 +1,114 @@
+extern crate tst;
+
+#[macro_use]
+mod common;
+
+use self::tst::{BalancedTSTMap, TSTMap};
+use self::tst::tst_map::Entry::*;
+
+fn keys() -> Vec<String> {
+    common::keys(17, 2000)
+}
+
+#[test]
+fn same_as_tstmap() {
+    let keys = keys();
+    let mut m = BalancedTSTMap::new();
+    let mut expected = TSTMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        if i % 3 == 2 {
+            assert_eq!(expected.remove(key), m.remove(key));
+        } else {
+            assert_eq!(expected.insert(key, i), m.insert(key, i));
+        }
+    }
+    assert_same_as_tstmap!(expected, m, &keys);
+
+    for key in expected.keys() {
+        assert!(m.remove(&key).is_some());
+    }
+    assert!(m.is_empty());
+    assert_eq!(0, m.iter().count());
+}
+
+#[test]
+fn shape_doesnt_depend_on_order() {
+    let keys = keys();
+    let mut forward = BalancedTSTMap::new();
+    let mut backward = BalancedTSTMap::new();
+    for key in keys.iter() {
+        forward.insert(key, key.len());
+    }
+    for key in keys.iter().rev() {
+        backward.insert(key, key.len());
+    }
+    assert!(forward == backward);
+
+    forward.remove(&keys[0]);
+    assert!(forward != backward);
+    backward.remove(&keys[0]);
+    assert!(forward == backward);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn sorted_inserts() {
+    let keys: Vec<String> = (0..10000).map(|i| format!("{:05}", i)).collect();
+    let m: BalancedTSTMap<usize> = keys.iter().map(|k| k.as_str()).zip(0..).collect();
+
+    assert_eq!(10000, m.len());
+    assert_eq!(Some(&1234), m.get("01234"));
+    assert_eq!("01234", m.longest_prefix("012345"));
+    assert_eq!(10, m.prefix_iter("0123").count());
+    let plain: TSTMap<usize> = m.clone().into_map();
+    assert!(plain.iter().eq(m.iter()));
+}
+
+#[test]
+fn entry_and_index() {
+    let mut m = BalancedTSTMap::new();
+    for word in ["b", "a", "b", "c", "b"] {
+        *m.entry(word).or_insert(0) += 1;
+    }
+    assert_eq!(3, m["b"]);
+    m["a"] = 10;
+    assert_eq!(Some(&10), m.get("a"));
+
+    match m.entry("c") {
+        Occupied(entry) => assert_eq!(1, entry.remove()),
+        Vacant(_) => panic!("c is in the map"),
+    }
+    assert!(!m.contains_key("c"));
+    assert_eq!(2, m.len());
+    assert_eq!("{\"a\": 10, \"b\": 3}", format!("{:?}", m));
+}
+
+#[test]
+fn empty_key() {
+    let mut m = BalancedTSTMap::new();
+    m.insert("", 1);
+    m.insert("a", 2);
+
+    assert_eq!(Some(&1), m.get(""));
+    assert_eq!(Some(1), m.remove(""));
+    assert_eq!(None, m.get(""));
+    assert_eq!(1, m.len());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let mut key = String::new();
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    let mut m = BalancedTSTMap::new();
+    m.insert(&key, 1);
+    m.insert(&key[..10], 2);
+
+    assert_eq!(Some(&1), m.get(&key));
+    assert_eq!(Some(1), m.remove(&key));
+    assert_eq!(&key[..10], m.longest_prefix(&key));
+    assert_eq!(Some(2), m.remove(&key[..10]));
+    assert!(m.is_empty());
+}
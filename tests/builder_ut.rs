@@ -0,0 +1,131 @@
+extern crate tst;
+
+#[macro_use]
+mod common;
+
+use self::tst::{TSTMap, TSTBuilder, BuildError, MappedTSTMap, ValueCodec};
+use std::io::{self, Write};
+
+fn words() -> Vec<String> {
+    let mut words = common::keys(5, 3000);
+    words.sort();
+    words.dedup();
+    words
+}
+
+#[test]
+fn same_as_tstmap() {
+    let words = words();
+    let mut builder = TSTBuilder::new(Vec::new()).unwrap();
+    let mut map = TSTMap::new();
+    for (i, w) in words.iter().enumerate() {
+        builder.insert(w, i as u32).unwrap();
+        map.insert(w, i as u32);
+    }
+    assert_eq!(words.len() as u64, builder.len());
+    let bytes = builder.finish().unwrap();
+    let m = MappedTSTMap::new(&bytes[..]).unwrap();
+
+    assert_same_as_tstmap!(map, m, &words, |v: &[u8]| u32::decode(v).unwrap());
+}
+
+#[test]
+fn prefixes_of_each_other() {
+    let mut builder = TSTBuilder::new(Vec::new()).unwrap();
+    for key in ["", "a", "ab", "abc", "abd", "b", "ba"] {
+        builder.insert(key, key.len() as u8).unwrap();
+    }
+    let m = MappedTSTMap::new(builder.finish().unwrap()).unwrap();
+
+    assert_eq!(7, m.len());
+    assert_eq!(vec!["", "a", "ab", "abc", "abd", "b", "ba"], m.keys().collect::<Vec<_>>());
+    assert_eq!(Some(&[0u8][..]), m.get(""));
+    assert_eq!(Some(&[3u8][..]), m.get("abd"));
+    assert_eq!("ab", m.longest_prefix("abx"));
+}
+
+#[test]
+fn empty() {
+    let builder: TSTBuilder<u8, _> = TSTBuilder::new(Vec::new()).unwrap();
+    assert!(builder.is_empty());
+    let m = MappedTSTMap::new(builder.finish().unwrap()).unwrap();
+
+    assert!(m.is_empty());
+    assert_eq!(0, m.iter().count());
+}
+
+#[test]
+fn out_of_order() {
+    let mut builder = TSTBuilder::new(Vec::new()).unwrap();
+    builder.insert("abc", 1u8).unwrap();
+
+    match builder.insert("abb", 2) {
+        Err(BuildError::OutOfOrder { previous, key }) => {
+            assert_eq!("abc", previous);
+            assert_eq!("abb", key);
+        }
+        _ => panic!("out of order key is accepted"),
+    }
+    assert!(builder.insert("abc", 2).is_err());
+    assert!(builder.insert("", 2).is_err());
+    assert_eq!("key \"ab\" doesn't go after \"abc\"", builder.insert("ab", 2).unwrap_err().to_string());
+
+    // rejected keys don't break the output
+    builder.insert("abd", 3).unwrap();
+    let m = MappedTSTMap::new(builder.finish().unwrap()).unwrap();
+    assert_eq!(vec!["abc", "abd"], m.keys().collect::<Vec<_>>());
+}
+
+#[test]
+fn balanced_levels() {
+    let mut builder = TSTBuilder::new(Vec::new()).unwrap();
+    let mut map = TSTMap::new();
+    for c in 'a'..='z' {
+        builder.insert(&c.to_string(), ()).unwrap();
+        map.insert(&c.to_string(), ());
+    }
+    let built = builder.finish().unwrap();
+    let mut sorted = Vec::new();
+    map.write_mapped(&mut sorted).unwrap();
+
+    // the same records, but the builder makes a balanced tree instead of a gt chain
+    assert_eq!(sorted.len(), built.len());
+    assert_ne!(sorted, built);
+    let m = MappedTSTMap::new(built).unwrap();
+    assert_eq!(26, m.wildcard_iter(".").count());
+}
+
+struct FailingWriter;
+
+impl Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk is full"))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_error() {
+    let mut builder = TSTBuilder::new(FailingWriter).unwrap();
+    builder.insert("a", 1u8).unwrap();
+
+    assert!(matches!(builder.finish(), Err(BuildError::Io(_))));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let mut key = String::new();
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    let mut builder = TSTBuilder::new(Vec::new()).unwrap();
+    builder.insert(&key[..10], 1).unwrap();
+    builder.insert(&key, 666).unwrap();
+    let m = MappedTSTMap::new(builder.finish().unwrap()).unwrap();
+
+    assert_eq!(Some(666), m.get(&key).and_then(i32::decode));
+    assert_eq!(Some(1), m.get(&key[..10]).and_then(i32::decode));
+}
@@ -0,0 +1,60 @@
+// Helpers shared by the integration tests, a test file takes them with `#[macro_use] mod common;`.
+// Not every file needs every helper.
+#![allow(dead_code, unused_macros)]
+
+/// Chars of the generated keys: ascii, multi-byte and control ones.
+pub const ALPHABET: [char; 8] = ['a', 'b', 'c', 'A', 'ж', '€', '\0', '\u{7f}'];
+
+/// Prefixes `assert_same_as_tstmap!` iterates with.
+pub const PREFIXES: [&str; 13] = ["", "a", "b", "ab", "abc", "ж", "€a", "aж", "A\0", "\u{7f}", "x", "ax", "bcab"];
+
+/// Wild-card patterns `assert_same_as_tstmap!` iterates with.
+pub const PATTERNS: [&str; 13] = ["", ".", "..", "...", "a.", ".b", "ж.", ".ж", "a..", "..a", ".€.", "x", "....."];
+
+/// Makes `count` pseudo-random keys of up to 7 chars of `ALPHABET`, repeats included.
+/// The same `seed` gives the same keys.
+pub fn keys(seed: u64, count: usize) -> Vec<String> {
+    // Miri checks a smaller sample, it's a lot slower
+    let count = if cfg!(miri) { count / 10 } else { count };
+    let mut seed = seed;
+    let mut keys = Vec::with_capacity(count);
+    for _ in 0..count {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let len = (seed >> 61) as usize;
+        keys.push((0..len).map(|i| ALPHABET[((seed >> (i * 3 + 8)) % 8) as usize]).collect());
+    }
+    keys
+}
+
+/// The default value conversion of `assert_same_as_tstmap!`.
+pub fn cloned<V: Clone>(v: &V) -> V { v.clone() }
+
+/// Asserts that `$map` holds the same as the `TSTMap` `$expected`: the length, all the
+/// iterators, and `get` and `longest_prefix` of every key of `$keys`. Values of `$map`
+/// go through `$value` to compare them, by default they are cloned.
+macro_rules! assert_same_as_tstmap {
+    ($expected:expr, $map:expr, $keys:expr) => {
+        assert_same_as_tstmap!($expected, $map, $keys, common::cloned)
+    };
+    ($expected:expr, $map:expr, $keys:expr, $value:expr) => {{
+        let expected = &$expected;
+        let value = $value;
+        assert_eq!(expected.len(), $map.len());
+        assert!(expected.iter().map(|(k, v)| (k, v.clone())).eq($map.iter().map(|(k, v)| (k, value(v)))));
+        for pref in common::PREFIXES {
+            assert!(expected.prefix_iter(pref).map(|(k, v)| (k, v.clone()))
+                .eq($map.prefix_iter(pref).map(|(k, v)| (k, value(v)))), "{:?}", pref);
+        }
+        for pat in common::PATTERNS {
+            assert!(expected.wildcard_iter(pat).map(|(k, v)| (k, v.clone()))
+                .eq($map.wildcard_iter(pat).map(|(k, v)| (k, value(v)))), "{:?}", pat);
+        }
+        for key in $keys {
+            let key: &str = key.as_ref();
+            assert_eq!(expected.get(key).cloned(), $map.get(key).map(value));
+            assert_eq!(expected.longest_prefix(key), $map.longest_prefix(key));
+            let longer = format!("{}bж", key);
+            assert_eq!(expected.longest_prefix(&longer), $map.longest_prefix(&longer));
+        }
+    }};
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "concurrent")]
+
+extern crate tst;
+
+use std::sync::Arc;
+use std::thread;
+use self::tst::{ConcurrentTSTMap, PersistentTSTMap};
+
+#[test]
+fn writers_dont_lose_updates() {
+    // Miri runs fewer rounds, it's a lot slower
+    let n = if cfg!(miri) { 25 } else { 250 };
+    let m = Arc::new(ConcurrentTSTMap::new());
+    let writers: Vec<_> = (0..4).map(|t| {
+        let m = m.clone();
+        thread::spawn(move || {
+            for i in 0..n {
+                m.insert(&format!("{}-{}", t, i), i);
+            }
+            for i in (0..n).filter(|i| i % 5 == 0) {
+                assert_eq!(Some(i), m.remove(&format!("{}-{}", t, i)).map(|v| *v));
+            }
+        })
+    }).collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    assert_eq!(4 * n * 4 / 5, m.len());
+    assert_eq!(n * 4 / 5, m.prefix_iter("3-").count());
+    assert_eq!(Some(7), m.get("2-7").map(|v| *v));
+    assert_eq!(None, m.get("2-5"));
+}
+
+#[test]
+fn readers_see_whole_updates() {
+    let n = if cfg!(miri) { 20 } else { 200 };
+    let m = Arc::new(ConcurrentTSTMap::new());
+    let writer = {
+        let m = m.clone();
+        thread::spawn(move || {
+            for i in 0..n {
+                // every version has "a" and "b" with the same value
+                m.update(|v| v.insert("a", i).insert("b", i));
+            }
+        })
+    };
+    let readers: Vec<_> = (0..3).map(|_| {
+        let m = m.clone();
+        thread::spawn(move || {
+            for _ in 0..n {
+                let snapshot = m.snapshot();
+                assert_eq!(snapshot.get("a"), snapshot.get("b"));
+                let values: Vec<i32> = m.iter().map(|(_, v)| *v).collect();
+                assert!(values.is_empty() || values[0] == values[1]);
+            }
+        })
+    }).collect();
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(Some(n - 1), m.get("a").map(|v| *v));
+}
+
+#[test]
+fn snapshots_and_iterators_are_stable() {
+    let m: ConcurrentTSTMap<i32> = vec![("a", 1), ("ab", 2), ("b", 3)].into_iter().collect();
+    let snapshot = m.snapshot();
+    let iter = m.iter();
+    let value = m.get("ab").unwrap();
+
+    m.remove("ab");
+    m.insert("a", 10);
+
+    assert_eq!(2, *value);
+    assert_eq!(Some(&2), snapshot.get("ab"));
+    let entries: Vec<(String, i32)> = iter.map(|(k, v)| (k, *v)).collect();
+    assert_eq!(vec![("a".to_string(), 1), ("ab".to_string(), 2), ("b".to_string(), 3)], entries);
+    assert_eq!("{\"a\": 10, \"b\": 3}", format!("{:?}", m));
+}
+
+#[test]
+fn from_persistent() {
+    let m = ConcurrentTSTMap::from(PersistentTSTMap::new().insert("", 0).insert("abc", 1));
+    assert_eq!(2, m.len());
+    assert_eq!("abc", m.longest_prefix("abcd"));
+    assert!(m.contains_key(""));
+    assert_eq!(None, m.remove("x"));
+    assert_eq!(2, m.len());
+}
+
+#[test]
+fn reads_dont_wait_for_an_update() {
+    let m = Arc::new(ConcurrentTSTMap::new());
+    m.insert("a", 1);
+    m.update(|v| {
+        // the writer is in the middle of an update, a reader still gets the current version
+        let reader = {
+            let m = m.clone();
+            thread::spawn(move || (m.get("a").map(|v| *v), m.len()))
+        };
+        assert_eq!((Some(1), 1), reader.join().unwrap());
+        v.insert("a", 2).insert("b", 3)
+    });
+    assert_eq!(Some(2), m.get("a").map(|v| *v));
+    assert_eq!(2, m.len());
+}
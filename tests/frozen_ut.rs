@@ -0,0 +1,135 @@
+extern crate tst;
+
+#[macro_use]
+mod common;
+
+use self::tst::{TSTMap, FrozenTSTMap};
+use std::iter::FromIterator;
+
+fn prepare_data() -> FrozenTSTMap<i32> {
+    TSTMap::from_iter(vec![
+        ("BY", 1),
+        ("BYGONE", 3),
+        ("BYE", 2),
+        ("BYLAW", 4),
+        ("BYLINE", 5),
+        ("BYPASS", 6),
+        ("BYPATH", 7),
+        ("BYPRODUCT", 8),
+        ("BYROAD", 9),
+        ("BYSTANDER", 10),
+        ("BYTE", 11),
+        ("BYWAY", 12),
+        ("BYWORD", 13),
+    ]).freeze()
+}
+
+#[test]
+fn freeze_empty() {
+    let m = TSTMap::<i32>::new().freeze();
+    assert_eq!(0, m.len());
+    assert!(m.is_empty());
+    assert_eq!(None, m.get(""));
+    assert_eq!(None, m.get("a"));
+    assert_eq!(0, m.iter().count());
+    assert_eq!(0, m.prefix_iter("a").count());
+    assert_eq!(0, m.wildcard_iter(".").count());
+    assert_eq!("", m.longest_prefix("abc"));
+}
+
+#[test]
+fn get() {
+    let m = prepare_data();
+
+    assert_eq!(13, m.len());
+    assert_eq!(Some(&1), m.get("BY"));
+    assert_eq!(Some(&8), m.get("BYPRODUCT"));
+    assert_eq!(Some(&13), m.get("BYWORD"));
+    assert_eq!(None, m.get("B"));
+    assert_eq!(None, m.get("BYPA"));
+    assert_eq!(None, m.get("BYWORDS"));
+    assert_eq!(None, m.get(""));
+    assert!(m.contains_key("BYTE"));
+    assert!(!m.contains_key("BYT"));
+    assert_eq!(11, m["BYTE"]);
+}
+
+#[test]
+fn empty_key() {
+    let mut m = TSTMap::new();
+    m.insert("", 0);
+    m.insert("a", 1);
+    let m = m.freeze();
+
+    assert_eq!(Some(&0), m.get(""));
+    assert_eq!(vec![("".to_string(), &0), ("a".to_string(), &1)], m.iter().collect::<Vec<_>>());
+    assert_eq!(2, m.prefix_iter("").count());
+    assert_eq!(vec![("".to_string(), &0)], m.wildcard_iter("").collect::<Vec<_>>());
+    assert_eq!("", m.longest_prefix("b"));
+}
+
+#[test]
+fn iterators() {
+    let m = prepare_data();
+
+    let keys: Vec<String> = m.keys().collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(sorted, keys);
+    assert_eq!((13, Some(13)), m.iter().size_hint());
+    assert_eq!(91, m.values().sum::<i32>());
+
+    let pref: Vec<String> = m.prefix_iter("BYP").map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYPASS", "BYPATH", "BYPRODUCT"], pref);
+    assert_eq!(0, m.prefix_iter("BYX").count());
+
+    let wild: Vec<String> = m.wildcard_iter("BY.A..").map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYPASS", "BYPATH"], wild);
+
+    let mut count = 0;
+    for (_, v) in &m {
+        count += *v;
+    }
+    assert_eq!(91, count);
+}
+
+#[test]
+fn longest_prefix() {
+    let m = prepare_data();
+
+    assert_eq!("BYLAW", m.longest_prefix("BYLAWS"));
+    assert_eq!("BY", m.longest_prefix("BYL"));
+    assert_eq!("", m.longest_prefix("B"));
+}
+
+#[test]
+fn same_as_tstmap() {
+    let words = common::keys(7, 2000);
+    let map: TSTMap<usize> = words.iter().map(|w| w.as_str()).zip(0..).collect();
+
+    let frozen = map.clone().freeze();
+    assert_same_as_tstmap!(map, frozen, &words);
+}
+
+#[test]
+fn format() {
+    let m = TSTMap::from_iter(vec![("abc", 2), ("abd", 1)]).freeze();
+
+    assert_eq!("{\"abc\": 2, \"abd\": 1}", format!("{:?}", m));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let mut m = TSTMap::new();
+    let mut key = String::new();
+
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    m.insert(&key, 666);
+    let m = m.freeze();
+
+    assert_eq!(Some(&666), m.get(&key));
+    assert_eq!(&key[..], m.longest_prefix(&key));
+}
@@ -0,0 +1,170 @@
+extern crate tst;
+
+#[macro_use]
+mod common;
+
+use self::tst::{HybridTSTMap, TSTMap};
+use std::ops::ControlFlow;
+
+fn keys() -> Vec<String> {
+    common::keys(41, 3000)
+}
+
+fn build(depth: usize) -> (HybridTSTMap<usize>, TSTMap<usize>) {
+    let mut m = HybridTSTMap::with_depth(depth);
+    let mut expected = TSTMap::new();
+    for (i, key) in keys().iter().enumerate() {
+        if i % 5 == 4 {
+            assert_eq!(expected.remove(key), m.remove(key));
+        } else {
+            assert_eq!(expected.insert(key, i), m.insert(key, i));
+        }
+    }
+    (m, expected)
+}
+
+#[test]
+fn same_as_tstmap() {
+    for depth in 1..=2 {
+        let (m, expected) = build(depth);
+        assert_same_as_tstmap!(expected, m, keys());
+        assert!(expected.keys().eq(m.keys()));
+        assert!(expected.values().eq(m.values()));
+    }
+}
+
+#[test]
+fn entry_index_and_debug() {
+    let mut m = HybridTSTMap::with_depth(2);
+    for word in ["b", "a", "b", "ab", "b"] {
+        *m.entry(word).or_insert(0) += 1;
+    }
+    assert_eq!(3, m["b"]);
+    m["ab"] = 10;
+    assert_eq!(Some(&10), m.get("ab"));
+    assert_eq!("{\"a\": 1, \"ab\": 10, \"b\": 3}", format!("{:?}", m));
+
+    m.clear();
+    assert!(m.is_empty());
+    assert_eq!(2, m.depth());
+    assert_eq!(0, m.iter().count());
+}
+
+#[test]
+fn empty_key() {
+    let mut m = HybridTSTMap::new();
+    m.insert("", 1);
+    m.insert("a", 2);
+
+    assert_eq!(Some(&1), m.get(""));
+    assert_eq!(vec!["", "a"], m.keys().collect::<Vec<_>>());
+    assert_eq!(Some(1), m.remove(""));
+    assert_eq!(None, m.get(""));
+    assert_eq!(1, m.len());
+}
+
+#[test]
+fn table_entries_emptied_and_refilled() {
+    let (mut m, mut expected) = build(2);
+    // a vacant entry left without a value doesn't make a key
+    let _ = m.entry("ab€");
+    assert_eq!(None, m.get("ab€"));
+    for key in keys() {
+        if key.starts_with('a') {
+            assert_eq!(expected.remove(&key), m.remove(&key));
+        }
+    }
+    assert_same_as_tstmap!(expected, m, keys());
+    assert_eq!(None, m.remove("ab"));
+    assert_eq!(None, m.get_mut("ab"));
+
+    m.insert("ab", 1);
+    expected.insert("ab", 1);
+    let cloned = m.clone();
+    assert_same_as_tstmap!(expected, cloned, keys());
+}
+
+#[test]
+#[should_panic]
+fn wrong_depth() {
+    let _: HybridTSTMap<u8> = HybridTSTMap::with_depth(3);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let mut key = String::new();
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    let mut m = HybridTSTMap::with_depth(2);
+    m.insert(&key, 1);
+    m.insert(&key[..10], 2);
+
+    assert_eq!(Some(&1), m.get(&key));
+    assert_eq!(&key, m.longest_prefix(&key));
+    assert_eq!(Some(2), m.remove(&key[..10]));
+    assert_eq!(1, m.len());
+
+    let copy = m.clone();
+    assert_eq!(Some(&1), copy.get(&key));
+    assert_eq!(key.len() + 7, format!("{:?}", copy).len());
+}
+
+#[test]
+fn iter_mut() {
+    for depth in 1..=2 {
+        let (mut m, mut expected) = build(depth);
+        for ((_, v), (_, expected_v)) in m.iter_mut().zip(expected.iter_mut()) {
+            *v += 1;
+            *expected_v += 1;
+        }
+        for pref in common::PREFIXES {
+            m.prefix_iter_mut(pref).for_each(|(_, v)| *v *= 2);
+            expected.prefix_iter_mut(pref).for_each(|(_, v)| *v *= 2);
+        }
+        for pat in common::PATTERNS {
+            m.wildcard_iter_mut(pat).for_each(|(_, v)| *v += 3);
+            expected.wildcard_iter_mut(pat).for_each(|(_, v)| *v += 3);
+        }
+        assert!(expected.iter().eq(m.iter()));
+    }
+}
+
+#[test]
+fn into_iter() {
+    for depth in 1..=2 {
+        let (m, expected) = build(depth);
+        let entries = m.into_iter();
+        assert_eq!(expected.len(), entries.len());
+        assert!(expected.into_iter().eq(entries));
+    }
+}
+
+#[test]
+fn for_each() {
+    for depth in 1..=2 {
+        let (mut m, mut expected) = build(depth);
+        m.insert("aжb", 0);
+        expected.insert("aжb", 0);
+        let mut entries = vec![];
+        m.for_each(|key, value| entries.push((key.to_string(), value)));
+        assert!(expected.iter().eq(entries));
+
+        let first = m.try_for_each(|key, _| if key.is_empty() { ControlFlow::Continue(()) } else { ControlFlow::Break(key.to_string()) });
+        assert_eq!(ControlFlow::Break(expected.keys().find(|k| !k.is_empty()).unwrap()), first);
+
+        // stops in the middle, at a key of the rest trie after the table entries under "a"
+        let mut visited = vec![];
+        let stop = m.try_for_each(|key, _| {
+            if key.starts_with("aж") {
+                return ControlFlow::Break(key.to_string());
+            }
+            visited.push(key.to_string());
+            ControlFlow::Continue(())
+        });
+        let stop_at = expected.keys().position(|k| k.starts_with("aж")).unwrap();
+        assert_eq!(ControlFlow::Break(expected.keys().nth(stop_at).unwrap()), stop);
+        assert!(expected.keys().take(stop_at).eq(visited));
+    }
+}
@@ -1,8 +1,13 @@
+#![allow(clippy::bool_assert_comparison)]
+
 #[macro_use] extern crate tst;
 
 use self::tst::TSTMap;
 use self::tst::tst_map::Entry::*;
 use std::iter::FromIterator;
+use std::ops::ControlFlow;
+
+mod common;
 
 fn prepare_data() -> TSTMap<i32> {
     tstmap! {
@@ -748,7 +753,7 @@ fn not_eq() {
 }
 
 #[test]
-fn not_eq_different_order() {
+fn eq_different_order() {
     let mut m1 = TSTMap::<i32>::new();
     let mut m2 = TSTMap::<i32>::new();
 
@@ -757,7 +762,7 @@ fn not_eq_different_order() {
     m1.insert("xxx", 2);
     m1.insert("abcdef", 100);
 
-    assert!(m1 != m2);
+    assert_eq!(m1, m2);
 }
 
 #[test]
@@ -795,6 +800,7 @@ fn macros_ctor() {
 }
 
 #[test]
+#[cfg_attr(miri, ignore)]
 fn insert_remove_get_big_key_not_overflow_stack() {
     let mut m = TSTMap::new();
     let mut key = String::new();
@@ -811,6 +817,7 @@ fn insert_remove_get_big_key_not_overflow_stack() {
 }
 
 #[test]
+#[cfg_attr(miri, ignore)]
 fn drop_stack_overflow() {
     let mut m = TSTMap::new();
     let mut key = String::new();
@@ -833,4 +840,557 @@ fn unicode() {
     assert_eq!(Some(&12), m.get("::ХУЙ"));
     assert_eq!(Some(12), m.remove("::ХУЙ"));
     assert_eq!(None, m.get("::ХУЙ"));
-}
\ No newline at end of file
+}
+#[test]
+fn empty_key() {
+    let mut m = TSTMap::new();
+
+    assert_eq!(None, m.insert("", 1));
+    assert_eq!(Some(1), m.insert("", 2));
+    assert_eq!(1, m.len());
+    assert_eq!(Some(&2), m.get(""));
+    assert!(m.contains_key(""));
+    assert_eq!(2, m[""]);
+
+    *m.get_mut("").unwrap() += 10;
+    assert_eq!(Some(12), m.remove(""));
+    assert_eq!(None, m.get(""));
+    assert!(m.is_empty());
+}
+
+#[test]
+fn empty_key_entry() {
+    let mut m = TSTMap::new();
+
+    *m.entry("").or_insert(0) += 1;
+    *m.entry("").or_insert(0) += 1;
+    m.insert("a", 5);
+
+    assert_eq!(2, m[""]);
+    assert_eq!(2, m.len());
+}
+
+#[test]
+fn empty_key_iterators() {
+    let mut m = tstmap! {
+        "b" => 2,
+        "" => 0,
+        "a" => 1,
+    };
+
+    let keys: Vec<String> = m.keys().collect();
+    assert_eq!(vec!["", "a", "b"], keys);
+
+    let pref: Vec<(String, &i32)> = m.prefix_iter("").collect();
+    assert_eq!(vec![("".to_string(), &0), ("a".to_string(), &1), ("b".to_string(), &2)], pref);
+
+    let values: Vec<&i32> = m.values().collect();
+    assert_eq!(vec![&0, &1, &2], values);
+
+    for (_, v) in m.iter_mut() {
+        *v += 10;
+    }
+    let wild: Vec<(String, &i32)> = m.wildcard_iter("").collect();
+    assert_eq!(vec![("".to_string(), &10)], wild);
+
+    let all: Vec<(String, i32)> = m.into_iter().collect();
+    assert_eq!(vec![("".to_string(), 10), ("a".to_string(), 11), ("b".to_string(), 12)], all);
+}
+
+#[test]
+fn empty_key_longest_prefix() {
+    let m = tstmap! {
+        "" => 0,
+        "ab" => 1,
+    };
+
+    assert_eq!("", m.longest_prefix("a"));
+    assert_eq!("ab", m.longest_prefix("abc"));
+}
+
+#[test]
+fn empty_pattern_without_empty_key() {
+    let m = tstmap! {
+        "a" => 1,
+    };
+
+    assert_eq!(0, m.wildcard_iter("").count());
+}
+
+#[test]
+fn longest_prefix_unicode() {
+    let m = tstmap! {
+        "ХУ" => 1,
+        "ХУЙ" => 2,
+    };
+
+    assert_eq!("ХУЙ", m.longest_prefix("ХУЙЯ"));
+    assert_eq!("ХУ", m.longest_prefix("ХУX"));
+}
+
+#[test]
+fn prefix_iterator_prefix_is_key() {
+    let m = tstmap! {
+        "fir" => 0,
+        "first" => 1,
+        "firstsecond" => 12,
+    };
+
+    let keys: Vec<String> = m.prefix_iter("fir").map(|(k, _)| k).collect();
+    assert_eq!(vec!["fir", "first", "firstsecond"], keys);
+}
+
+#[test]
+fn get_by_chars() {
+    let m = prepare_data();
+    let buf: Vec<char> = "BYLAW".chars().collect();
+
+    assert_eq!(Some(&4), m.get_chars(buf.iter().cloned()));
+    assert_eq!(Some(&1), m.get_chars(buf[..2].iter().cloned()));
+    assert_eq!(None, m.get_chars(buf[..3].iter().cloned()));
+    assert_eq!(None, m.get_chars("BYLAWS".chars()));
+    assert!(m.contains_chars(buf));
+    assert!(!m.contains_chars(std::iter::empty()));
+}
+
+#[test]
+fn prefix_iterator_by_chars() {
+    let m = prepare_data();
+
+    let by_str: Vec<(String, &i32)> = m.prefix_iter("BYP").collect();
+    let by_chars: Vec<(String, &i32)> = m.prefix_iter_chars(['B', 'Y', 'P']).collect();
+    assert_eq!(3, by_chars.len());
+    assert_eq!(by_str, by_chars);
+    assert_eq!(0, m.prefix_iter_chars("BYX".chars()).count());
+    assert_eq!(m.len(), m.prefix_iter_chars(std::iter::empty()).count());
+}
+
+#[test]
+fn from_sorted_iter() {
+    let mut keys: Vec<String> = (0..1000).map(|i| format!("{:x}", i * 7919)).collect();
+    keys.sort();
+    let m = TSTMap::from_sorted_iter(keys.iter().map(|k| (k.as_str(), k.len())));
+    let expected: TSTMap<usize> = keys.iter().map(|k| (k.as_str(), k.len())).collect();
+
+    assert_eq!(1000, m.len());
+    assert!(expected.iter().eq(m.iter()));
+    assert_eq!(expected.prefix_iter("1a").count(), m.prefix_iter("1a").count());
+}
+
+#[test]
+fn from_sorted_iter_repeated_and_empty_keys() {
+    let m = TSTMap::from_sorted_iter(vec![
+        (String::new(), 0),
+        ("a".to_string(), 1),
+        ("a".to_string(), 2),
+        ("ab".to_string(), 3),
+    ]);
+
+    assert_eq!(3, m.len());
+    assert_eq!(Some(&0), m.get(""));
+    assert_eq!(Some(&2), m.get("a"));
+
+    let m: TSTMap<i32> = TSTMap::from_sorted_iter(Vec::<(&str, i32)>::new());
+    assert!(m.is_empty());
+}
+
+#[test]
+fn from_sorted_iter_unsorted() {
+    let m = TSTMap::from_sorted_iter(vec![("a", 1), ("z", 0), ("a", 2), ("y", 0)]);
+
+    assert_eq!(3, m.len());
+    assert_eq!(Some(&2), m.get("a"));
+    assert_eq!(vec!["a", "y", "z"], m.keys().collect::<Vec<_>>());
+}
+
+#[test]
+fn rebalance_keeps_content() {
+    let mut m = prepare_data();
+    m.insert("", -1);
+    let before: Vec<(String, i32)> = m.iter().map(|(k, v)| (k, *v)).collect();
+
+    m.rebalance();
+    let after: Vec<(String, i32)> = m.iter().map(|(k, v)| (k, *v)).collect();
+    assert_eq!(before, after);
+    assert_eq!(before.len(), m.len());
+    assert_eq!(Some(&-1), m.get(""));
+
+    // still a valid trie for updates
+    m.insert("BYZ", 100);
+    assert_eq!(Some(100), m.remove("BYZ"));
+    for (k, v) in before.iter() {
+        assert_eq!(Some(*v), m.remove(k));
+    }
+    assert!(m.is_empty());
+
+    m.rebalance();
+    assert!(m.is_empty());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn rebalance_big_key_not_overflow_stack() {
+    let mut key = String::new();
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    let mut m = TSTMap::new();
+    m.insert(&key, 1);
+    m.insert(&key[..100], 2);
+
+    m.rebalance();
+    assert_eq!(Some(&1), m.get(&key));
+    assert_eq!(&key[..100], m.longest_prefix(&key[..500]));
+}
+
+#[test]
+fn stats() {
+    use std::mem::size_of;
+
+    let mut m = TSTMap::new();
+    m.insert("", String::with_capacity(3));
+    for key in ["a", "b", "c", "d", "ab"] {
+        m.insert(key, String::with_capacity(10));
+    }
+    let stats = m.stats();
+
+    assert_eq!(5, stats.nodes);
+    assert_eq!(6, stats.values);
+    // sorted inserts make a chain a -> b -> c -> d
+    assert_eq!(vec![2, 1, 1, 1], stats.chain_lengths);
+    assert_eq!(vec![1, 4, 1], stats.key_lengths);
+    assert_eq!(4, stats.max_depth);
+    assert_eq!((1 + 2 + 3 + 4 + 2) as f64 / 6.0, stats.avg_depth);
+    assert!(stats.heap_bytes >= 5 * size_of::<(char, Option<String>)>() + 3 + 5 * 10);
+
+    m.rebalance();
+    assert_eq!(vec![2, 2, 1], m.stats().chain_lengths);
+
+    let empty: TSTMap<u8> = TSTMap::new();
+    assert_eq!(tst::Stats::default(), empty.stats());
+}
+
+#[test]
+fn to_dot() {
+    let mut m = TSTMap::new();
+    m.insert("b", 1);
+    m.insert("a\"", 2);
+    m.insert("", 0);
+    let mut out = Vec::new();
+    m.to_dot(&mut out).unwrap();
+
+    let expected = "digraph tst {
+    node [shape=circle];
+    n0 [label=\"\", shape=doublecircle, style=filled, fillcolor=lightyellow];
+    n1 [label=\"b\", shape=doublecircle, style=filled, fillcolor=lightyellow];
+    n0 -> n1 [label=\"eq\", color=black, fontcolor=black];
+    n2 [label=\"a\"];
+    n1 -> n2 [label=\"lt\", color=blue, fontcolor=blue];
+    n3 [label=\"\\\"\", shape=doublecircle, style=filled, fillcolor=lightyellow];
+    n2 -> n3 [label=\"eq\", color=black, fontcolor=black];
+}
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    let mut out = Vec::new();
+    m.to_dot_filtered(&mut out, "x", Some(3)).unwrap();
+    assert_eq!("digraph tst {\n}\n", String::from_utf8(out).unwrap());
+
+    let mut out = Vec::new();
+    m.to_dot_filtered(&mut out, "", Some(0)).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("n0 "));
+    assert!(!out.contains("n1"));
+}
+
+#[test]
+fn cursor_same_as_prefix_iter() {
+    let m = prepare_data();
+    let mut cursor = m.cursor();
+    for (i, ch) in "BYLAWS".chars().enumerate() {
+        assert_eq!(i < 5, cursor.push(ch));
+        let key = cursor.key().to_string();
+        assert_eq!(m.get(&key), cursor.value());
+        assert!(cursor.completions().eq(m.prefix_iter(&key)));
+        let next: Vec<char> = m.prefix_iter(&key).filter_map(|(k, _)| k[key.len()..].chars().next()).collect();
+        let mut expected = next.clone();
+        expected.dedup();
+        assert_eq!(expected, cursor.next_chars().collect::<Vec<_>>());
+        assert_eq!(!expected.is_empty(), cursor.has_children());
+    }
+
+    // back from outside of the trie
+    assert_eq!(Some('S'), cursor.pop());
+    assert!(cursor.is_key());
+    for _ in 0..3 {
+        cursor.pop();
+    }
+    assert_eq!("BY", cursor.key());
+    assert_eq!(Some(&1), cursor.value());
+    assert_eq!("EGLPRSTW", cursor.next_chars().collect::<String>());
+    assert_eq!(13, cursor.completions().count());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn cursor_big_key_not_overflow_stack() {
+    let key: String = (0..1_000_000).map(|_| 'a').collect();
+    let mut m = TSTMap::new();
+    m.insert(&key, 1);
+    let mut cursor = m.cursor();
+    for ch in key.chars() {
+        assert!(cursor.push(ch));
+    }
+    assert_eq!(Some(&1), cursor.value());
+    while cursor.pop().is_some() {}
+    assert!(cursor.has_children());
+}
+
+#[test]
+fn cursor_mut_walks_in_order() {
+    let mut m = prepare_data();
+    m.insert("", 0);
+    m.insert("A", 14);
+    m.insert("BYTES", 15);
+    let keys: Vec<String> = m.keys().collect();
+
+    let mut cursor = m.cursor_mut();
+    for key in keys.iter() {
+        assert_eq!(Some(key.as_str()), cursor.key());
+        cursor.move_next();
+    }
+    assert_eq!(None, cursor.current());
+    for key in keys.iter().rev() {
+        cursor.move_prev();
+        assert_eq!(Some(key.as_str()), cursor.key());
+    }
+    cursor.move_prev();
+    assert_eq!(None, cursor.key());
+}
+
+#[test]
+fn cursor_mut_remove_current() {
+    let mut m = prepare_data();
+    let mut expected = m.clone();
+
+    {
+        let mut cursor = m.cursor_mut();
+        let mut i = 0;
+        while let Some((key, _)) = cursor.current() {
+            if i % 3 == 0 {
+                let key = key.to_string();
+                assert_eq!(expected.remove(&key).map(|v| (key, v)), cursor.remove_current());
+            } else {
+                cursor.move_next();
+            }
+            i += 1;
+        }
+        assert_eq!(None, cursor.remove_current());
+    }
+
+    assert_eq!(expected.len(), m.len());
+    assert!(expected.iter().eq(m.iter()));
+    while let Some(key) = m.keys().next() {
+        m.cursor_mut().remove_current();
+        assert!(!m.contains_key(&key));
+    }
+    assert!(m.is_empty());
+    // all nodes are pruned
+    assert_eq!(0, m.stats().nodes);
+}
+
+#[test]
+fn cursor_mut_insert() {
+    let mut m = TSTMap::new();
+    {
+        let mut cursor = m.cursor_mut();
+        assert_eq!(Ok(()), cursor.insert_after("b", 2));
+        assert_eq!(Err(tst::tst_map::UnorderedKeyError), cursor.insert_before("b", 0));
+        cursor.move_next();
+        assert_eq!(Ok(()), cursor.insert_after("bc", 3));
+        assert_eq!(Ok(()), cursor.insert_before("", 0));
+        assert_eq!(Err(tst::tst_map::UnorderedKeyError), cursor.insert_before("", 1));
+        cursor.move_prev();
+        assert_eq!(Some(("", &0)), cursor.current());
+        assert_eq!(Ok(()), cursor.insert_after("a", 1));
+        assert_eq!(Err(tst::tst_map::UnorderedKeyError), cursor.insert_after("c", 4));
+        *cursor.value_mut().unwrap() = 10;
+    }
+    assert_eq!(4, m.len());
+    assert_eq!(vec![("".to_string(), &10), ("a".to_string(), &1), ("b".to_string(), &2), ("bc".to_string(), &3)],
+        m.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn cursor_mut_walks_large_map() {
+    let mut m = TSTMap::new();
+    for (i, key) in common::keys(11, 20000).iter().enumerate() {
+        m.insert(key, i);
+    }
+    let keys: Vec<String> = m.keys().collect();
+    let mut expected = m.clone();
+
+    let mut visits = vec![];
+    let mut cursor = m.cursor_mut();
+    while let Some(key) = cursor.key() {
+        visits.push(key.to_string());
+        cursor.move_next();
+    }
+    assert_eq!(keys, visits);
+    visits.clear();
+    cursor.move_prev();
+    while let Some(key) = cursor.key() {
+        visits.push(key.to_string());
+        cursor.move_prev();
+    }
+    visits.reverse();
+    assert_eq!(keys, visits);
+
+    // removes every third entry and puts a new one right after every other,
+    // `expected` gets the same through `remove` and `insert`
+    let mut i = 0;
+    cursor.move_next();
+    while let Some((key, _)) = cursor.current() {
+        let key = key.to_string();
+        if i % 3 == 0 {
+            assert_eq!(expected.remove(&key).map(|v| (key, v)), cursor.remove_current());
+        } else {
+            let next = format!("{}\0", key);
+            if keys.binary_search(&next).is_err() {
+                assert_eq!(Ok(()), cursor.insert_after(&next, 0));
+                assert_eq!(Some(key.as_str()), cursor.key());
+                expected.insert(&next, 0);
+                cursor.move_next();
+            }
+            cursor.move_next();
+        }
+        i += 1;
+    }
+    drop(cursor);
+    assert!(expected.iter().eq(m.iter()));
+    // nodes are pruned the same way
+    assert_eq!(expected.stats().nodes, m.stats().nodes);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn cursor_mut_big_key_not_overflow_stack() {
+    let key: String = (0..1_000_000).map(|_| 'a').collect();
+    let mut m = TSTMap::new();
+    m.insert(&key, 1);
+    m.insert("b", 2);
+    let mut cursor = m.cursor_mut();
+    assert_eq!(Some(key.as_str()), cursor.key());
+    cursor.move_next();
+    assert_eq!(Some("b"), cursor.key());
+    cursor.move_prev();
+    assert_eq!(Some(1), cursor.remove_current().map(|(_, v)| v));
+    drop(cursor);
+    assert_eq!(1, m.len());
+}
+
+#[test]
+fn for_each_same_as_iter() {
+    let mut m = prepare_data();
+    m.insert("", 0);
+    m.insert("BZ", 14);
+    m.insert("ЖЖ", 15);
+
+    let mut entries = vec![];
+    m.for_each(|k, v| entries.push((k.to_string(), v)));
+    assert_eq!(m.iter().collect::<Vec<_>>(), entries);
+
+    for pref in ["", "B", "BY", "BYP", "BYPA", "BYTE", "BYTES", "Ж", "X"] {
+        let mut entries = vec![];
+        m.prefix_for_each(pref, |k, v| entries.push((k.to_string(), v)));
+        assert_eq!(m.prefix_iter(pref).collect::<Vec<_>>(), entries);
+    }
+
+    for pat in ["", ".", "..", "B.", "BY.....", "BY.A..", "......", "Ж.", "X"] {
+        let mut entries = vec![];
+        m.wildcard_for_each(pat, |k, v| entries.push((k.to_string(), v)));
+        assert_eq!(m.wildcard_iter(pat).collect::<Vec<_>>(), entries, "pattern {:?}", pat);
+    }
+}
+
+#[test]
+fn try_for_each_stops_early() {
+    let m = prepare_data();
+    let mut seen = 0;
+    let res = m.try_for_each(|k, _| {
+        seen += 1;
+        if k == "BYLAW" { ControlFlow::Break(k.len()) } else { ControlFlow::Continue(()) }
+    });
+    assert_eq!(ControlFlow::Break(5), res);
+    assert_eq!(4, seen);
+
+    assert_eq!(ControlFlow::Continue(()), m.try_prefix_for_each("BYP", |_, v| {
+        if *v > 8 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    }));
+    assert_eq!(ControlFlow::Break("BYWAY".to_string()), m.try_wildcard_for_each("BY.A.", |k, _| {
+        if k.ends_with('Y') { ControlFlow::Break(k.to_string()) } else { ControlFlow::Continue(()) }
+    }));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn for_each_big_key_not_overflow_stack() {
+    let key: String = (0..1_000_000).map(|_| 'a').collect();
+    let mut m = TSTMap::new();
+    m.insert(&key, 1);
+    m.insert("b", 2);
+    let mut lengths = vec![];
+    m.for_each(|k, _| lengths.push(k.len()));
+    assert_eq!(vec![1_000_000, 1], lengths);
+    let mut count = 0;
+    m.wildcard_for_each(&key.replace('a', "."), |_, _| count += 1);
+    assert_eq!(1, count);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn clone_eq_format_big_key_not_overflow_stack() {
+    // a genome fragment of a million bases
+    let key: String = "GATTACA".chars().cycle().take(1_000_000).collect();
+    let mut m = TSTMap::new();
+    m.insert(&key, 1);
+    m.insert(&key[..10], 2);
+
+    let mut copy = m.clone();
+    assert_eq!(m, copy);
+    copy.insert(&key, 3);
+    assert_ne!(m, copy);
+    assert_eq!(Some(&3), copy.get(&key));
+    assert_eq!(Some(&1), m.get(&key));
+
+    assert_eq!(vec![10, 1_000_000], m.keys().map(|k| k.len()).collect::<Vec<_>>());
+    let text = format!("{:?}", m);
+    assert!(text.starts_with("{\"GATTACAGAT\": 2, \"GATTACAGATT"));
+    assert_eq!(1_000_000 + 24, text.len());
+}
+
+#[test]
+fn hash_and_ord_by_content() {
+    use std::collections::{BTreeSet, HashSet};
+
+    let m1 = tstmap!{"abcdef" => 100, "xxx" => 2, "x" => 1};
+    let m2 = tstmap!{"x" => 1, "xxx" => 2, "abcdef" => 100};
+    let m3 = tstmap!{"x" => 1, "xxx" => 3, "abcdef" => 100};
+    let m4 = tstmap!{"abcdef" => 100, "x" => 1};
+
+    let hashed: HashSet<TSTMap<i32>> = vec![m1.clone(), m2.clone(), m3.clone()].into_iter().collect();
+    assert_eq!(2, hashed.len());
+    assert!(hashed.contains(&m2));
+
+    // compared as sorted (key, value) sequences
+    assert_eq!(std::cmp::Ordering::Equal, m1.cmp(&m2));
+    assert!(m1 < m3);
+    assert!(m4 < m1);
+    assert!(TSTMap::new() < m4);
+    let sorted: Vec<TSTMap<i32>> = vec![m3.clone(), m4.clone(), m1.clone()].into_iter().collect::<BTreeSet<_>>().into_iter().collect();
+    assert_eq!(vec![m4, m1, m3], sorted);
+
+    let f1 = tstmap!{"a" => 1.0, "b" => f64::NAN};
+    assert!(f1 != f1.clone());
+    assert_eq!(None, f1.partial_cmp(&f1.clone()));
+}
@@ -0,0 +1,171 @@
+extern crate tst;
+
+#[macro_use]
+mod common;
+
+use self::tst::{TSTMap, MappedTSTMap, SnapshotError, ValueCodec};
+use std::iter::FromIterator;
+
+fn prepare_data() -> TSTMap<i32> {
+    TSTMap::from_iter(vec![
+        ("BY", 1),
+        ("BYGONE", 3),
+        ("BYE", 2),
+        ("BYLAW", 4),
+        ("BYLINE", 5),
+        ("BYPASS", 6),
+        ("BYPATH", 7),
+        ("BYPRODUCT", 8),
+        ("BYROAD", 9),
+        ("BYSTANDER", 10),
+        ("BYTE", 11),
+        ("BYWAY", 12),
+        ("BYWORD", 13),
+    ])
+}
+
+fn mapped<V: ValueCodec>(m: &TSTMap<V>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    m.write_mapped(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn get() {
+    let bytes = mapped(&prepare_data());
+    let m = MappedTSTMap::new(&bytes[..]).unwrap();
+
+    assert_eq!(13, m.len());
+    assert_eq!(Some(1), m.get("BY").and_then(i32::decode));
+    assert_eq!(Some(8), m.get("BYPRODUCT").and_then(i32::decode));
+    assert_eq!(None, m.get("B"));
+    assert_eq!(None, m.get("BYPA"));
+    assert_eq!(None, m.get(""));
+    assert!(m.contains_key("BYTE"));
+    assert!(!m.contains_key("BYTES"));
+}
+
+#[test]
+fn empty() {
+    let bytes = mapped(&TSTMap::<i32>::new());
+    let m = MappedTSTMap::new(bytes).unwrap();
+
+    assert!(m.is_empty());
+    assert_eq!(None, m.get("a"));
+    assert_eq!(0, m.iter().count());
+    assert_eq!(0, m.wildcard_iter(".").count());
+    assert_eq!("", m.longest_prefix("a"));
+}
+
+#[test]
+fn empty_key() {
+    let mut map = prepare_data();
+    map.insert("", 0);
+    let bytes = mapped(&map);
+    let m = MappedTSTMap::new(&bytes).unwrap();
+
+    assert_eq!(Some(0), m.get("").and_then(i32::decode));
+    assert_eq!(Some("".to_string()), m.keys().next());
+    assert_eq!(1, m.wildcard_iter("").count());
+}
+
+#[test]
+fn iterators() {
+    let bytes = mapped(&prepare_data());
+    let m = MappedTSTMap::new(&bytes[..]).unwrap();
+
+    let keys: Vec<String> = m.keys().collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(sorted, keys);
+
+    let pref: Vec<String> = m.prefix_iter("BYP").map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYPASS", "BYPATH", "BYPRODUCT"], pref);
+    assert_eq!(0, m.prefix_iter("BYX").count());
+
+    let wild: Vec<String> = m.wildcard_iter("BY.A..").map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYPASS", "BYPATH"], wild);
+
+    let sum: i32 = (&m).into_iter().map(|(_, v)| i32::decode(v).unwrap()).sum();
+    assert_eq!(91, sum);
+    assert_eq!("BYLAW", m.longest_prefix("BYLAWS"));
+    assert_eq!("BY", m.longest_prefix("BYL"));
+}
+
+#[test]
+fn same_as_tstmap() {
+    let words = common::keys(11, 2000);
+    let mut map = TSTMap::new();
+    for (i, word) in words.iter().enumerate() {
+        map.insert(word, format!("value {}", i));
+    }
+    let bytes = mapped(&map);
+    let m = MappedTSTMap::new(bytes).unwrap();
+
+    assert_same_as_tstmap!(map, m, &words, |v: &[u8]| String::from_utf8(v.to_vec()).unwrap());
+}
+
+#[test]
+fn bad_header() {
+    let mut bytes = mapped(&prepare_data());
+
+    assert!(matches!(MappedTSTMap::new(&bytes[..10]), Err(SnapshotError::Truncated)));
+    assert!(matches!(MappedTSTMap::new(&bytes[..bytes.len() - 1]), Err(SnapshotError::Corrupted(_))));
+    bytes[4] = 9;
+    assert!(matches!(MappedTSTMap::new(&bytes[..]), Err(SnapshotError::UnsupportedVersion(9))));
+    bytes[0] = 0;
+    assert!(matches!(MappedTSTMap::new(&bytes[..]), Err(SnapshotError::BadMagic)));
+}
+
+#[test]
+// thousands of broken copies take ages under Miri
+#[cfg_attr(miri, ignore)]
+fn corrupted_bytes_dont_panic() {
+    let bytes = mapped(&prepare_data());
+
+    for i in 8..bytes.len() {
+        for bit in [0x01, 0x10, 0x80] {
+            let mut broken = bytes.clone();
+            broken[i] ^= bit;
+            if let Ok(m) = MappedTSTMap::new(&broken[..]) {
+                let _ = m.get("BYPATH");
+                let _ = m.longest_prefix("BYWORDS");
+                let _ = m.iter().count();
+                let _ = m.prefix_iter("BYP").count();
+                let _ = m.wildcard_iter("BY...").count();
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let mut map = TSTMap::new();
+    let mut key = String::new();
+
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    map.insert(&key, 666);
+    let bytes = mapped(&map);
+    let m = MappedTSTMap::new(bytes).unwrap();
+
+    assert_eq!(Some(666), m.get(&key).and_then(i32::decode));
+    assert_eq!(&key[..], m.longest_prefix(&key));
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+// Miri can't map files
+#[cfg_attr(miri, ignore)]
+fn open_file() {
+    let path = std::env::temp_dir().join(format!("tst-mapped-{}.tstd", std::process::id()));
+    std::fs::write(&path, mapped(&prepare_data())).unwrap();
+
+    let m = unsafe { MappedTSTMap::open(&path) }.unwrap();
+    assert_eq!(13, m.len());
+    assert_eq!(Some(13), m.get("BYWORD").and_then(i32::decode));
+    drop(m);
+    std::fs::remove_file(&path).unwrap();
+}
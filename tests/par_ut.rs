@@ -0,0 +1,72 @@
+#![cfg(feature = "rayon")]
+
+extern crate tst;
+extern crate rayon;
+
+#[macro_use]
+mod common;
+
+use rayon::prelude::*;
+use self::tst::TSTMap;
+
+fn keys() -> Vec<String> {
+    common::keys(44, 5000)
+}
+
+fn build() -> TSTMap<usize> {
+    let keys = keys();
+    keys.iter().enumerate().map(|(i, k)| (k.as_str(), i)).collect()
+}
+
+#[test]
+fn par_iter_same_as_iter() {
+    let m = build();
+    let expected: Vec<(String, usize)> = m.iter().map(|(k, v)| (k, *v)).collect();
+    let entries: Vec<(String, usize)> = m.par_iter().map(|(k, v)| (k, *v)).collect();
+    assert_eq!(expected, entries);
+    assert_eq!(m.len(), m.par_iter().count());
+}
+
+#[test]
+fn par_prefix_iter_same_as_prefix_iter() {
+    let m = build();
+    for pref in ["", "a", "ab", "ж€", "\0", "abcabc", "x"] {
+        let expected: Vec<(String, usize)> = m.prefix_iter(pref).map(|(k, v)| (k, *v)).collect();
+        let entries: Vec<(String, usize)> = m.par_prefix_iter(pref).map(|(k, v)| (k, *v)).collect();
+        assert_eq!(expected, entries);
+    }
+}
+
+#[test]
+fn par_iter_mut_updates_every_value() {
+    let mut m = build();
+    m.par_iter_mut().for_each(|(key, value)| *value = key.chars().count());
+    for (key, value) in m.iter() {
+        assert_eq!(key.chars().count(), *value);
+    }
+    assert!(m.par_iter_mut().find_any(|(key, _)| key == "zz").is_none());
+}
+
+#[test]
+fn par_extend_same_as_extend() {
+    let keys = keys();
+    let mut expected: TSTMap<usize> = keys.iter().take(100).enumerate().map(|(i, k)| (k.as_str(), i)).collect();
+    let mut m = expected.clone();
+
+    let entries: Vec<(&str, usize)> = keys.iter().enumerate().map(|(i, k)| (k.as_str(), i + 1)).collect();
+    expected.extend(entries.iter().cloned());
+    m.par_extend(entries.par_iter().cloned());
+
+    assert_eq!(expected.len(), m.len());
+    assert!(expected.iter().eq(m.iter()));
+}
+
+#[test]
+fn par_extend_empty_key() {
+    let mut m = TSTMap::new();
+    m.par_extend(vec![("", 1), ("a", 2), ("", 3)]);
+    assert_eq!(2, m.len());
+    assert_eq!(Some(&3), m.get(""));
+    let keys: Vec<String> = m.par_iter().map(|(k, _)| k).collect();
+    assert_eq!(vec!["", "a"], keys);
+}
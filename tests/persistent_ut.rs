@@ -0,0 +1,111 @@
+extern crate tst;
+
+#[macro_use]
+mod common;
+
+use std::collections::BTreeMap;
+use self::tst::{PersistentTSTMap, TSTMap};
+
+fn keys() -> Vec<String> {
+    common::keys(42, 3000)
+}
+
+#[test]
+fn same_as_tstmap() {
+    let keys = keys();
+    let mut m = PersistentTSTMap::new();
+    let mut expected = TSTMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        if i % 5 == 4 {
+            m = m.remove(key);
+            expected.remove(key);
+        } else {
+            m = m.insert(key, i);
+            expected.insert(key, i);
+        }
+        assert_eq!(expected.len(), m.len());
+    }
+    assert_same_as_tstmap!(expected, m, &keys);
+}
+
+#[test]
+fn old_versions_stay_unchanged() {
+    // every version is walked, which is quadratic
+    let n = if cfg!(miri) { 50 } else { 500 };
+    let mut versions = vec![PersistentTSTMap::new()];
+    for (i, key) in keys().iter().take(n).enumerate() {
+        let last = versions.last().unwrap();
+        versions.push(if i % 3 == 2 { last.remove(key) } else { last.insert(key, i) });
+    }
+
+    let mut expected = BTreeMap::new();
+    for (i, key) in keys().iter().take(n).enumerate() {
+        assert!(versions[i].iter().map(|(k, v)| (k, *v)).eq(expected.iter().map(|(k, v): (&String, &usize)| (k.clone(), *v))));
+        if i % 3 == 2 {
+            expected.remove(key);
+        } else {
+            expected.insert(key.clone(), i);
+        }
+    }
+}
+
+#[test]
+fn clone_is_snapshot() {
+    let m: PersistentTSTMap<i32> = vec![("a", 1), ("ab", 2), ("", 0)].into_iter().collect();
+    let snapshot = m.clone();
+    let m = m.insert("", 10).remove("ab").insert("b", 3);
+
+    assert_eq!(3, snapshot.len());
+    assert_eq!(Some(&0), snapshot.get(""));
+    assert_eq!(Some(&2), snapshot.get("ab"));
+    assert_eq!(None, snapshot.get("b"));
+    assert_eq!(3, m.len());
+    assert_eq!(m[""], 10);
+    assert_eq!("{\"\": 10, \"a\": 1, \"b\": 3}", format!("{:?}", m));
+}
+
+#[test]
+fn remove_missing_key() {
+    let m = PersistentTSTMap::new().insert("abc", 1);
+    for key in ["", "a", "ab", "abcd", "b"] {
+        let n = m.remove(key);
+        assert_eq!(1, n.len());
+        assert_eq!(Some(&1), n.get("abc"));
+    }
+}
+
+#[test]
+fn longest_prefix() {
+    let m = PersistentTSTMap::new().insert("abc", 1).insert("abcd", 2).insert("abce", 3).insert("abcef", 4);
+    assert_eq!("", m.longest_prefix("ab"));
+    assert_eq!("abc", m.longest_prefix("abcx"));
+    assert_eq!("abce", m.longest_prefix("abcez"));
+    assert_eq!("abcef", m.longest_prefix("abcefg"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let key: String = (0..1_000_000).map(|_| 'a').collect();
+    let m = PersistentTSTMap::new().insert(&key, 1);
+    let n = m.insert("b", 2);
+    assert_eq!(Some(&1), n.get(&key));
+    assert_eq!(key, n.longest_prefix(&key));
+    let n = n.remove(&key);
+    assert_eq!(1, n.len());
+    assert_eq!(Some(&1), m.get(&key));
+    drop(m);
+}
+
+#[test]
+fn versions_across_threads() {
+    let m: PersistentTSTMap<i32> = vec![("a", 1), ("b", 2)].into_iter().collect();
+    let handles: Vec<_> = (0..4).map(|i| {
+        let m = m.clone();
+        std::thread::spawn(move || m.insert("c", i).len())
+    }).collect();
+    for handle in handles {
+        assert_eq!(3, handle.join().unwrap());
+    }
+    assert_eq!(2, m.len());
+}
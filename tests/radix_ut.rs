@@ -0,0 +1,227 @@
+extern crate tst;
+
+#[macro_use]
+mod common;
+
+use self::tst::{RadixTSTMap, TSTMap};
+use self::tst::tst_map::Entry::*;
+use std::iter::FromIterator;
+
+fn prepare_data() -> RadixTSTMap<i32> {
+    RadixTSTMap::from_iter(vec![
+        ("BY", 1),
+        ("BYGONE", 3),
+        ("BYE", 2),
+        ("BYLAW", 4),
+        ("BYLINE", 5),
+        ("BYPASS", 6),
+        ("BYPATH", 7),
+        ("BYPRODUCT", 8),
+        ("BYROAD", 9),
+        ("BYSTANDER", 10),
+        ("BYTE", 11),
+        ("BYWAY", 12),
+        ("BYWORD", 13),
+    ])
+}
+
+#[test]
+fn create_root() {
+    let m = RadixTSTMap::<i32>::new();
+    assert_eq!(0, m.len());
+    assert!(m.is_empty());
+}
+
+#[test]
+fn insert_get() {
+    let mut m = RadixTSTMap::new();
+
+    assert_eq!(None, m.insert("abcdef", 13));
+    assert_eq!(Some(13), m.insert("abcdef", 14));
+    assert_eq!(None, m.insert("abcxyz", 15));
+    assert_eq!(None, m.insert("abc", 16));
+    assert_eq!(3, m.len());
+    assert_eq!(Some(&14), m.get("abcdef"));
+    assert_eq!(Some(&15), m.get("abcxyz"));
+    assert_eq!(Some(&16), m.get("abc"));
+    assert_eq!(None, m.get("ab"));
+    assert_eq!(None, m.get("abcd"));
+    assert_eq!(None, m.get("abcdefg"));
+    assert_eq!(None, m.get(""));
+}
+
+#[test]
+fn entry() {
+    let mut m = RadixTSTMap::new();
+
+    match m.entry("abcdg") {
+        Vacant(entry) => {
+            assert_eq!(100, *entry.insert(100));
+        },
+        Occupied(_) => unreachable!(),
+    }
+    match m.entry("abcdg") {
+        Vacant(_) => unreachable!(),
+        Occupied(entry) => {
+            assert_eq!(100, entry.remove());
+        }
+    }
+    assert_eq!(None, m.get("abcdg"));
+    assert_eq!(0, m.len());
+}
+
+#[test]
+fn remove_rich() {
+    let mut m = prepare_data();
+
+    assert_eq!(Some(1), m.remove("BY"));
+    assert_eq!(Some(12), m.remove("BYWAY"));
+    assert_eq!(Some(10), m.remove("BYSTANDER"));
+    assert_eq!(Some(8), m.remove("BYPRODUCT"));
+    assert_eq!(None, m.remove("BYPRODUCT"));
+    assert_eq!(None, m.remove("BYPA"));
+    assert_eq!(Some(2), m.remove("BYE"));
+    assert_eq!(8, m.len());
+    assert_eq!(Some(&13), m.get("BYWORD"));
+    assert_eq!(Some(&7), m.get("BYPATH"));
+    assert_eq!(Some(&6), m.get("BYPASS"));
+}
+
+#[test]
+fn remove_and_insert_back() {
+    let mut m = RadixTSTMap::new();
+    m.insert("romane", 1);
+    m.insert("romanus", 2);
+    m.insert("romulus", 3);
+    m.insert("rubens", 4);
+
+    assert_eq!(Some(2), m.remove("romanus"));
+    assert_eq!(Some(3), m.remove("romulus"));
+    assert_eq!(Some(&1), m.get("romane"));
+    assert_eq!(None, m.insert("romanus", 5));
+    assert_eq!(None, m.insert("roman", 6));
+    assert_eq!(vec!["roman", "romane", "romanus", "rubens"], m.keys().collect::<Vec<_>>());
+}
+
+#[test]
+fn empty_key() {
+    let mut m = prepare_data();
+
+    assert_eq!(None, m.insert("", 0));
+    assert_eq!(Some(&0), m.get(""));
+    assert_eq!(Some(("".to_string(), &0)), m.iter().next());
+    assert_eq!(Some(0), m.remove(""));
+    assert_eq!(13, m.len());
+}
+
+#[test]
+fn longest_prefix() {
+    let m = prepare_data();
+
+    assert_eq!("BYLAW", m.longest_prefix("BYLAWS"));
+    assert_eq!("BY", m.longest_prefix("BYL"));
+    assert_eq!("BY", m.longest_prefix("BYLAX"));
+    assert_eq!("", m.longest_prefix("B"));
+}
+
+#[test]
+fn iterators() {
+    let mut m = prepare_data();
+
+    let keys: Vec<String> = m.keys().collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(sorted, keys);
+    assert_eq!((13, Some(13)), m.iter().size_hint());
+
+    let pref: Vec<String> = m.prefix_iter("BYP").map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYPASS", "BYPATH", "BYPRODUCT"], pref);
+    // prefix ends in the middle of a run
+    let pref: Vec<String> = m.prefix_iter("BYPRO").map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYPRODUCT"], pref);
+    assert_eq!(0, m.prefix_iter("BYPROX").count());
+
+    let wild: Vec<String> = m.wildcard_iter("BY.A..").map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYPASS", "BYPATH"], wild);
+    assert_eq!(0, m.wildcard_iter("BYPRODUC").count());
+
+    for (_, v) in m.iter_mut() {
+        *v += 1;
+    }
+    assert_eq!(13, m["BYWAY"]);
+    assert_eq!(2, m["BY"]);
+
+    let sum: i32 = m.values().sum();
+    let all: Vec<(String, i32)> = m.into_iter().collect();
+    assert_eq!(13, all.len());
+    assert_eq!(sum, all.iter().map(|&(_, v)| v).sum());
+}
+
+#[test]
+fn same_as_tstmap() {
+    let words = common::keys(42, 2000);
+    let mut radix = RadixTSTMap::new();
+    let mut map = TSTMap::new();
+    for (i, w) in words.iter().enumerate() {
+        assert_eq!(map.insert(w, i), radix.insert(w, i));
+    }
+    for w in words.iter().step_by(3) {
+        assert_eq!(map.remove(w), radix.remove(w));
+    }
+    assert_same_as_tstmap!(map, radix, &words);
+    for w in words.iter() {
+        assert_eq!(map.remove(w), radix.remove(w));
+    }
+    assert!(radix.is_empty());
+    assert_eq!(0, radix.iter().count());
+}
+
+#[test]
+fn clear() {
+    let mut m = prepare_data();
+    m.clear();
+    assert!(m.is_empty());
+    assert_eq!(None, m.get("BY"));
+    assert_eq!(0, m.iter().count());
+    m.insert("BY", 1);
+    assert_eq!(1, m["BY"]);
+}
+
+#[test]
+fn format() {
+    let m = RadixTSTMap::from_iter(vec![("abc", 2), ("abd", 1)]);
+
+    assert_eq!("{\"abc\": 2, \"abd\": 1}", format!("{:?}", m));
+}
+
+#[test]
+fn unicode() {
+    let mut m = RadixTSTMap::new();
+    m.insert("привет", 1);
+    m.insert("приём", 2);
+    m.insert("при", 3);
+
+    assert_eq!(Some(&2), m.get("приём"));
+    assert_eq!("при", m.longest_prefix("прийти"));
+    assert_eq!(vec!["привет"], m.wildcard_iter("при..т").map(|(k, _)| k).collect::<Vec<_>>());
+    assert_eq!(vec!["приём"], m.wildcard_iter("при.м").map(|(k, _)| k).collect::<Vec<_>>());
+    assert_eq!(Some(3), m.remove("при"));
+    assert_eq!(2, m.prefix_iter("пр").count());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let mut m = RadixTSTMap::new();
+    let mut key = String::new();
+
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    m.insert(&key, 666);
+    let cpy = m.clone();
+
+    assert_eq!(Some(&666), cpy.get(&key));
+    assert_eq!(Some(666), m.remove(&key));
+    assert_eq!(None, m.get(&key));
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "serde")]
+
+extern crate tst;
+extern crate serde_json;
+extern crate bincode;
+
+use self::tst::{TSTMap, TSTSet};
+use std::iter::FromIterator;
+
+fn prepare_data() -> TSTMap<i32> {
+    TSTMap::from_iter(vec![
+        ("BY", 1),
+        ("BYGONE", 3),
+        ("BYE", 2),
+        ("BYLAW", 4),
+        ("", 0),
+        ("bypass", 6),
+        ("привет", 7),
+    ])
+}
+
+#[test]
+fn map_json() {
+    let m = prepare_data();
+
+    let json = serde_json::to_string(&m).unwrap();
+    assert_eq!(r#"{"":0,"BY":1,"BYE":2,"BYGONE":3,"BYLAW":4,"bypass":6,"привет":7}"#, json);
+    let back: TSTMap<i32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(m.len(), back.len());
+    assert!(m.iter().eq(back.iter()));
+}
+
+#[test]
+fn map_json_unordered_input() {
+    let m: TSTMap<i32> = serde_json::from_str(r#"{"b":2,"a":1,"c":3,"a":10}"#).unwrap();
+
+    assert_eq!(3, m.len());
+    assert_eq!(vec![("a".to_string(), &10), ("b".to_string(), &2), ("c".to_string(), &3)],
+               m.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn map_bincode() {
+    let m = prepare_data();
+
+    let bytes = bincode::serialize(&m).unwrap();
+    let back: TSTMap<i32> = bincode::deserialize(&bytes).unwrap();
+    assert!(m.iter().eq(back.iter()));
+}
+
+#[test]
+fn set_json() {
+    let s = TSTSet::from_iter(vec!["xyz", "abc", "abd", ""]);
+
+    let json = serde_json::to_string(&s).unwrap();
+    assert_eq!(r#"["","abc","abd","xyz"]"#, json);
+    let back: TSTSet = serde_json::from_str(&json).unwrap();
+    assert!(s.iter().eq(back.iter()));
+
+    let dup: TSTSet = serde_json::from_str(r#"["b","a","b"]"#).unwrap();
+    assert_eq!(2, dup.len());
+}
+
+#[test]
+fn set_bincode() {
+    let s = TSTSet::from_iter((0..1000).map(|i| format!("{:05}", i)).collect::<Vec<_>>().iter().map(|k| k.as_str()));
+
+    let bytes = bincode::serialize(&s).unwrap();
+    let back: TSTSet = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(1000, back.len());
+    assert!(s.iter().eq(back.iter()));
+}
+
+#[test]
+fn wrong_input() {
+    assert!(serde_json::from_str::<TSTMap<i32>>(r#"["a"]"#).is_err());
+    assert!(serde_json::from_str::<TSTMap<i32>>(r#"{"a":"b"}"#).is_err());
+    assert!(serde_json::from_str::<TSTSet>(r#"{"a":1}"#).is_err());
+}
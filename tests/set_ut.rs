@@ -1,3 +1,5 @@
+#![allow(clippy::bool_assert_comparison)]
+
 #[macro_use] extern crate tst;
 
 use self::tst::TSTSet;
@@ -250,3 +252,69 @@ fn prefix_iterator() {
 
     assert_eq!("firstfirstsecondfirstthird",  set.prefix_iter("fir").collect::<String>());
 }
+
+#[test]
+fn empty_key() {
+    let mut s = tstset!["b", "a"];
+
+    assert!(!s.contains(""));
+    assert!(s.insert(""));
+    assert!(!s.insert(""));
+    assert!(s.contains(""));
+    assert_eq!(3, s.len());
+    assert_eq!(vec!["", "a", "b"], s.iter().collect::<Vec<String>>());
+    assert_eq!(vec!["", "a", "b"], s.prefix_iter("").collect::<Vec<String>>());
+    assert!(s.remove(""));
+    assert!(!s.contains(""));
+}
+
+#[test]
+fn contains_and_prefix_by_chars() {
+    let s = prepare_data();
+
+    assert!(s.contains_chars(['B', 'Y', 'E']));
+    assert!(!s.contains_chars(['B']));
+    assert_eq!(vec!["BYWAY", "BYWORD"], s.prefix_iter_chars("BYW".chars()).collect::<Vec<String>>());
+}
+
+#[test]
+fn from_sorted_iter() {
+    let s = TSTSet::from_sorted_iter(vec!["", "a", "abc", "abc", "b"]);
+
+    assert_eq!(4, s.len());
+    assert_eq!(vec!["", "a", "abc", "b"], s.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn from_sorted_iter_unsorted() {
+    let s = TSTSet::from_sorted_iter(vec!["b", "abc", "", "abc", "a"]);
+
+    assert_eq!(4, s.len());
+    assert_eq!(vec!["", "a", "abc", "b"], s.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn stats() {
+    let s = tstset! { "abc", "abd", "x" };
+    let stats = s.stats();
+
+    assert_eq!(5, stats.nodes);
+    assert_eq!(3, stats.values);
+    assert_eq!(vec![0, 1, 0, 2], stats.key_lengths);
+}
+
+#[test]
+fn hash_and_ord_by_content() {
+    use std::collections::HashSet;
+
+    let s1 = tstset!{"b", "a", "ab"};
+    let s2 = tstset!{"ab", "a", "b"};
+    let s3 = tstset!{"a", "b"};
+
+    assert_eq!(s1, s2);
+    let hashed: HashSet<TSTSet> = vec![s1.clone(), s2.clone(), s3.clone()].into_iter().collect();
+    assert_eq!(2, hashed.len());
+    // "ab" goes before "b"
+    assert!(s1 < s3);
+    assert!(tstset!{} < s3);
+}
@@ -0,0 +1,159 @@
+extern crate tst;
+
+use self::tst::{TSTMap, SnapshotError};
+use std::io::{self, Write};
+use std::iter::FromIterator;
+
+fn prepare_data() -> TSTMap<String> {
+    TSTMap::from_iter(vec![
+        ("BY", "1".to_string()),
+        ("BYGONE", "3".to_string()),
+        ("BYE", "2".to_string()),
+        ("BYLAW", "4".to_string()),
+        ("", "empty".to_string()),
+        ("bypass", "".to_string()),
+        ("привет", "мир".to_string()),
+    ])
+}
+
+fn snapshot<V: tst::ValueCodec>(m: &TSTMap<V>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    m.write_snapshot(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn round_trip() {
+    let m = prepare_data();
+    let copy = TSTMap::<String>::read_snapshot(&snapshot(&m)[..]).unwrap();
+
+    assert_eq!(m.len(), copy.len());
+    assert!(m.iter().eq(copy.iter()));
+    assert_eq!(Some(&"empty".to_string()), copy.get(""));
+    // node structure is kept as is
+    assert!(m == copy);
+}
+
+#[test]
+fn round_trip_empty() {
+    let m: TSTMap<u64> = TSTMap::new();
+    let buf = snapshot(&m);
+
+    // header, root node and checksum
+    assert_eq!(4 + 2 + 8 + 5 + 4, buf.len());
+    let copy = TSTMap::<u64>::read_snapshot(&buf[..]).unwrap();
+    assert!(copy.is_empty());
+}
+
+#[test]
+fn round_trip_numbers() {
+    let mut m = TSTMap::new();
+    for i in 0..1000u32 {
+        m.insert(&format!("{}", i * 7919 % 1000), i as f64 / 3.0);
+    }
+    let copy = TSTMap::<f64>::read_snapshot(&snapshot(&m)[..]).unwrap();
+
+    assert!(m.iter().eq(copy.iter()));
+}
+
+#[test]
+fn bad_magic() {
+    let mut buf = snapshot(&prepare_data());
+    buf[0] = b'X';
+
+    assert!(matches!(TSTMap::<String>::read_snapshot(&buf[..]), Err(SnapshotError::BadMagic)));
+}
+
+#[test]
+fn unsupported_version() {
+    let mut buf = snapshot(&prepare_data());
+    buf[4] = 2;
+
+    assert!(matches!(TSTMap::<String>::read_snapshot(&buf[..]), Err(SnapshotError::UnsupportedVersion(2))));
+}
+
+#[test]
+fn checksum_mismatch() {
+    let mut buf = snapshot(&prepare_data());
+    let last = buf.len() - 1;
+    buf[last] ^= 1;
+
+    assert!(matches!(TSTMap::<String>::read_snapshot(&buf[..]), Err(SnapshotError::ChecksumMismatch { .. })));
+}
+
+#[test]
+fn corrupted_bytes() {
+    let buf = snapshot(&prepare_data());
+
+    for i in 14..buf.len() - 4 {
+        let mut broken = buf.clone();
+        broken[i] ^= 0x40;
+        assert!(TSTMap::<String>::read_snapshot(&broken[..]).is_err(), "byte {}", i);
+    }
+}
+
+#[test]
+fn truncated() {
+    let buf = snapshot(&prepare_data());
+
+    for len in 0..buf.len() {
+        match TSTMap::<String>::read_snapshot(&buf[..len]) {
+            Err(SnapshotError::Truncated) => {}
+            other => panic!("length {}: {:?}", len, other.map(|m| m.len())),
+        }
+    }
+}
+
+#[test]
+fn trailing_data() {
+    let mut buf = snapshot(&prepare_data());
+    buf.push(0);
+
+    assert!(matches!(TSTMap::<String>::read_snapshot(&buf[..]), Err(SnapshotError::Corrupted(_))));
+}
+
+#[test]
+fn bad_value() {
+    let mut m = TSTMap::new();
+    m.insert("a", 5u8);
+    let buf = snapshot(&m);
+
+    assert!(matches!(TSTMap::<u16>::read_snapshot(&buf[..]), Err(SnapshotError::BadValue)));
+    assert_eq!("snapshot contains a value, which can't be decoded",
+               TSTMap::<bool>::read_snapshot(&buf[..]).unwrap_err().to_string());
+}
+
+struct FailingWriter;
+
+impl Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk is full"))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_error() {
+    let m = prepare_data();
+
+    assert!(matches!(m.write_snapshot(&mut FailingWriter), Err(SnapshotError::Io(_))));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let mut m = TSTMap::new();
+    let mut key = String::new();
+
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    m.insert(&key, 666);
+    let buf = snapshot(&m);
+    let copy = TSTMap::<i32>::read_snapshot(&buf[..]).unwrap();
+    assert_eq!(Some(&666), copy.get(&key));
+
+    assert!(TSTMap::<i32>::read_snapshot(&buf[..buf.len() / 2]).is_err());
+}
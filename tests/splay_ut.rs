@@ -0,0 +1,100 @@
+extern crate tst;
+
+#[macro_use]
+mod common;
+
+use self::tst::{SplayTSTMap, TSTMap};
+use self::tst::tst_map::Entry::*;
+
+fn keys() -> Vec<String> {
+    common::keys(29, 3000)
+}
+
+#[test]
+fn same_as_tstmap() {
+    let keys = keys();
+    let mut m = SplayTSTMap::new();
+    let mut expected = TSTMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        match i % 4 {
+            0 | 1 => assert_eq!(expected.insert(key, i), m.insert(key, i)),
+            2 => assert_eq!(expected.get(key), m.get(key)),
+            _ => assert_eq!(expected.remove(key), m.remove(key)),
+        }
+        // a skewed workload, the same key again and again
+        assert_eq!(expected.get(&keys[7]), m.get(&keys[7]));
+    }
+    assert_same_as_tstmap!(expected, m, &keys);
+
+    for key in expected.keys() {
+        assert!(m.contains_key(&key));
+        assert!(m.remove(&key).is_some());
+        assert!(!m.contains_key(&key));
+    }
+    assert!(m.is_empty());
+    assert_eq!(0, m.iter().count());
+}
+
+#[test]
+fn entry_and_get_mut() {
+    let mut m = SplayTSTMap::new();
+    for word in ["b", "a", "b", "c", "b"] {
+        *m.entry(word).or_insert(0) += 1;
+    }
+    assert_eq!(Some(&3), m.get("b"));
+    *m.get_mut("a").unwrap() = 10;
+    assert_eq!(Some(&10), m.as_map().get("a"));
+
+    match m.entry("c") {
+        Occupied(entry) => assert_eq!(1, entry.remove()),
+        Vacant(_) => panic!("c is in the map"),
+    }
+    assert!(!m.contains_key("c"));
+    assert_eq!(2, m.len());
+    assert_eq!("{\"a\": 10, \"b\": 3}", format!("{:?}", m));
+    assert_eq!("", m.longest_prefix("x"));
+}
+
+#[test]
+fn empty_key() {
+    let mut m = SplayTSTMap::new();
+    m.insert("", 1);
+    m.insert("a", 2);
+
+    assert_eq!(Some(&1), m.get(""));
+    assert_eq!(Some(1), m.remove(""));
+    assert_eq!(None, m.get(""));
+    assert_eq!(1, m.len());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn sorted_inserts_not_overflow_stack() {
+    let mut m = SplayTSTMap::new();
+    let chars: Vec<char> = (0x400..0x400 + 100_000).filter_map(char::from_u32).collect();
+    for c in chars.iter() {
+        m.insert(&c.to_string(), *c);
+    }
+    // the first char is at the bottom of a chain of all the others
+    assert_eq!(Some(&chars[0]), m.get(&chars[0].to_string()));
+    assert_eq!(Some(chars[1]), m.remove(&chars[1].to_string()));
+    assert_eq!(chars.len() - 1, m.len());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn big_key_not_overflow_stack() {
+    let mut key = String::new();
+    while key.len() < 1_000_000 {
+        key.push_str("qwertyuiopasdfghjkl;");
+    }
+    let mut m = SplayTSTMap::new();
+    m.insert(&key, 1);
+    m.insert(&key[..10], 2);
+
+    assert_eq!(Some(&1), m.get(&key));
+    assert_eq!(Some(1), m.remove(&key));
+    assert_eq!(&key[..10], m.longest_prefix(&key));
+    assert_eq!(Some(2), m.remove(&key[..10]));
+    assert!(m.is_empty());
+}